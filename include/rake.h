@@ -0,0 +1,120 @@
+/* The C API from src/ffi.rs, for building with `--features ffi`.
+ * Regenerate with `cbindgen --config cbindgen.toml --output include/rake.h`
+ * after changing it. */
+
+#ifndef RAKE_H
+#define RAKE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Set in what `rake_tick` returns when the snake moved.
+ */
+#define RAKE_MOVED 1
+
+/**
+ * Set in what `rake_tick` returns when the snake ate an apple.
+ */
+#define RAKE_ATE 2
+
+/**
+ * Set in what `rake_tick` returns when the snake died.
+ */
+#define RAKE_DIED 4
+
+/**
+ * Set in what `rake_tick` returns when the snake filled the board.
+ */
+#define RAKE_WON 8
+
+/**
+ * A game, only ever handled through a pointer.
+ */
+typedef struct RakeGame RakeGame;
+
+/**
+ * Everything about a game that isn't the board.
+ */
+typedef struct RakeStatus {
+  uint16_t width;
+  uint16_t height;
+  int16_t head_x;
+  int16_t head_y;
+  /**
+   * The way the snake goes next, 0 up, 1 down, 2 left or 3 right.
+   */
+  uint8_t direction;
+  uint32_t length;
+  uint16_t score;
+  /**
+   * How many times the snake has moved.
+   */
+  uint64_t ticks;
+  /**
+   * Whether the game has ended, the snake dead or the board full.
+   */
+  bool over;
+} RakeStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * A new game on a `width` by `height` board, border included, with
+ * apples coming from `seed`. Returns null for a board that's too small
+ * or too big to play on.
+ */
+RakeGame *rake_create(uint16_t width, uint16_t height, uint64_t seed);
+
+/**
+ * Moves the game on a tick, turning first if `direction` is 0 to 3.
+ * Anything else carries on straight. Returns `RAKE_MOVED`, `RAKE_ATE`,
+ * `RAKE_DIED` and `RAKE_WON` or'd together for whatever happened.
+ *
+ * # Safety
+ *
+ * `game` has to be from `rake_create` and not destroyed yet.
+ */
+uint8_t rake_tick(RakeGame *game, int32_t direction);
+
+/**
+ * Copies the board into `buffer` if `len` bytes is room enough, and
+ * returns how many bytes it takes: width times height. Passing a null
+ * `buffer` just asks how big it needs to be.
+ *
+ * # Safety
+ *
+ * `game` has to be from `rake_create` and not destroyed yet, and
+ * `buffer` either null or `len` bytes that can be written to.
+ */
+size_t rake_board(const RakeGame *game, uint8_t *buffer, size_t len);
+
+/**
+ * Where the snake is, the score and so on. A null `game` gives all
+ * zeroes.
+ *
+ * # Safety
+ *
+ * `game` has to be from `rake_create` and not destroyed yet.
+ */
+RakeStatus rake_status(const RakeGame *game);
+
+/**
+ * Frees a game. Null is fine and does nothing.
+ *
+ * # Safety
+ *
+ * `game` has to be from `rake_create`, and not used again afterwards.
+ */
+void rake_destroy(RakeGame *game);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RAKE_H */
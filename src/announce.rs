@@ -0,0 +1,140 @@
+//! The game as lines of text, for playing with a screen reader.
+//!
+//! Redrawing the board every tick gives a screen reader nothing it can
+//! read out. Instead `Announcer` says what matters in a sentence: where
+//! the head is, which way it's going, how to get to the nearest apple and
+//! the score. It speaks up whenever something changes and every few
+//! seconds otherwise, and each line stands on its own.
+
+use crate::game::{Direction, Game, GameEvent};
+use crate::lang::Lang;
+use rand::Rng;
+use std::sync::Arc;
+use std::time;
+
+// how long it stays quiet when nothing's happening
+const QUIET_SPELL: time::Duration = time::Duration::from_secs(3);
+
+pub struct Announcer {
+    lang: Arc<Lang>,
+    // ticks between announcements when nothing's happening
+    every: u64,
+    // the tick the next of those is due on
+    due: u64,
+}
+
+impl Announcer {
+    pub fn new(lang: Arc<Lang>, tick_rate: time::Duration) -> Announcer {
+        let every = (QUIET_SPELL.as_millis() / tick_rate.as_millis().max(1)).max(1) as u64;
+        Announcer {
+            lang,
+            every,
+            due: 0,
+        }
+    }
+
+    /// The first thing to say, before the game gets going.
+    pub fn start<R: Rng>(&mut self, game: &Game<R>) -> String {
+        self.due = game.ticks() + self.every;
+        describe(game, &self.lang)
+    }
+
+    /// What to say after the latest tick, if anything. Eating, turning,
+    /// bugs and the end of the game are announced straight away.
+    pub fn after_tick<R: Rng>(&mut self, game: &Game<R>) -> Option<String> {
+        let lang = &self.lang;
+        let score: [(&str, &dyn std::fmt::Display); 1] = [("score", &game.score())];
+        // what happened, ahead of where everything is now
+        let mut news = vec![];
+        for event in game.events() {
+            match event {
+                GameEvent::Won => return Some(lang.format("game-won", &score)),
+                GameEvent::Died { cause } => {
+                    let cause = lang.text(&format!("death-{}", cause.name())).to_string();
+                    return Some(lang.format(
+                        "announce-died",
+                        &[("cause", &cause), ("score", &game.score())],
+                    ));
+                }
+                GameEvent::AppleEaten { .. } => news.push(lang.text("announce-ate").to_string()),
+                GameEvent::BugSpawned { at: [x, y] } => {
+                    news.push(lang.format("announce-bug", &[("x", x), ("y", y)]))
+                }
+                GameEvent::ObstacleSpawned { at: [x, y] } => {
+                    news.push(lang.format("announce-obstacle", &[("x", x), ("y", y)]))
+                }
+                GameEvent::BugEaten { points, .. } => {
+                    news.push(lang.format("announce-bug-eaten", &[("points", points)]))
+                }
+                GameEvent::OutOfOrder { points, .. } => {
+                    news.push(lang.format("announce-out-of-order", &[("points", points)]))
+                }
+                GameEvent::Turned { .. } | GameEvent::Bounced { .. } => self.due = game.ticks(),
+                GameEvent::Cleared { stage } => {
+                    self.due = game.ticks() + self.every;
+                    let cleared = lang.format("announce-stage", &[("stage", stage)]);
+                    return Some(format!("{cleared} {}", describe(game, lang)));
+                }
+                GameEvent::AppleSpawned { .. } => {}
+            }
+        }
+        if news.is_empty() && game.ticks() < self.due {
+            return None;
+        }
+        self.due = game.ticks() + self.every;
+        news.push(describe(game, lang));
+        Some(news.join(" "))
+    }
+}
+
+/// A sentence on where the snake is and where the nearest apple is from
+/// its head.
+pub fn describe<R: Rng>(game: &Game<R>, lang: &Lang) -> String {
+    let snake = game.snake();
+    let [x, y] = snake.head();
+    let direction = lang.text(match snake.direction() {
+        Direction::Up => "direction-up",
+        Direction::Down => "direction-down",
+        Direction::Left => "direction-left",
+        Direction::Right => "direction-right",
+    });
+    // as the crow flies, not counting walls, the body or wrapping around,
+    // and only the next apple when they're numbered
+    let nearest = game
+        .targets()
+        .map(|apple| {
+            let [ax, ay] = apple.position();
+            (ax - x, ay - y)
+        })
+        .min_by_key(|(dx, dy)| dx.abs() + dy.abs());
+    let apple = match nearest {
+        Some((dx, dy)) => {
+            let across = match dx.signum() {
+                1 => Some(lang.format("apple-right", &[("cells", &dx)])),
+                -1 => Some(lang.format("apple-left", &[("cells", &-dx)])),
+                _ => None,
+            };
+            let along = match dy.signum() {
+                1 => Some(lang.format("apple-down", &[("cells", &dy)])),
+                -1 => Some(lang.format("apple-up", &[("cells", &-dy)])),
+                _ => None,
+            };
+            across
+                .into_iter()
+                .chain(along)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        None => lang.text("apple-none").to_string(),
+    };
+    lang.format(
+        "announce",
+        &[
+            ("x", &x),
+            ("y", &y),
+            ("direction", &direction),
+            ("apple", &apple),
+            ("score", &game.score()),
+        ],
+    )
+}
@@ -0,0 +1,594 @@
+//! Several snakes on one board, for multiplayer.
+//!
+//! `Arena` is the authoritative simulation a host runs. Clients never see
+//! it, only the `ArenaView` it sends out after every tick.
+
+use crate::Result;
+use crate::config::{GameConfig, WallMode};
+use crate::game::{self, Apple, DeathCause, Direction, GameRng, Snake};
+use crate::grid::{Coord, Grid, Tile};
+use crate::lang::Lang;
+use crate::render::Renderer;
+use crate::theme::Theme;
+use crossterm::style::Color;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Colors for everyone but the player looking at the board, who gets the
+/// theme's own snake colors.
+const RIVALS: [Color; 4] = [Color::Yellow, Color::Blue, Color::Magenta, Color::White];
+// and the shapes they get instead when there's no color
+const RIVAL_SHAPES: [char; 4] = ['%', '+', '&', '='];
+
+struct Player {
+    name: String,
+    snake: Snake,
+    score: u16,
+    alive: bool,
+    // what killed it last, if anything did
+    death: Option<DeathCause>,
+    // gone for good, and the slot free for the next player to join
+    left: bool,
+}
+
+pub struct Arena<R = GameRng> {
+    config: GameConfig,
+    grid: Grid,
+    players: Vec<Player>,
+    apples: Vec<Apple>,
+    rng: R,
+    ticks: u64,
+    // whether snakes die running into each other or slide straight through
+    friendly_fire: bool,
+}
+
+impl Arena {
+    /// Seeds like `Game::new`.
+    pub fn new(mut config: GameConfig) -> Arena {
+        let seed = *config.seed.get_or_insert_with(|| rand::rng().random());
+        Arena::with_rng(config, GameRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: Rng> Arena<R> {
+    pub fn with_rng(config: GameConfig, rng: R) -> Arena<R> {
+        let (_, grid) = game::build_board(&config);
+        let mut arena = Arena {
+            config,
+            grid,
+            players: vec![],
+            apples: vec![],
+            rng,
+            ticks: 0,
+            friendly_fire: true,
+        };
+        arena.fill_apples();
+        arena
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// On by default. With it off, snakes pass through each other and can
+    /// only die on the walls or their own bodies.
+    pub fn set_friendly_fire(&mut self, on: bool) {
+        self.friendly_fire = on;
+    }
+
+    /// Puts a new snake on the board and returns its player number, or
+    /// `None` if there's nowhere left to fit one. Numbers of players who
+    /// have left get handed out again.
+    pub fn add_player(&mut self, name: &str) -> Option<usize> {
+        let index = self
+            .players
+            .iter()
+            .position(|p| p.left)
+            .unwrap_or(self.players.len());
+        let snake = self.spawn_snake(index)?;
+        self.place(&snake);
+        let player = Player {
+            name: name.into(),
+            snake,
+            score: 0,
+            alive: true,
+            death: None,
+            left: false,
+        };
+        match self.players.get_mut(index) {
+            Some(slot) => *slot = player,
+            None => self.players.push(player),
+        }
+        Some(index)
+    }
+
+    /// Brings a dead player back with a fresh snake and no score. False if
+    /// there's no room for one right now.
+    pub fn respawn(&mut self, player: usize) -> bool {
+        if self.players.get(player).is_none_or(|p| p.alive || p.left) {
+            return false;
+        }
+        let Some(snake) = self.spawn_snake(player) else {
+            return false;
+        };
+        self.place(&snake);
+        let p = &mut self.players[player];
+        p.snake = snake;
+        p.score = 0;
+        p.alive = true;
+        p.death = None;
+        true
+    }
+
+    /// Takes a player out of the arena altogether.
+    pub fn remove(&mut self, player: usize) {
+        self.kill(player);
+        if let Some(p) = self.players.get_mut(player) {
+            p.left = true;
+        }
+    }
+
+    pub fn players(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_alive(&self, player: usize) -> bool {
+        self.players.get(player).is_some_and(|p| p.alive)
+    }
+
+    pub fn alive(&self) -> usize {
+        self.players.iter().filter(|p| p.alive).count()
+    }
+
+    /// What killed `player` last, while they're dead.
+    pub fn death(&self, player: usize) -> Option<DeathCause> {
+        self.players.get(player).and_then(|p| p.death)
+    }
+
+    /// Whether the round is decided: everyone's dead, or with more than one
+    /// player, only one is left.
+    pub fn is_over(&self) -> bool {
+        match self.players.len() {
+            0 => false,
+            1 => self.alive() == 0,
+            _ => self.alive() <= 1,
+        }
+    }
+
+    /// Whoever's still alive when there's exactly one, otherwise whoever
+    /// scored most, if anybody did better than everyone else.
+    pub fn winner(&self) -> Option<usize> {
+        if self.players.len() > 1 && self.alive() == 1 {
+            return self.players.iter().position(|p| p.alive);
+        }
+        let best = self.players.iter().map(|p| p.score).max()?;
+        let mut leaders = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.score == best);
+        match (leaders.next(), leaders.next()) {
+            (Some((i, _)), None) => Some(i),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self, player: usize) -> &str {
+        &self.players[player].name
+    }
+
+    pub fn turn(&mut self, player: usize, direction: Direction) {
+        if let Some(player) = self.players.get_mut(player) {
+            player.snake.turn(direction);
+        }
+    }
+
+    /// Takes a snake off the board, as if it had died. For players who
+    /// leave mid-round.
+    pub fn kill(&mut self, player: usize) {
+        if let Some(p) = self.players.get_mut(player)
+            && p.alive
+        {
+            p.alive = false;
+            for cell in p.snake.body() {
+                self.grid.set(*cell, Tile::Empty);
+            }
+            self.overlap();
+        }
+    }
+
+    // snakes passing through each other, or casual ones through
+    // themselves, can share cells, so whenever one moves off or dies, put
+    // back anything still covering it
+    fn overlap(&mut self) {
+        if self.friendly_fire && !self.config.casual {
+            return;
+        }
+        for player in self.players.iter().filter(|p| p.alive) {
+            for cell in player.snake.body() {
+                self.grid.set(*cell, Tile::Snake);
+            }
+        }
+    }
+
+    /// Moves every living snake one step at the same time.
+    ///
+    /// Tails all move out of the way first, so following someone closely
+    /// is safe. Two heads landing on the same cell both die, unless
+    /// friendly fire is off. Walls bounce snakes back with
+    /// `WallMode::Bounce`, and casual snakes go through themselves, like
+    /// they do on a board of their own.
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+        let (config, grid) = (&self.config, &self.grid);
+        // a snake that bounces stays where it is this tick
+        let next: Vec<Option<Coord>> = self
+            .players
+            .iter_mut()
+            .map(|p| {
+                (p.alive && !p.snake.bounce(config, grid))
+                    .then(|| game::neighbour(config, p.snake.head(), p.snake.direction()))
+            })
+            .collect();
+
+        for (player, cell) in self.players.iter_mut().zip(&next) {
+            if cell.is_some() {
+                player.snake.lift_tail(&mut self.grid);
+            }
+        }
+
+        let mut dead = vec![];
+        for (i, cell) in next.iter().enumerate() {
+            let Some(cell) = cell else {
+                continue;
+            };
+            // the grid can't say whose body a cell is, so ask the snake
+            let own = self.players[i].snake.body().contains(cell);
+            let head_on = next
+                .iter()
+                .enumerate()
+                .any(|(j, other)| j != i && *other == Some(*cell));
+            let cause = match self.grid.get(*cell) {
+                Tile::Wall if self.config.walls.contains(cell) => Some(DeathCause::Hazard),
+                Tile::Wall => Some(DeathCause::Wall),
+                _ if head_on && self.friendly_fire => Some(DeathCause::Enemy),
+                _ if own => (!self.config.casual).then_some(DeathCause::OwnBody),
+                Tile::Snake if self.friendly_fire => Some(DeathCause::Enemy),
+                _ => None,
+            };
+            if let Some(cause) = cause {
+                dead.push(i);
+                self.players[i].death = Some(cause);
+            }
+        }
+
+        for (i, cell) in next.into_iter().enumerate() {
+            let Some(cell) = cell else {
+                continue;
+            };
+            if dead.contains(&i) {
+                continue;
+            }
+            let player = &mut self.players[i];
+            if player.snake.push_head(&mut self.grid, cell) == Tile::Apple {
+                self.apples.retain(|apple| apple.position() != cell);
+                player.snake.grow(1);
+                player.score += 1;
+            }
+        }
+        for i in dead {
+            self.kill(i);
+        }
+        self.overlap();
+        self.fill_apples();
+    }
+
+    /// Everything a client needs to draw the board.
+    pub fn view(&self) -> ArenaView {
+        ArenaView {
+            width: self.config.width,
+            height: self.config.height,
+            wall_mode: self.config.wall_mode,
+            tick: self.ticks,
+            players: self
+                .players
+                .iter()
+                .map(|p| PlayerView {
+                    name: p.name.clone(),
+                    body: p.snake.body().iter().copied().collect(),
+                    score: p.score,
+                    alive: p.alive,
+                    death: p.death,
+                    left: p.left,
+                })
+                .collect(),
+            apples: self.apples.iter().map(Apple::position).collect(),
+            walls: self.config.walls.clone(),
+            portals: self
+                .config
+                .portals
+                .iter()
+                .flat_map(|portal| portal.ends)
+                .collect(),
+            gates: self
+                .config
+                .gates
+                .iter()
+                .flat_map(|gate| [*gate, self.config.across(*gate)])
+                .collect(),
+        }
+    }
+
+    fn place(&mut self, snake: &Snake) {
+        for cell in snake.body() {
+            self.grid.set(*cell, Tile::Snake);
+        }
+    }
+
+    fn fill_apples(&mut self) {
+        // a crowded arena might not have room for them all
+        while self.apples.len() < self.config.apples {
+            let Some(apple) = Apple::spawn(&self.grid, &mut self.rng) else {
+                break;
+            };
+            self.grid.set(apple.position(), Tile::Apple);
+            self.apples.push(apple);
+        }
+    }
+
+    // three cells with room to move ahead, facing right and left by turns
+    // and scanning out from the middle row, so two players start facing
+    // each other across the board
+    fn spawn_snake(&self, index: usize) -> Option<Snake> {
+        let (width, height) = (self.config.width as i16, self.config.height as i16);
+        let middle = height / 2;
+        let rows = (0..height).map(|i| {
+            let offset = (i + 1) / 2 * if i % 2 == 0 { 1 } else { -1 };
+            middle + offset * 2
+        });
+        let taken: Vec<i16> = self
+            .players
+            .iter()
+            .filter(|p| p.alive)
+            .map(|p| p.snake.head()[1])
+            .collect();
+        for y in rows.filter(|y| *y > 0 && *y < height - 1 && !taken.contains(y)) {
+            let (x, direction) = if index.is_multiple_of(2) {
+                (width / 3, Direction::Right)
+            } else {
+                (width - 1 - width / 3, Direction::Left)
+            };
+            let back = direction.opposite().delta()[0];
+            let body: VecDeque<Coord> = (0..3).map(|i| [x + back * i, y]).collect();
+            let ahead = (1..=3).map(|i| [x - back * i, y]);
+            if body
+                .iter()
+                .copied()
+                .chain(ahead)
+                .all(|cell| self.grid.is_empty(cell))
+            {
+                return Some(Snake::new(body, direction));
+            }
+        }
+        None
+    }
+}
+
+/// One snake as a client sees it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub name: String,
+    /// Head first.
+    pub body: Vec<Coord>,
+    pub score: u16,
+    pub alive: bool,
+    /// What killed it, while it's dead. Hosts from before there was one
+    /// don't say.
+    #[serde(default)]
+    pub death: Option<DeathCause>,
+    /// Disconnected, and not worth showing any more.
+    pub left: bool,
+}
+
+/// The whole board at one moment, as sent to clients.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArenaView {
+    pub width: u16,
+    pub height: u16,
+    pub wall_mode: WallMode,
+    pub tick: u64,
+    pub players: Vec<PlayerView>,
+    pub apples: Vec<Coord>,
+    /// The level's walls inside the border, and its portals' ends. Left
+    /// out without a level, and hosts from before levels don't send any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub walls: Vec<Coord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub portals: Vec<Coord>,
+    /// Gaps in the border, both ends of each.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gates: Vec<Coord>,
+}
+
+impl ArenaView {
+    /// Draws the board and a score line under it, every cell every time.
+    /// `me` is drawn in the theme's snake colors and everyone else in
+    /// their own.
+    pub fn draw(
+        &self,
+        renderer: &mut impl Renderer,
+        theme: &Theme,
+        lang: &Lang,
+        me: Option<usize>,
+    ) -> Result<()> {
+        self.draw_board(renderer, theme, me)?;
+        let width = self.width as usize;
+        let scores: Vec<String> = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !p.left)
+            .map(|(i, p)| {
+                let name = if Some(i) == me {
+                    lang.text("hud-you")
+                } else {
+                    p.name.as_str()
+                };
+                let dead = if p.alive { "" } else { lang.text("hud-dead") };
+                format!("{name}: {}{dead}", p.score)
+            })
+            .collect();
+        let line = scores.join("  ");
+        renderer.draw_text(
+            0,
+            self.height,
+            &format!("{line:<width$.width$}"),
+            theme.text,
+        )?;
+        Ok(())
+    }
+
+    /// Just the board, without the score line.
+    pub fn draw_board(
+        &self,
+        renderer: &mut impl Renderer,
+        theme: &Theme,
+        me: Option<usize>,
+    ) -> Result<()> {
+        let mut cells = vec![None; self.width as usize * self.height as usize];
+        let mut put = |cell: Coord, glyph: (char, Color)| {
+            if cell[0] >= 0
+                && cell[1] >= 0
+                && cell[0] < self.width as i16
+                && cell[1] < self.height as i16
+            {
+                cells[cell[1] as usize * self.width as usize + cell[0] as usize] = Some(glyph);
+            }
+        };
+        for wall in &self.walls {
+            put(*wall, (theme.wall.symbol, theme.wall.color));
+        }
+        for end in &self.portals {
+            put(*end, (theme.portal.symbol, theme.portal.color));
+        }
+        for apple in &self.apples {
+            put(*apple, (theme.apple.symbol, theme.apple.color));
+        }
+        let monochrome = theme.snake[0].color == Color::Reset;
+        let mut rival = 0;
+        for (i, player) in self.players.iter().enumerate().filter(|(_, p)| !p.left) {
+            let glyphs = if Some(i) == me {
+                theme.snake.map(|glyph| (glyph.symbol, glyph.color))
+            } else if monochrome {
+                rival += 1;
+                [(RIVAL_SHAPES[(rival - 1) % RIVAL_SHAPES.len()], Color::Reset); 2]
+            } else {
+                rival += 1;
+                theme
+                    .snake
+                    .map(|glyph| (glyph.symbol, RIVALS[(rival - 1) % RIVALS.len()]))
+            };
+            if !player.alive {
+                continue;
+            }
+            for (n, cell) in player.body.iter().enumerate() {
+                put(*cell, glyphs[n % 2]);
+            }
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let border = (x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1)
+                    && !self.gates.contains(&[x as i16, y as i16]);
+                match cells[y as usize * self.width as usize + x as usize] {
+                    Some((symbol, color)) => renderer.draw_cell(x, y, symbol, color)?,
+                    None if border => {
+                        renderer.draw_cell(x, y, theme.wall.symbol, theme.wall.color)?
+                    }
+                    None => renderer.clear_cell(x, y)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arena(config: crate::config::GameConfigBuilder) -> Arena {
+        let mut arena = Arena::new(config.size(12, 8).seed(1).build().unwrap());
+        arena.add_player("1");
+        arena
+    }
+
+    #[test]
+    fn walls_bounce_snakes_back() {
+        let mut bouncing = arena(GameConfig::builder().wall_mode(WallMode::Bounce));
+        let mut fatal = arena(GameConfig::builder().wall_mode(WallMode::Fatal));
+        for _ in 0..30 {
+            bouncing.tick();
+            fatal.tick();
+        }
+        assert!(bouncing.is_alive(0));
+        assert_eq!(fatal.death(0), Some(DeathCause::Wall));
+    }
+
+    // a snake heading down into its own middle
+    fn hooked(casual: bool) -> Arena {
+        let mut arena = arena(GameConfig::builder().casual(casual));
+        arena.kill(0);
+        for apple in std::mem::take(&mut arena.apples) {
+            arena.grid.set(apple.position(), Tile::Empty);
+        }
+        let body = [[5, 3], [6, 3], [6, 4], [5, 4], [4, 4]];
+        let snake = Snake::new(body.into(), Direction::Down);
+        arena.place(&snake);
+        arena.players[0].snake = snake;
+        arena.players[0].alive = true;
+        arena
+    }
+
+    #[test]
+    fn casual_snakes_go_through_themselves() {
+        let mut casual = hooked(true);
+        casual.tick();
+        assert!(casual.is_alive(0));
+        assert_eq!(casual.players[0].snake.head(), [5, 4]);
+        assert_eq!(casual.grid.get([5, 4]), Tile::Snake);
+
+        let mut hooked = hooked(false);
+        hooked.tick();
+        assert_eq!(hooked.death(0), Some(DeathCause::OwnBody));
+    }
+
+    #[test]
+    fn a_levels_walls_portals_and_gates_show_on_the_board() {
+        use crate::level::Portal;
+        use crate::render::HeadlessRenderer;
+
+        let mut config = GameConfig::builder().size(12, 8).seed(1).build().unwrap();
+        config.walls = vec![[9, 1], [9, 2]];
+        config.portals = vec![Portal {
+            ends: [[2, 1], [2, 6]],
+        }];
+        config.gates = vec![[0, 5]];
+        let arena = Arena::new(config);
+        let theme = &arena.config().theme;
+        let mut renderer = HeadlessRenderer::new(12, 8);
+        arena.view().draw_board(&mut renderer, theme, None).unwrap();
+        renderer.present().unwrap();
+        let glyph = |x, y| renderer.cell(x, y).map(|(glyph, _)| glyph);
+        assert_eq!(glyph(9, 2), Some(theme.wall.symbol));
+        assert_eq!([glyph(2, 1), glyph(2, 6)], [Some(theme.portal.symbol); 2]);
+        assert_eq!([glyph(0, 5), glyph(11, 5)], [None, None]);
+        assert_eq!(glyph(0, 4), Some(theme.wall.symbol));
+    }
+}
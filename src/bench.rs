@@ -0,0 +1,220 @@
+use crate::bot::{self, Greedy};
+use crate::config::GameConfig;
+use crate::game::Game;
+use crate::render::{HeadlessRenderer, Renderer};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+// bytes allocated and not yet freed, and the most there have been since
+// the last `reset_peak`
+static IN_USE: AtomicU64 = AtomicU64::new(0);
+static PEAK: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, plus a count of every allocation made through it
+/// and how much memory they add up to. Install it with `#[global_allocator]`
+/// for `run` to report allocations and memory; without it they read as
+/// zero.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        grew(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        IN_USE.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        grew(layout.size());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        IN_USE.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        grew(new_size);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+fn grew(size: usize) {
+    let in_use = IN_USE.fetch_add(size as u64, Ordering::Relaxed) + size as u64;
+    PEAK.fetch_max(in_use, Ordering::Relaxed);
+}
+
+/// Allocations made so far through `CountingAllocator`.
+pub fn allocations() -> u64 {
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+/// Bytes allocated through `CountingAllocator` and not freed yet.
+pub fn memory_in_use() -> u64 {
+    IN_USE.load(Ordering::Relaxed)
+}
+
+/// The most `memory_in_use` has been since the last `reset_peak`.
+pub fn peak_memory() -> u64 {
+    PEAK.load(Ordering::Relaxed)
+}
+
+/// Starts `peak_memory` over from what's in use now.
+pub fn reset_peak() {
+    PEAK.store(memory_in_use(), Ordering::Relaxed);
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BenchReport {
+    /// The board's width and height.
+    pub board: [u16; 2],
+    pub ticks: u64,
+    pub games: u64,
+    pub elapsed: time::Duration,
+    /// Allocations made while ticking and drawing, leaving out setting up
+    /// each game. Should be zero: nothing on that path needs the heap once
+    /// a game's going.
+    pub allocations: u64,
+    /// How long 99% of ticks took at most, drawing included, to within a
+    /// few percent.
+    pub p99: time::Duration,
+    /// The most memory the games had at once, over what was in use before
+    /// they started.
+    pub peak_memory: u64,
+}
+
+impl BenchReport {
+    pub fn ticks_per_second(&self) -> f64 {
+        self.ticks as f64 / self.elapsed.as_secs_f64()
+    }
+
+    pub fn allocations_per_tick(&self) -> f64 {
+        self.allocations as f64 / self.ticks as f64
+    }
+
+    /// The report for scripts to keep track of, with the version it was
+    /// run on so results from different releases can be told apart.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "width": self.board[0],
+            "height": self.board[1],
+            "ticks": self.ticks,
+            "games": self.games,
+            "seconds": self.elapsed.as_secs_f64(),
+            "ticks_per_second": self.ticks_per_second(),
+            "p99_tick_ns": self.p99.as_nanos() as u64,
+            "peak_memory_bytes": self.peak_memory,
+            "allocations": self.allocations,
+            "allocations_per_tick": self.allocations_per_tick(),
+        })
+    }
+}
+
+// how many buckets each power of two of nanoseconds is split into, which
+// is what keeps tick times to within about 3%
+const SPLITS: u64 = 32;
+
+// how long ticks took, in buckets that get wider as they get longer, so
+// there's a fixed amount to keep however many ticks there are
+struct TickTimes {
+    counts: Vec<u64>,
+}
+
+impl TickTimes {
+    fn new() -> TickTimes {
+        // enough buckets for every u64 of nanoseconds
+        TickTimes {
+            counts: vec![0; (SPLITS * (65 - SPLITS.ilog2() as u64)) as usize],
+        }
+    }
+
+    fn record(&mut self, tick: time::Duration) {
+        let ns = u64::try_from(tick.as_nanos()).unwrap_or(u64::MAX);
+        let bucket = if ns < SPLITS {
+            ns
+        } else {
+            // the top bits of `ns`, and which power of two it's in
+            let shift = ns.ilog2() - SPLITS.ilog2();
+            SPLITS * (shift as u64 + 1) + (ns >> shift) - SPLITS
+        };
+        self.counts[bucket as usize] += 1;
+    }
+
+    // the shortest time in the bucket the `n`th percentile falls in
+    fn percentile(&self, n: u64) -> time::Duration {
+        let total: u64 = self.counts.iter().sum();
+        let wanted = (total * n).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= wanted {
+                let bucket = bucket as u64;
+                let ns = match bucket.checked_sub(SPLITS) {
+                    None => bucket,
+                    Some(over) => (SPLITS + over % SPLITS) << (over / SPLITS),
+                };
+                return time::Duration::from_nanos(ns);
+            }
+        }
+        time::Duration::ZERO
+    }
+}
+
+/// Plays `ticks` ticks of headless games of `config` with the greedy bot,
+/// drawing each one, and starting a new game whenever one ends. Game `n`
+/// uses `config`'s seed plus `n`, or just `n` without one, so runs with
+/// the same arguments do the same work.
+pub fn run(config: &GameConfig, ticks: u64) -> BenchReport {
+    let mut bot = Greedy;
+    let mut games = 0;
+    let mut ticked = 0;
+    let mut allocated = 0;
+    let mut times = TickTimes::new();
+
+    let seed = config.seed.unwrap_or(0);
+    // one more row for the HUD
+    let mut renderer = HeadlessRenderer::new(config.width, config.height + 1);
+    let baseline = memory_in_use();
+    reset_peak();
+    let start = time::Instant::now();
+    while ticked < ticks {
+        let config = GameConfig {
+            seed: Some(seed + games),
+            ..config.clone()
+        };
+        let mut game = Game::new(config);
+        games += 1;
+        let allocations_before = allocations();
+        while ticked < ticks {
+            ticked += 1;
+            let began = time::Instant::now();
+            let tick = bot::step(&mut game, &mut bot);
+            // the HUD's clock has to keep up too
+            game.set_elapsed(start.elapsed());
+            game.draw(&mut renderer)
+                .expect("headless drawing can't fail");
+            renderer.present().expect("headless drawing can't fail");
+            times.record(began.elapsed());
+            if tick.died || tick.won {
+                break;
+            }
+        }
+        allocated += allocations() - allocations_before;
+    }
+
+    BenchReport {
+        board: [config.width, config.height],
+        ticks: ticked,
+        games,
+        elapsed: start.elapsed(),
+        allocations: allocated,
+        p99: times.percentile(99),
+        peak_memory: peak_memory().saturating_sub(baseline),
+    }
+}
@@ -0,0 +1,468 @@
+use crate::config::WallMode;
+use crate::game::{Direction, Game, TickResult};
+use crate::grid::{Coord, Tile};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+/// Anything that can steer a snake: built-in AIs, plugins, scripted
+/// input for tests.
+pub trait Controller {
+    /// The direction for the next tick, or `None` to carry on straight.
+    fn decide(&mut self, game: &Game) -> Option<Direction>;
+}
+
+/// Every built-in AI `named` knows about, weakest first.
+pub const NAMES: [&str; 4] = ["greedy", "bfs", "astar", "hamiltonian"];
+
+/// A built-in AI by name.
+pub fn named(name: &str) -> Option<Box<dyn Controller>> {
+    match name {
+        "greedy" => Some(Box::new(Greedy)),
+        "bfs" => Some(Box::new(Bfs)),
+        "astar" => Some(Box::new(AStar)),
+        "hamiltonian" => Some(Box::new(Hamiltonian::default())),
+        _ => None,
+    }
+}
+
+/// Asks `controller` for a move and ticks once with it.
+pub fn step(game: &mut Game, controller: &mut dyn Controller) -> TickResult {
+    let direction = controller.decide(game);
+    game.tick(direction)
+}
+
+/// Heads straight for the nearest apple, swerving only to avoid moving
+/// into something fatal on the very next tick. Cheap and dim: it happily
+/// traps itself. Mostly useful as a baseline and for benchmarks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Greedy;
+
+impl Controller for Greedy {
+    fn decide(&mut self, game: &Game) -> Option<Direction> {
+        let head = game.snake().head();
+        let reverse = game.snake().direction().opposite();
+        let distance = |cell: Coord| {
+            game.targets()
+                .map(|apple| {
+                    let at = apple.position();
+                    (at[0] - cell[0]).abs() + (at[1] - cell[1]).abs()
+                })
+                .min()
+                .unwrap_or(0)
+        };
+
+        Direction::ALL
+            .into_iter()
+            .filter(|direction| *direction != reverse)
+            .map(|direction| (direction, game.neighbour(head, direction)))
+            .filter(|(_, cell)| matches!(game.grid().get(*cell), Tile::Empty | Tile::Apple))
+            .min_by_key(|(_, cell)| distance(*cell))
+            .map(|(direction, _)| direction)
+    }
+}
+
+/// Takes the shortest way to the nearest apple, around walls and itself,
+/// or if there's no way there, wherever leaves it the most room. Doesn't
+/// look any further ahead than the apple, so it still traps itself, just
+/// less often than `Greedy`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bfs;
+
+impl Controller for Bfs {
+    fn decide(&mut self, game: &Game) -> Option<Direction> {
+        let board = Board::new(game, game.snake().body().iter().copied());
+        let head = game.snake().head();
+        board
+            .search(head, |cell| {
+                game.targets().any(|apple| apple.position() == cell)
+            })
+            .or_else(|| board.roomiest(head))
+    }
+}
+
+/// Finds its way to an apple with A*, but only goes if it'd still be able
+/// to reach its own tail after eating it, so it never shuts itself in.
+/// Otherwise it follows its tail round until it's safe to go.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AStar;
+
+impl Controller for AStar {
+    fn decide(&mut self, game: &Game) -> Option<Direction> {
+        let snake = game.snake();
+        let board = Board::new(game, snake.body().iter().copied());
+        let head = snake.head();
+        if let Some(path) = safe_path(game) {
+            return board.direction(head, path[0]);
+        }
+        let tail = snake.body()[snake.len() - 1];
+        board
+            .search(head, |cell| cell == tail)
+            .or_else(|| board.roomiest(head))
+    }
+}
+
+/// The way `AStar` would go for an apple: every cell from the one after
+/// the head to the apple itself, by the shortest way to the nearest apple
+/// it could still reach its own tail from after eating. `None` if there's
+/// no apple that's safe to go for.
+pub fn safe_path(game: &Game) -> Option<Vec<Coord>> {
+    let snake = game.snake();
+    let board = Board::new(game, snake.body().iter().copied());
+    let head = snake.head();
+    let mut apples: Vec<Coord> = game.targets().map(|apple| apple.position()).collect();
+    apples.sort_by_key(|apple| board.distance(head, *apple));
+    apples.into_iter().find_map(|apple| {
+        let path = board.path(head, apple)?;
+        // the snake as it'd be just after eating, a cell longer
+        let body: Vec<Coord> = path
+            .iter()
+            .rev()
+            .chain(snake.body())
+            .copied()
+            .take(snake.len() + 1)
+            .collect();
+        let after = Board::new(game, body.iter().copied());
+        let tail = body[body.len() - 1];
+        after.search(body[0], |cell| cell == tail)?;
+        Some(path)
+    })
+}
+
+/// Goes round a Hamiltonian cycle, a path through every cell on the board
+/// and back, which can't fail to fill the board given time. While the
+/// snake's short it cuts across the cycle towards the apple, as long as
+/// that can't take it past its own tail.
+///
+/// There's no cycle on a board with an odd number of cells each way, or
+/// one with walls or portals in it, and there it plays like `AStar`.
+#[derive(Clone, Debug, Default)]
+pub struct Hamiltonian {
+    // made for the first board it plays on, and again if the size changes
+    cycle: Option<Cycle>,
+}
+
+// how much room to leave behind the tail when cutting across, for growing
+// by more than a cell at once
+const SHORTCUT_SLACK: u32 = 4;
+
+impl Controller for Hamiltonian {
+    fn decide(&mut self, game: &Game) -> Option<Direction> {
+        let config = game.config();
+        if !config.walls.is_empty() || !config.portals.is_empty() {
+            return AStar.decide(game);
+        }
+        if self
+            .cycle
+            .as_ref()
+            .is_none_or(|cycle| (cycle.width, cycle.height) != (config.width, config.height))
+        {
+            self.cycle = Cycle::new(config.width, config.height);
+        }
+        let Some(cycle) = &self.cycle else {
+            return AStar.decide(game);
+        };
+
+        let snake = game.snake();
+        let head = snake.head();
+        let free = |cell: Coord| matches!(game.grid().get(cell), Tile::Empty | Tile::Apple);
+        // the cycle goes whichever way round doesn't start by doubling back
+        let neck = snake.body().get(1).copied();
+        let backwards = cycle.next(head, false) == neck;
+        let Some(next) = cycle.next(head, backwards) else {
+            return AStar.decide(game);
+        };
+        let ahead = |cell: Coord| cycle.ahead(head, cell, backwards);
+
+        let mut target = next;
+        if (snake.len() as u32) < cycle.len / 2
+            && let Some(apple) = game
+                .targets()
+                .filter_map(|apple| ahead(apple.position()))
+                .min()
+            && let Some(tail) = snake
+                .body()
+                .iter()
+                .skip(1)
+                .filter_map(|cell| ahead(*cell))
+                .min()
+        {
+            let reverse = snake.direction().opposite();
+            let shortcut = Direction::ALL
+                .into_iter()
+                .filter(|direction| *direction != reverse)
+                .map(|direction| game.neighbour(head, direction))
+                .filter(|cell| free(*cell))
+                .filter_map(|cell| Some((ahead(cell)?, cell)))
+                .filter(|(steps, _)| *steps <= apple && *steps + SHORTCUT_SLACK < tail)
+                .max_by_key(|(steps, _)| *steps);
+            if let Some((_, cell)) = shortcut {
+                target = cell;
+            }
+        }
+        if !free(target) {
+            return AStar.decide(game);
+        }
+        Direction::ALL
+            .into_iter()
+            .find(|direction| game.neighbour(head, *direction) == target)
+    }
+}
+
+// a way round every cell inside the border, as where each cell comes in it
+#[derive(Clone, Debug)]
+struct Cycle {
+    width: u16,
+    height: u16,
+    // by cell, row by row, with the border left out
+    order: Vec<u32>,
+    cells: Vec<Coord>,
+    len: u32,
+}
+
+impl Cycle {
+    // along the top row, back and forth down the rest leaving the first
+    // column, and up that to the start. that needs an even number of
+    // rows, or columns if it's turned on its side
+    fn new(width: u16, height: u16) -> Option<Cycle> {
+        let (inside_x, inside_y) = (width.checked_sub(2)?, height.checked_sub(2)?);
+        let (across, down, sideways) = if inside_y % 2 == 0 && inside_x >= 2 {
+            (inside_x, inside_y, false)
+        } else if inside_x % 2 == 0 && inside_y >= 2 {
+            (inside_y, inside_x, true)
+        } else {
+            return None;
+        };
+        let mut cells = vec![];
+        cells.extend((0..across).map(|x| [x, 0]));
+        for y in 1..down {
+            if y % 2 == 1 {
+                cells.extend((1..across).rev().map(|x| [x, y]));
+            } else {
+                cells.extend((1..across).map(|x| [x, y]));
+            }
+        }
+        cells.extend((1..down).rev().map(|y| [0, y]));
+        let cells: Vec<Coord> = cells
+            .into_iter()
+            .map(|[x, y]| if sideways { [y, x] } else { [x, y] })
+            .map(|[x, y]| [x as i16 + 1, y as i16 + 1])
+            .collect();
+        let mut order = vec![u32::MAX; inside_x as usize * inside_y as usize];
+        for (n, cell) in cells.iter().enumerate() {
+            order[(cell[1] - 1) as usize * inside_x as usize + (cell[0] - 1) as usize] = n as u32;
+        }
+        Some(Cycle {
+            width,
+            height,
+            order,
+            len: cells.len() as u32,
+            cells,
+        })
+    }
+
+    fn position(&self, cell: Coord) -> Option<u32> {
+        let (x, y) = (cell[0] - 1, cell[1] - 1);
+        if x < 0 || y < 0 || x >= self.width as i16 - 2 || y >= self.height as i16 - 2 {
+            return None;
+        }
+        Some(self.order[y as usize * (self.width as usize - 2) + x as usize])
+    }
+
+    // the cell after `cell`, going round forwards or backwards
+    fn next(&self, cell: Coord, backwards: bool) -> Option<Coord> {
+        let position = self.position(cell)?;
+        let next = if backwards {
+            (position + self.len - 1) % self.len
+        } else {
+            (position + 1) % self.len
+        };
+        Some(self.cells[next as usize])
+    }
+
+    // how many steps round it is from `from` to `to`
+    fn ahead(&self, from: Coord, to: Coord, backwards: bool) -> Option<u32> {
+        let (from, to) = (self.position(from)?, self.position(to)?);
+        let steps = if backwards {
+            from + self.len - to
+        } else {
+            to + self.len - from
+        };
+        Some(steps % self.len)
+    }
+}
+
+// the board as far as finding a way round it goes: how many moves it is
+// until each cell's clear. walls never are, and a snake cell is once the
+// tail's gone past it
+struct Board<'a> {
+    game: &'a Game,
+    clear_in: Vec<u32>,
+}
+
+impl<'a> Board<'a> {
+    // with the snake at `body`, head first, which needn't be where it is
+    fn new(game: &'a Game, body: impl ExactSizeIterator<Item = Coord>) -> Board<'a> {
+        let clear_in = game
+            .grid()
+            .tiles()
+            .iter()
+            .map(|tile| match tile {
+                Tile::Wall => u32::MAX,
+                _ => 0,
+            })
+            .collect();
+        let mut board = Board { game, clear_in };
+        let len = body.len() as u32;
+        for (n, cell) in body.enumerate() {
+            if let Some(index) = board.index(cell) {
+                board.clear_in[index] = len - n as u32;
+            }
+        }
+        board
+    }
+
+    fn index(&self, cell: Coord) -> Option<usize> {
+        let grid = self.game.grid();
+        let x = usize::try_from(cell[0])
+            .ok()
+            .filter(|x| *x < grid.width() as usize)?;
+        let y = usize::try_from(cell[1])
+            .ok()
+            .filter(|y| *y < grid.height() as usize)?;
+        Some(y * grid.width() as usize + x)
+    }
+
+    // whether the head could be on `cell` after `moves` moves
+    fn open(&self, cell: Coord, moves: u32) -> bool {
+        self.index(cell)
+            .is_some_and(|index| self.clear_in[index] <= moves)
+    }
+
+    // the moves from `from`, leaving out turning back on itself
+    fn moves(&self, from: Coord) -> impl Iterator<Item = (Direction, Coord)> + '_ {
+        let reverse = self.game.snake().direction().opposite();
+        Direction::ALL
+            .into_iter()
+            .filter(move |direction| *direction != reverse || from != self.game.snake().head())
+            .map(move |direction| (direction, self.game.neighbour(from, direction)))
+    }
+
+    // the first move on the shortest way from `from` to any cell `goal`
+    // likes, breadth first
+    fn search(&self, from: Coord, goal: impl Fn(Coord) -> bool) -> Option<Direction> {
+        let mut seen = vec![false; self.clear_in.len()];
+        let mut queue = VecDeque::new();
+        for (direction, cell) in self.moves(from) {
+            if let Some(index) = self.index(cell)
+                && self.open(cell, 1)
+                && !seen[index]
+            {
+                seen[index] = true;
+                queue.push_back((cell, direction, 1));
+            }
+        }
+        while let Some((cell, first, moves)) = queue.pop_front() {
+            if goal(cell) {
+                return Some(first);
+            }
+            for (_, next) in self.moves(cell) {
+                if let Some(index) = self.index(next)
+                    && self.open(next, moves + 1)
+                    && !seen[index]
+                {
+                    seen[index] = true;
+                    queue.push_back((next, first, moves + 1));
+                }
+            }
+        }
+        None
+    }
+
+    // the cells on the way from `from` to `to` by A*, `to` included and
+    // `from` not
+    fn path(&self, from: Coord, to: Coord) -> Option<Vec<Coord>> {
+        let mut came_from: Vec<Option<Coord>> = vec![None; self.clear_in.len()];
+        let mut moves = vec![u32::MAX; self.clear_in.len()];
+        let mut open = BinaryHeap::new();
+        moves[self.index(from)?] = 0;
+        open.push(Reverse((self.distance(from, to), 0, from)));
+        while let Some(Reverse((_, so_far, cell))) = open.pop() {
+            if cell == to {
+                let mut path = vec![cell];
+                while let Some(before) = came_from[self.index(path[path.len() - 1])?]
+                    && before != from
+                {
+                    path.push(before);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if so_far > moves[self.index(cell)?] {
+                continue;
+            }
+            for (_, next) in self.moves(cell) {
+                let Some(index) = self.index(next) else {
+                    continue;
+                };
+                if self.open(next, so_far + 1) && so_far + 1 < moves[index] {
+                    moves[index] = so_far + 1;
+                    came_from[index] = Some(cell);
+                    open.push(Reverse((
+                        so_far + 1 + self.distance(next, to),
+                        so_far + 1,
+                        next,
+                    )));
+                }
+            }
+        }
+        None
+    }
+
+    // how many moves it'd be from `from` to `to` with nothing in the way
+    fn distance(&self, from: Coord, to: Coord) -> u32 {
+        let config = self.game.config();
+        let (dx, dy) = (from[0].abs_diff(to[0]), from[1].abs_diff(to[1]));
+        // gates only wrap some of the way, so it's never more than this
+        if config.wall_mode == WallMode::Wrap || !config.gates.is_empty() {
+            let (across, down) = (config.width - 2, config.height - 2);
+            (dx.min(across.saturating_sub(dx)) + dy.min(down.saturating_sub(dy))) as u32
+        } else {
+            (dx + dy) as u32
+        }
+    }
+
+    // the move into the cell next door, `to`
+    fn direction(&self, from: Coord, to: Coord) -> Option<Direction> {
+        self.moves(from)
+            .find(|(_, cell)| *cell == to)
+            .map(|(direction, _)| direction)
+    }
+
+    // the move that leaves the most room to move about in afterwards
+    fn roomiest(&self, from: Coord) -> Option<Direction> {
+        self.moves(from)
+            .filter(|(_, cell)| self.open(*cell, 1))
+            .max_by_key(|(_, cell)| self.room(*cell))
+            .map(|(direction, _)| direction)
+    }
+
+    // how many cells could be got to from `from`
+    fn room(&self, from: Coord) -> usize {
+        let mut seen = vec![false; self.clear_in.len()];
+        let mut queue = VecDeque::from([(from, 1)]);
+        let mut room = 0;
+        while let Some((cell, moves)) = queue.pop_front() {
+            for (_, next) in self.moves(cell) {
+                if let Some(index) = self.index(next)
+                    && self.open(next, moves + 1)
+                    && !seen[index]
+                {
+                    seen[index] = true;
+                    room += 1;
+                    queue.push_back((next, moves + 1));
+                }
+            }
+        }
+        room
+    }
+}
@@ -0,0 +1,92 @@
+//! The bonus bug, Snake II style: every few apples one scuttles out along
+//! the border, two cells long, and sits there for a while before it goes
+//! again. It's worth a lot more than an apple, and less the longer it's
+//! left, but the snake doesn't grow any eating it.
+//!
+//! The grid has it down as an apple in both its cells, so the head going
+//! into either end eats the whole thing.
+
+use crate::Result;
+use crate::game::draw_cell;
+use crate::grid::{Coord, Grid};
+use crate::render::Renderer;
+use crate::theme::Theme;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How many apples go by between bugs.
+pub const EVERY: u16 = 5;
+
+/// How many ticks a bug stays out for.
+pub const TICKS: u64 = 60;
+
+// what it's worth when it's just come out, and when it's about to go
+const MOST_POINTS: u16 = 35;
+const LEAST_POINTS: u16 = 5;
+
+/// A bug out on the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bug {
+    /// Its two cells, top or left one first.
+    pub cells: [Coord; 2],
+    /// The tick it goes on.
+    pub leaves: u64,
+}
+
+impl Bug {
+    /// A bug somewhere along the inside of the border, lying along it, out
+    /// for `TICKS` from `ticks`. `None` if there's nowhere with two empty
+    /// cells side by side.
+    pub fn spawn(grid: &Grid, ticks: u64, rng: &mut impl Rng) -> Option<Bug> {
+        let free = || places(grid).filter(|cells| cells.iter().all(|cell| grid.is_empty(*cell)));
+        let count = free().count();
+        if count == 0 {
+            return None;
+        }
+        free().nth(rng.random_range(0..count)).map(|cells| Bug {
+            cells,
+            leaves: ticks + TICKS,
+        })
+    }
+
+    pub fn covers(&self, cell: Coord) -> bool {
+        self.cells.contains(&cell)
+    }
+
+    /// How many ticks it has left, at `ticks`.
+    pub fn left(&self, ticks: u64) -> u64 {
+        self.leaves.saturating_sub(ticks)
+    }
+
+    /// What it's worth eaten at `ticks`, before the scoring rule has its
+    /// say: the most straight away, down to the least as it's leaving.
+    pub fn points(&self, ticks: u64) -> u16 {
+        let spread = u64::from(MOST_POINTS - LEAST_POINTS);
+        LEAST_POINTS + (spread * self.left(ticks).min(TICKS) / TICKS) as u16
+    }
+
+    pub(crate) fn draw(
+        &self,
+        renderer: &mut impl Renderer,
+        theme: &Theme,
+        grid: &Grid,
+    ) -> Result<()> {
+        for (cell, glyph) in self.cells.into_iter().zip(theme.bug) {
+            draw_cell(renderer, theme, grid, cell, glyph)?;
+        }
+        Ok(())
+    }
+}
+
+// every pair of cells just inside the border, lying along it: across the
+// top and bottom rows, and down the sides
+fn places(grid: &Grid) -> impl Iterator<Item = [Coord; 2]> {
+    let (right, bottom) = (grid.width() as i16 - 2, grid.height() as i16 - 2);
+    let across = [1, bottom]
+        .into_iter()
+        .flat_map(move |y| (1..right).map(move |x| [[x, y], [x + 1, y]]));
+    let down = [1, right]
+        .into_iter()
+        .flat_map(move |x| (1..bottom).map(move |y| [[x, y], [x, y + 1]]));
+    across.chain(down)
+}
@@ -0,0 +1,109 @@
+//! A few lines to paste somewhere and show off a run, wordle style: the
+//! score and time, the board and seed it was played on, and the final
+//! board shrunk down to a grid of squares.
+//!
+//! ```text
+//! rake: 17 points in 1:23
+//! fatal 40x15, seed 8812
+//! ⬛⬛⬛⬛⬛⬛⬛⬛⬛⬛
+//! ⬛⬛🟩🟩🟩🟨⬛⬛⬛⬛
+//! ⬛⬛🟩⬛⬛⬛⬛🟥⬛⬛
+//! ```
+
+use crate::clock::Clock;
+use crate::game::Game;
+use crate::grid::Tile;
+use crate::lang::Lang;
+use crate::results::RunSummary;
+use crate::scores::board_key;
+use rand::Rng;
+
+// how many squares wide the grid is at most, so it fits in a chat message
+const MAX_COLUMNS: usize = 10;
+
+// what each square is drawn with, in order of what wins when a square
+// covers several cells: the head, the rest of the snake, an apple, a wall
+// and then nothing at all
+const EMOJI: [&str; 5] = ["🟨", "🟩", "🟥", "⬜", "⬛"];
+const ASCII: [&str; 5] = ["@", "o", "*", "#", "."];
+
+/// The card for `game`, which `summary` is the run of. `ascii` sticks to
+/// characters that aren't emoji, for terminals and screen readers that
+/// don't do them justice.
+pub fn card<R: Rng>(game: &Game<R>, summary: &RunSummary, lang: &Lang, ascii: bool) -> String {
+    let time = Clock::from_ms(summary.duration_ms);
+    let mut lines = vec![
+        lang.format("card", &[("score", &summary.score), ("time", &time)]),
+        match summary.seed {
+            Some(seed) => lang.format(
+                "card-board",
+                &[("board", &board_key(game.config())), ("seed", &seed)],
+            ),
+            None => board_key(game.config()),
+        },
+    ];
+    let squares = if ascii { ASCII } else { EMOJI };
+    lines.extend(grid(game).into_iter().map(|row| {
+        row.into_iter()
+            .map(|square| squares[square])
+            .collect::<String>()
+    }));
+    lines.join("\n")
+}
+
+// the inside of the board in squares, each one the index of whatever in
+// it comes first in `EMOJI`
+fn grid<R: Rng>(game: &Game<R>) -> Vec<Vec<usize>> {
+    let grid = game.grid();
+    let (width, height) = (grid.width() as usize - 2, grid.height() as usize - 2);
+    // terminal cells are about twice as tall as they're wide, and squares
+    // aren't, so a square takes half as many rows as columns
+    let across = width.div_ceil(MAX_COLUMNS);
+    let down = across.div_ceil(2);
+    let head = game.snake().head();
+    let mut rows = vec![vec![4; width.div_ceil(across)]; height.div_ceil(down)];
+    for y in 0..height {
+        for x in 0..width {
+            let cell = [x as i16 + 1, y as i16 + 1];
+            let square = if cell == head {
+                0
+            } else {
+                match grid.get(cell) {
+                    Tile::Snake => 1,
+                    Tile::Apple => 2,
+                    Tile::Wall => 3,
+                    Tile::Empty => 4,
+                }
+            };
+            let best = &mut rows[y / down][x / across];
+            *best = (*best).min(square);
+        }
+    }
+    rows
+}
+
+/// The escape sequence asking the terminal to put `text` on the clipboard
+/// (OSC 52). Terminals that don't support it, or have it turned off, just
+/// ignore it.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let n = group
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= group.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,474 @@
+use clap::{Args, Parser, Subcommand};
+use rake::config::{Scoring, SpawnPattern, WallMode};
+use rake::net::DEFAULT_PORT;
+use rake::settings::Settings;
+use rake::{RakeError, Result};
+use std::env;
+use std::path::PathBuf;
+
+/// Snake, but in the terminal.
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub game: GameArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Play a game (the default), with a line summing up the session's
+    /// games at the end
+    Play,
+    /// Learn the controls on a slow, guided board, then play for real,
+    /// summed up like play
+    Tutorial,
+    /// Time the simulation headlessly with a bot at the controls
+    Bench {
+        /// How many ticks to run
+        #[arg(default_value_t = 1_000_000)]
+        ticks: u64,
+        /// Print the report as JSON, for keeping track of it from scripts
+        #[arg(long)]
+        json: bool,
+    },
+    /// Play bots against each other headlessly and rank them
+    Tournament {
+        /// Built-in AIs by name, or .wasm plugin files with the wasm
+        /// feature; every built-in AI if none are given
+        entrants: Vec<String>,
+        /// Games each entrant plays
+        #[arg(long, default_value_t = 100)]
+        games: u64,
+        /// Moves before a game that's still going is called
+        #[arg(long, default_value_t = 10_000)]
+        max_ticks: u64,
+    },
+    /// Show the local leaderboards
+    Highscores {
+        /// Fetch the global top list for this board instead
+        #[cfg(feature = "online")]
+        #[arg(long)]
+        global: bool,
+    },
+    /// Show where you die most on this board
+    Heatmap,
+    /// Play back a run saved with --json-results
+    Replay {
+        /// The saved run
+        path: PathBuf,
+
+        /// Write it to a .cast (asciinema) or .gif file instead of playing
+        /// it here
+        #[arg(long, value_name = "FILE")]
+        export: Option<PathBuf>,
+    },
+    /// Lay out a level with the cursor keys, to play with --level
+    Edit {
+        /// The level file, made if it isn't there yet
+        path: PathBuf,
+    },
+    /// Check a level file for mistakes, on the board the settings give.
+    /// Exits 1 if there are any, and 2 if it can't be read at all
+    CheckLevel {
+        /// The level file
+        path: PathBuf,
+    },
+    /// Show the snake skins and how to unlock them
+    Skins,
+    /// Read or change the settings file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Two snakes on one keyboard, WASD and the arrow keys, sharing a score
+    Coop {
+        /// End as soon as either snake dies, not once both have
+        #[arg(long)]
+        either: bool,
+        /// Let the snakes crash into each other instead of passing through
+        #[arg(long)]
+        friendly_fire: bool,
+    },
+    /// Two boards side by side on the same seed, racing to a score: two
+    /// players on WASD and the arrow keys, or one against a bot
+    Race {
+        /// The score that wins
+        #[arg(long, default_value_t = 20)]
+        target: u16,
+        /// Race a built-in AI instead of a second player
+        #[arg(long, value_name = "NAME")]
+        against: Option<String>,
+    },
+    /// Two players taking turns at whole runs on the same seed, best of
+    /// a few rounds
+    Hotseat {
+        /// How many rounds, the series going to whoever wins most of them
+        #[arg(long, default_value_t = 3)]
+        rounds: u32,
+        /// What to call the two players
+        #[arg(long, num_args = 2, value_names = ["ONE", "TWO"])]
+        names: Option<Vec<String>>,
+    },
+    /// Host a multiplayer game and play in it
+    Host {
+        /// Port to listen on
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+        /// How many players to wait for before starting
+        #[arg(long, default_value_t = 2)]
+        players: usize,
+        /// Name to show the other players
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Run a multiplayer arena without playing in it, for a server
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+    },
+    /// Join a multiplayer game, like `rake join 192.168.1.5:7878`
+    Join {
+        addr: String,
+        /// Name to show the other players
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Watch a multiplayer game without playing in it
+    Watch { addr: String },
+    /// Pass a multiplayer game on to browsers over WebSocket
+    #[cfg(feature = "websocket")]
+    Bridge {
+        addr: String,
+        /// Port browsers connect to
+        #[arg(long, default_value_t = rake::websocket::DEFAULT_PORT)]
+        port: u16,
+    },
+    /// Let anyone `ssh` in for a game of their own
+    #[cfg(feature = "ssh")]
+    SshServe {
+        /// Port to listen on
+        #[arg(long, default_value_t = rake::ssh::DEFAULT_PORT)]
+        port: u16,
+        /// Address to listen on; 0.0.0.0 lets in anyone who can reach
+        /// this machine
+        #[arg(long, default_value = rake::ssh::DEFAULT_BIND)]
+        bind: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Print a setting, like `speed` or `keys.up`
+    Get { key: String },
+    /// Change a setting, like `speed 7` or `keys.up w,Up`
+    Set { key: String, value: String },
+    /// Open the settings file in $VISUAL or $EDITOR
+    Edit,
+    /// Print where the settings file is
+    Path,
+}
+
+#[derive(Debug, Args)]
+pub struct GameArgs {
+    /// Settings file to use instead of the default one
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Named profile from the settings file to play with
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Board width in cells, border included
+    #[arg(long, global = true)]
+    pub width: Option<u16>,
+
+    /// Board height in cells, border included
+    #[arg(long, global = true)]
+    pub height: Option<u16>,
+
+    /// How fast the snake moves, from 1 to 10
+    #[arg(long, global = true)]
+    pub speed: Option<u8>,
+
+    /// Milliseconds per move instead of --speed, up to 10000 for a very
+    /// slow game
+    #[arg(long, global = true, value_name = "MS")]
+    pub tick_ms: Option<u64>,
+
+    /// Only move when a direction key is pressed, one cell per press
+    #[arg(long, global = true)]
+    pub turn_based: bool,
+
+    /// Wrap around the edges instead of dying on them
+    #[arg(long, global = true)]
+    pub wrap: bool,
+
+    /// Bounce off walls instead of dying on them, turning end to end
+    #[arg(long, global = true, conflicts_with = "wrap")]
+    pub bounce: bool,
+
+    /// How apples score: flat, speed (times how fast the game is), length
+    /// (times how long the snake is) or time (a bonus for eating quickly)
+    #[arg(long, global = true, value_name = "RULE")]
+    pub scoring: Option<Scoring>,
+
+    /// Where apples come out: random, clustered (near the last one), far
+    /// (from the snake), ring (round the middle) or sequence (the level's
+    /// apples in turn)
+    #[arg(long, global = true, value_name = "PATTERN")]
+    pub spawn_pattern: Option<SpawnPattern>,
+
+    /// Pass through your own body instead of dying on it; walls still kill
+    #[arg(long, global = true)]
+    pub casual: bool,
+
+    /// Play in rooms joined up by corridors, moving on to a new stage
+    /// once every room's had its apples
+    #[arg(long, global = true)]
+    pub rooms: bool,
+
+    /// Every few apples a bug comes out along the wall for a while, worth
+    /// a lot more the sooner it's eaten
+    #[arg(long, global = true)]
+    pub bugs: bool,
+
+    /// Five numbered apples at a time, to be eaten in order; one out of
+    /// order costs what it would have scored
+    #[arg(long, global = true)]
+    pub ordered: bool,
+
+    /// Apples worth up to nine times as much the sooner they're eaten,
+    /// what each is worth now shown next to it
+    #[arg(long, global = true)]
+    pub decay: bool,
+
+    /// A block that never goes away comes down every ten seconds, never
+    /// right in front of the snake
+    #[arg(long, global = true)]
+    pub obstacles: bool,
+
+    /// Move up and down every other tick, so the snake looks as fast in
+    /// every direction on tall terminal cells
+    #[arg(long, global = true)]
+    pub slow_vertical: bool,
+
+    /// Color scheme: classic, ocean or ember
+    #[arg(long, global = true)]
+    pub theme: Option<String>,
+
+    /// Snake skin, once unlocked (see `rake skins`)
+    #[arg(long, global = true)]
+    pub skin: Option<String>,
+
+    /// Seed apple spawns, for reproducible runs
+    #[arg(long, global = true)]
+    pub seed: Option<u64>,
+
+    /// Only draw plain ASCII characters
+    #[arg(long, global = true)]
+    pub ascii: bool,
+
+    /// Practice: take moves back with U, even after crashing. Practice
+    /// games aren't saved and don't count for high scores
+    #[arg(long, global = true)]
+    pub practice: bool,
+
+    /// For working on the game: one tick per key press, any key, with the
+    /// head, direction, length and grid occupancy shown under the board
+    #[arg(long, global = true)]
+    pub step_debug: bool,
+
+    /// For working on the game: ~ opens a console for spawning apples,
+    /// changing speed, teleporting and reseeding. Dev games aren't saved
+    /// and don't count for high scores
+    #[arg(long, global = true)]
+    pub dev: bool,
+
+    /// Write a JSON summary of the run here when it ends (`-` for stdout)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub json_results: Option<PathBuf>,
+
+    /// Write the final board here when the game ends: a PNG if the name
+    /// ends in .png, plain text otherwise (`-` for stdout)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub snapshot: Option<PathBuf>,
+
+    /// Play on a level: a TOML file of walls, where the snake starts,
+    /// portals, gates in the border, where apples come out, and ice and
+    /// mud zones that speed the snake up and slow it down
+    #[arg(long, global = true, value_name = "PATH")]
+    pub level: Option<PathBuf>,
+
+    /// Race the snake from a run saved with --json-results
+    #[arg(long, global = true, value_name = "PATH")]
+    pub ghost: Option<PathBuf>,
+
+    /// Keep the screen as still as possible: a one-color snake, no trail,
+    /// no banners and no bell, which some terminals flash for
+    #[arg(long, global = true)]
+    pub reduced_motion: bool,
+
+    /// Say what's happening a line at a time instead of drawing the board,
+    /// for screen readers
+    #[arg(long, global = true)]
+    pub screen_reader: bool,
+
+    /// Time the run to a tenth of a second, with a split every ten apples
+    #[arg(long, global = true)]
+    pub speedrun: bool,
+
+    /// Show how fast the game's going on the HUD, as a speed level and
+    /// milliseconds a move
+    #[arg(long, global = true)]
+    pub show_speed: bool,
+
+    /// Slide the head half a cell on between moves, for smoother movement
+    /// on a fast terminal
+    #[arg(long, global = true)]
+    pub smooth: bool,
+
+    /// Pause the game once nothing's been pressed for this many seconds,
+    /// after a countdown; 0 never does
+    #[arg(long, global = true, value_name = "SECS")]
+    pub idle_pause: Option<u64>,
+
+    /// Play the way an old Windows console needs: ASCII, steady, and only
+    /// redrawing what's changed. On by itself where one's detected
+    #[arg(long, global = true)]
+    pub legacy_console: bool,
+
+    /// Copy the score card to the clipboard when the game ends, for
+    /// terminals that allow it (OSC 52)
+    #[arg(long, global = true)]
+    pub copy_card: bool,
+
+    /// Language to play in: en, the code of a translation in the `lang`
+    /// directory next to the settings file, or a path to one
+    #[arg(long, global = true, value_name = "CODE")]
+    pub lang: Option<String>,
+
+    /// Draw everything in the terminal's default colors (also set by
+    /// NO_COLOR)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Lua script with custom rules
+    #[cfg(feature = "lua")]
+    #[arg(long, global = true)]
+    pub script: Option<PathBuf>,
+
+    /// Let a Twitch channel's chat steer by voting w, a, s or d each tick
+    #[arg(long, global = true, value_name = "CHANNEL")]
+    pub twitch: Option<String>,
+
+    /// A built-in AI to play instead of the keyboard, to sit back and
+    /// watch: greedy, bfs, astar or hamiltonian, weakest first
+    #[arg(long, global = true, value_name = "NAME")]
+    pub bot: Option<String>,
+
+    /// WebAssembly bot plugin to play instead of the keyboard
+    #[cfg(feature = "wasm")]
+    #[arg(long, global = true)]
+    pub plugin: Option<PathBuf>,
+}
+
+impl GameArgs {
+    /// Everything that decides how a game is set up, lowest priority
+    /// first: NO_COLOR, the settings file (and profile), RAKE_* variables
+    /// and then the command line.
+    pub fn settings(&self) -> Result<Settings> {
+        // no-color.org asks for config files and flags to win over NO_COLOR
+        let base = Settings {
+            color: no_color_env().then_some(false),
+            ..Settings::default()
+        };
+        let settings = base
+            .merge(self.file_settings()?)
+            .merge(Settings::from_env()?)
+            .merge(self.flag_settings());
+        // an old Windows console gets by best on ASCII and as little
+        // changing on screen as can be, unless something says otherwise
+        if settings.legacy_console() {
+            return Ok(Settings {
+                ascii: settings.ascii.or(Some(true)),
+                reduced_motion: settings.reduced_motion.or(Some(true)),
+                ..settings
+            });
+        }
+        Ok(settings)
+    }
+
+    /// Whatever was given on the command line.
+    pub fn flag_settings(&self) -> Settings {
+        Settings {
+            width: self.width,
+            height: self.height,
+            speed: self.speed,
+            tick_ms: self.tick_ms,
+            turn_based: self.turn_based.then_some(true),
+            theme: self.theme.clone(),
+            skin: self.skin.clone(),
+            mode: if self.bounce {
+                Some(WallMode::Bounce)
+            } else {
+                self.wrap.then_some(WallMode::Wrap)
+            },
+            scoring: self.scoring,
+            spawn_pattern: self.spawn_pattern,
+            slow_vertical: self.slow_vertical.then_some(true),
+            ascii: self.ascii.then_some(true),
+            color: self.no_color.then_some(false),
+            reduced_motion: self.reduced_motion.then_some(true),
+            screen_reader: self.screen_reader.then_some(true),
+            casual: self.casual.then_some(true),
+            rooms: self.rooms.then_some(true),
+            bugs: self.bugs.then_some(true),
+            ordered: self.ordered.then_some(true),
+            decay: self.decay.then_some(true),
+            obstacles: self.obstacles.then_some(true),
+            speedrun: self.speedrun.then_some(true),
+            show_speed: self.show_speed.then_some(true),
+            smooth: self.smooth.then_some(true),
+            idle_pause: self.idle_pause,
+            legacy_console: self.legacy_console.then_some(true),
+            copy_card: self.copy_card.then_some(true),
+            seed: self.seed,
+            lang: self.lang.clone(),
+            ..Settings::default()
+        }
+    }
+
+    /// Where the settings file is, whether or not it exists yet.
+    pub fn config_path(&self) -> Result<PathBuf> {
+        self.config
+            .clone()
+            .or_else(Settings::default_path)
+            .ok_or_else(|| {
+                RakeError::Config(
+                    "couldn't work out where the config file goes, pass --config".into(),
+                )
+            })
+    }
+
+    /// The settings file, or an empty one if there's nowhere to look, with
+    /// the chosen profile applied.
+    pub fn file_settings(&self) -> Result<Settings> {
+        let settings = match self.config.clone().or_else(Settings::default_path) {
+            Some(path) => Settings::load(&path)?,
+            None => Settings::default(),
+        };
+        match &self.profile {
+            Some(name) => settings.with_profile(name),
+            None => Ok(settings),
+        }
+    }
+}
+
+// https://no-color.org: set to anything but the empty string
+fn no_color_env() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
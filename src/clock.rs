@@ -0,0 +1,48 @@
+//! How long a run has taken, written out the way the HUD shows it.
+
+use std::fmt;
+use std::time;
+
+/// How many apples there are to a speedrun split.
+pub const SPLIT_APPLES: usize = 10;
+
+/// A length of time as `m:ss`, or `h:mm:ss` past the hour. With `tenths`
+/// there's a tenth of a second on the end, like `1:05.3`.
+///
+/// Writing one out doesn't allocate, so the HUD can show it every frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clock {
+    ms: u64,
+    tenths: bool,
+}
+
+impl Clock {
+    pub fn new(elapsed: time::Duration) -> Clock {
+        Clock::from_ms(elapsed.as_millis().try_into().unwrap_or(u64::MAX))
+    }
+
+    pub fn from_ms(ms: u64) -> Clock {
+        Clock { ms, tenths: false }
+    }
+
+    /// The same time down to a tenth of a second, for `--speedrun`.
+    pub fn tenths(self, tenths: bool) -> Clock {
+        Clock { tenths, ..self }
+    }
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.ms / 1000;
+        let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+        if hours > 0 {
+            write!(f, "{hours}:{minutes:02}:{:02}", seconds % 60)?;
+        } else {
+            write!(f, "{minutes}:{:02}", seconds % 60)?;
+        }
+        if self.tenths {
+            write!(f, ".{}", self.ms % 1000 / 100)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,732 @@
+use crate::game::Direction;
+use crate::grid::Coord;
+use crate::level::{Level, Portal, SPAWN_LENGTH, Spawn, Zone};
+use crate::theme::Theme;
+use crate::{RakeError, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time;
+
+/// What happens when the snake runs into the border.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WallMode {
+    /// The border is solid and hitting it ends the game.
+    #[default]
+    Fatal,
+    /// Leaving one side of the board comes back in on the opposite side.
+    Wrap,
+    /// Hitting a wall, the border or any other, turns the snake end to end
+    /// and it sits out a tick before heading off again.
+    Bounce,
+}
+
+/// What an apple turns out to be. They all sit on the board the same way
+/// and differ in what they're worth, set by `Foods`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Food {
+    #[default]
+    Cherry,
+    Banana,
+    Melon,
+}
+
+impl Food {
+    pub const ALL: [Food; 3] = [Food::Cherry, Food::Banana, Food::Melon];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Food::Cherry => "cherry",
+            Food::Banana => "banana",
+            Food::Melon => "melon",
+        }
+    }
+}
+
+/// What eating one food does, and how often it turns up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoodRule {
+    /// How many cells longer the snake gets.
+    pub growth: u16,
+    pub points: u16,
+    /// How likely it is to be the next apple, against the other foods'
+    /// weights. Zero for never.
+    pub weight: u32,
+}
+
+impl FoodRule {
+    pub const fn new(growth: u16, points: u16, weight: u32) -> FoodRule {
+        FoodRule {
+            growth,
+            points,
+            weight,
+        }
+    }
+}
+
+/// A `FoodRule` for every food.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Foods {
+    pub cherry: FoodRule,
+    pub banana: FoodRule,
+    pub melon: FoodRule,
+}
+
+impl Foods {
+    pub fn rule(&self, food: Food) -> FoodRule {
+        match food {
+            Food::Cherry => self.cherry,
+            Food::Banana => self.banana,
+            Food::Melon => self.melon,
+        }
+    }
+
+    pub fn rule_mut(&mut self, food: Food) -> &mut FoodRule {
+        match food {
+            Food::Cherry => &mut self.cherry,
+            Food::Banana => &mut self.banana,
+            Food::Melon => &mut self.melon,
+        }
+    }
+
+    // wide enough that adding up the weights can't overflow
+    fn total_weight(&self) -> u64 {
+        Food::ALL
+            .iter()
+            .map(|food| self.rule(*food).weight as u64)
+            .sum()
+    }
+
+    /// A food picked at random by weight. `GameConfig::validate` makes
+    /// sure there's something to pick.
+    pub fn pick(&self, rng: &mut impl Rng) -> Food {
+        let mut roll = rng.random_range(0..self.total_weight().max(1));
+        for food in Food::ALL {
+            let weight = self.rule(food).weight as u64;
+            if roll < weight {
+                return food;
+            }
+            roll -= weight;
+        }
+        Food::Cherry
+    }
+}
+
+impl Default for Foods {
+    fn default() -> Foods {
+        Foods {
+            cherry: FoodRule::new(1, 1, 10),
+            banana: FoodRule::new(2, 2, 4),
+            melon: FoodRule::new(3, 3, 1),
+        }
+    }
+}
+
+/// How eating turns into points. Scores under one rule don't compare with
+/// scores under another, so each has high scores of its own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scoring {
+    /// Each food's points and nothing else.
+    #[default]
+    Flat,
+    /// Points times how fast the game goes: one for a tick of 200ms or
+    /// slower, two at the default 100ms, and six at `--speed 10`.
+    Speed,
+    /// Points times one more than the snake's length in tens, so the
+    /// longer it gets the more each apple's worth.
+    Length,
+    /// Points and a bonus point for every whole second under five it took
+    /// to get there since the last one (or the start).
+    Time,
+}
+
+// the tick that scores plain points under `Scoring::Speed`, each time as
+// fast again adding as many more, and how long counts as quick under
+// `Scoring::Time`
+const SPEED_BASE: time::Duration = time::Duration::from_millis(200);
+const TIME_BONUS_SECS: u64 = 5;
+
+impl Scoring {
+    pub const ALL: [Scoring; 4] = [
+        Scoring::Flat,
+        Scoring::Speed,
+        Scoring::Length,
+        Scoring::Time,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scoring::Flat => "flat",
+            Scoring::Speed => "speed",
+            Scoring::Length => "length",
+            Scoring::Time => "time",
+        }
+    }
+
+    /// What a food worth `points` scores on `config`, eaten by a snake
+    /// `length` cells long `since` the last apple was eaten. Time is game
+    /// time, ticks at `tick_rate`, so it's the same played back.
+    pub fn score(self, points: u16, config: &GameConfig, length: usize, since: u64) -> u16 {
+        let (tick_ms, base_ms) = (config.tick_rate.as_millis(), SPEED_BASE.as_millis());
+        let (times, plus) = match self {
+            Scoring::Flat => (1, 0),
+            Scoring::Speed => (((base_ms + tick_ms / 2) / tick_ms).max(1) as u16, 0),
+            Scoring::Length => ((length / 10 + 1).min(u16::MAX as usize) as u16, 0),
+            Scoring::Time => {
+                let seconds = (since as u128 * tick_ms / 1000) as u64;
+                (1, TIME_BONUS_SECS.saturating_sub(seconds) as u16)
+            }
+        };
+        points.saturating_mul(times).saturating_add(plus)
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = RakeError;
+
+    fn from_str(name: &str) -> Result<Scoring> {
+        Scoring::ALL
+            .into_iter()
+            .find(|scoring| scoring.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Scoring::ALL.iter().map(|scoring| scoring.name()).collect();
+                RakeError::Config(format!(
+                    "unknown scoring {name:?}, pick one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// Where apples come out. Whatever the pattern, an apple goes anywhere
+/// free when there's nowhere free that fits it. See `spawning`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpawnPattern {
+    /// Anywhere free, all equally likely.
+    #[default]
+    Random,
+    /// Close to wherever the last apple came out.
+    Clustered,
+    /// As far from the snake's head as it can find.
+    Far,
+    /// Around a ring halfway between the border and the middle.
+    Ring,
+    /// On the level's `apples` cells, one after the other, starting over
+    /// at the end.
+    Sequence,
+}
+
+impl SpawnPattern {
+    pub const ALL: [SpawnPattern; 5] = [
+        SpawnPattern::Random,
+        SpawnPattern::Clustered,
+        SpawnPattern::Far,
+        SpawnPattern::Ring,
+        SpawnPattern::Sequence,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SpawnPattern::Random => "random",
+            SpawnPattern::Clustered => "clustered",
+            SpawnPattern::Far => "far",
+            SpawnPattern::Ring => "ring",
+            SpawnPattern::Sequence => "sequence",
+        }
+    }
+}
+
+impl FromStr for SpawnPattern {
+    type Err = RakeError;
+
+    fn from_str(name: &str) -> Result<SpawnPattern> {
+        SpawnPattern::ALL
+            .into_iter()
+            .find(|pattern| pattern.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = SpawnPattern::ALL
+                    .iter()
+                    .map(|pattern| pattern.name())
+                    .collect();
+                RakeError::Config(format!(
+                    "unknown spawn pattern {name:?}, pick one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// Tick intervals for `--speed 1` through `--speed 10`.
+const SPEED_LEVELS: [u64; 10] = [250, 200, 160, 130, 100, 85, 70, 55, 45, 35];
+
+/// How long a tick lasts at speed `level`, from 1 (slowest) to 10.
+pub fn speed_level(level: u8) -> Result<time::Duration> {
+    match SPEED_LEVELS.get((level as usize).wrapping_sub(1)) {
+        Some(ms) => Ok(time::Duration::from_millis(*ms)),
+        None => Err(RakeError::Config(format!(
+            "speed must be between 1 and {}, got {level}",
+            SPEED_LEVELS.len()
+        ))),
+    }
+}
+
+/// The `--speed` level a tick of `tick_rate` is closest to, from 1 to 10,
+/// for telling at a glance how fast a game is going whatever set it.
+pub fn speed_tier(tick_rate: time::Duration) -> u8 {
+    let ms = tick_rate.as_millis();
+    let closest = SPEED_LEVELS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, level)| ms.abs_diff(u128::from(**level)))
+        .map_or(0, |(i, _)| i);
+    closest as u8 + 1
+}
+
+/// The slowest a game can go, which is slow enough for anyone short of
+/// `--turn-based`.
+pub const MAX_TICK_RATE: time::Duration = time::Duration::from_secs(10);
+
+// the snake spawns a third of the way in and three cells long, and the
+// board needs some room to play in around it
+const MIN_WIDTH: u16 = 10;
+const MIN_HEIGHT: u16 = 5;
+// coordinates are i16, and nobody has a terminal this big anyway
+const MAX_SIZE: u16 = 1000;
+
+/// Everything needed to set up a game. Build one with
+/// `GameConfig::builder()`, or use `GameConfig::default()`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub width: u16,
+    pub height: u16,
+    pub tick_rate: time::Duration,
+    pub wall_mode: WallMode,
+    pub apples: usize,
+    /// Moves up and down only every other tick. Terminal cells are about
+    /// twice as tall as they're wide, so this makes the snake look as fast
+    /// going up as going across.
+    #[serde(default)]
+    pub slow_vertical: bool,
+    pub theme: Theme,
+    pub seed: Option<u64>,
+    /// Ice and mud, from a level.
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+    /// Walls inside the border, from a level.
+    #[serde(default)]
+    pub walls: Vec<Coord>,
+    /// Where a level has the snake start, instead of `GameConfig::spawn`'s
+    /// usual place.
+    #[serde(default)]
+    pub spawn: Option<Spawn>,
+    #[serde(default)]
+    pub portals: Vec<Portal>,
+    /// Gaps in the border, from a level, each one leading out through the
+    /// gap straight across from it. The rest of the border is whatever
+    /// the wall mode makes it.
+    #[serde(default)]
+    pub gates: Vec<Coord>,
+    #[serde(default)]
+    pub foods: Foods,
+    #[serde(default)]
+    pub scoring: Scoring,
+    /// The snake passes through itself instead of dying on it. Walls are
+    /// as fatal as ever.
+    #[serde(default)]
+    pub casual: bool,
+    /// Rooms and corridors, laid out afresh for each stage, with the
+    /// stage's walls and spawn going in `walls` and `spawn`. See `rooms`.
+    #[serde(default)]
+    pub rooms: bool,
+    /// Every few apples a bonus bug comes out for a while. See `bug`.
+    #[serde(default)]
+    pub bugs: bool,
+    /// Apples come out numbered, five at a time, to be eaten in order.
+    /// One eaten out of order costs what it would have scored, and
+    /// `apples` goes unused.
+    #[serde(default)]
+    pub ordered: bool,
+    /// Apples are worth more the sooner they're eaten, starting out at
+    /// `game::DECAY_START` times their points and going down to just their
+    /// points, with what they're worth now shown next to them.
+    #[serde(default)]
+    pub decay: bool,
+    /// A block goes down on a free cell every `game::OBSTACLE_TICKS` and
+    /// stays there, never right in front of the snake, so the board fills
+    /// up the longer the run goes.
+    #[serde(default)]
+    pub obstacles: bool,
+    #[serde(default)]
+    pub spawn_pattern: SpawnPattern,
+    /// The cells `SpawnPattern::Sequence` puts apples on, in order, from a
+    /// level.
+    #[serde(default)]
+    pub apple_cells: Vec<Coord>,
+}
+
+impl GameConfig {
+    pub fn builder() -> GameConfigBuilder {
+        GameConfigBuilder::default()
+    }
+
+    /// Where the snake starts: the level's spawn, or a third of the way
+    /// across and halfway down, heading right.
+    pub fn spawn(&self) -> Spawn {
+        self.spawn.unwrap_or(Spawn {
+            at: [self.width as i16 / 3, self.height as i16 / 2],
+            direction: Direction::Right,
+        })
+    }
+
+    /// Whether `cell` is a gap in the border: one of the gates, or the
+    /// cell across from one.
+    pub fn is_gate(&self, cell: Coord) -> bool {
+        self.gates
+            .iter()
+            .any(|gate| *gate == cell || self.across(*gate) == cell)
+    }
+
+    /// The border cell on the opposite side from `cell`, in line with it.
+    /// Anything not on the border stays where it is.
+    pub fn across(&self, [x, y]: Coord) -> Coord {
+        let (right, bottom) = (self.width as i16 - 1, self.height as i16 - 1);
+        match (x, y) {
+            (0, _) => [right, y],
+            (_, 0) => [x, bottom],
+            _ if x == right => [0, y],
+            _ if y == bottom => [x, 0],
+            _ => [x, y],
+        }
+    }
+
+    /// Checks the board can actually be played on. The builder calls this,
+    /// but anything deserialized needs to go through it too.
+    pub fn validate(&self) -> Result<()> {
+        if self.width < MIN_WIDTH || self.height < MIN_HEIGHT {
+            return Err(RakeError::Config(format!(
+                "board must be at least {MIN_WIDTH}x{MIN_HEIGHT}, got {}x{}",
+                self.width, self.height
+            )));
+        }
+        if self.width > MAX_SIZE || self.height > MAX_SIZE {
+            return Err(RakeError::Config(format!(
+                "board can be at most {MAX_SIZE}x{MAX_SIZE}, got {}x{}",
+                self.width, self.height
+            )));
+        }
+        if self.tick_rate.is_zero() {
+            return Err(RakeError::Config("tick rate must be above zero".into()));
+        }
+        if self.tick_rate > MAX_TICK_RATE {
+            return Err(RakeError::Config(format!(
+                "tick rate can be at most {}s, got {:?}",
+                MAX_TICK_RATE.as_secs(),
+                self.tick_rate
+            )));
+        }
+        if self.foods.total_weight() == 0 {
+            return Err(RakeError::Config(
+                "at least one food needs a weight above zero".into(),
+            ));
+        }
+        for zone in &self.zones {
+            let [x, y] = zone.at;
+            let [width, height] = zone.size;
+            // one past the far corner, which can be on the border but
+            // no further
+            let (right, bottom) = (x as i32 + width as i32, y as i32 + height as i32);
+            let inside =
+                x >= 1 && y >= 1 && right < self.width as i32 && bottom < self.height as i32;
+            if !inside {
+                return Err(RakeError::Config(format!(
+                    "a {width}x{height} zone at {x},{y} doesn't fit inside the border of a {}x{} board",
+                    self.width, self.height
+                )));
+            }
+        }
+        self.validate_layout()?;
+        // leave at least as much room as the snake starts with
+        let ends = self.portals.len() * 2;
+        let interior = (self.width as usize - 2) * (self.height as usize - 2)
+            - self.walls.len()
+            - ends
+            - SPAWN_LENGTH;
+        if self.apples == 0 || self.apples > interior {
+            return Err(RakeError::Config(format!(
+                "apple count must be between 1 and {interior}, got {}",
+                self.apples
+            )));
+        }
+        Ok(())
+    }
+
+    // walls, portals and the snake's spawn all inside the border and not
+    // on top of each other, and gates along the border
+    fn validate_layout(&self) -> Result<()> {
+        let (width, height) = (self.width as i16, self.height as i16);
+        let inside = |[x, y]: Coord| x >= 1 && y >= 1 && x < width - 1 && y < height - 1;
+        let mut taken: Vec<Coord> = vec![];
+        let cells = self
+            .spawn()
+            .cells()
+            .map(|cell| ("the snake", cell))
+            .chain(self.walls.iter().map(|cell| ("a wall", *cell)))
+            .chain(
+                self.portals
+                    .iter()
+                    .flat_map(|portal| portal.ends.map(|cell| ("a portal", cell))),
+            );
+        for (what, cell @ [x, y]) in cells {
+            if !inside(cell) {
+                return Err(RakeError::Config(format!(
+                    "{what} at {x},{y} isn't inside the border of a {}x{} board",
+                    self.width, self.height
+                )));
+            }
+            if taken.contains(&cell) {
+                return Err(RakeError::Config(format!(
+                    "{what} at {x},{y} is on top of something else"
+                )));
+            }
+            taken.push(cell);
+        }
+        for &cell @ [x, y] in &self.apple_cells {
+            if !inside(cell) || self.walls.contains(&cell) {
+                return Err(RakeError::Config(format!(
+                    "an apple at {x},{y} isn't inside the border of a {}x{} board, or is on a wall",
+                    self.width, self.height
+                )));
+            }
+        }
+        if self.spawn_pattern == SpawnPattern::Sequence && self.apple_cells.is_empty() {
+            return Err(RakeError::Config(
+                "the sequence spawn pattern needs a level with apples listed".into(),
+            ));
+        }
+        if self.rooms && !(self.portals.is_empty() && self.gates.is_empty()) {
+            return Err(RakeError::Config(
+                "rooms can't have portals or gates, they lay the board out themselves".into(),
+            ));
+        }
+        let (right, bottom) = (width - 1, height - 1);
+        for &[x, y] in &self.gates {
+            let across = x == 0 || x == right;
+            let down = y == 0 || y == bottom;
+            // on one side or the other, but not both, which is a corner
+            let on_border = (0..width).contains(&x) && (0..height).contains(&y) && across != down;
+            if !on_border {
+                return Err(RakeError::Config(format!(
+                    "a gate at {x},{y} isn't on the border of a {}x{} board, or is in a corner",
+                    self.width, self.height
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            width: 40,
+            height: 15,
+            tick_rate: time::Duration::from_millis(100),
+            wall_mode: WallMode::Fatal,
+            apples: 1,
+            slow_vertical: false,
+            theme: Theme::default(),
+            seed: None,
+            zones: vec![],
+            walls: vec![],
+            spawn: None,
+            portals: vec![],
+            gates: vec![],
+            foods: Foods::default(),
+            scoring: Scoring::Flat,
+            casual: false,
+            rooms: false,
+            bugs: false,
+            ordered: false,
+            decay: false,
+            obstacles: false,
+            spawn_pattern: SpawnPattern::Random,
+            apple_cells: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GameConfigBuilder {
+    config: GameConfig,
+}
+
+impl GameConfigBuilder {
+    /// Board size in cells, border included.
+    pub fn size(mut self, width: u16, height: u16) -> GameConfigBuilder {
+        self.config.width = width;
+        self.config.height = height;
+        self
+    }
+
+    pub fn width(mut self, width: u16) -> GameConfigBuilder {
+        self.config.width = width;
+        self
+    }
+
+    pub fn height(mut self, height: u16) -> GameConfigBuilder {
+        self.config.height = height;
+        self
+    }
+
+    /// How long one simulation step lasts.
+    pub fn tick_rate(mut self, tick_rate: time::Duration) -> GameConfigBuilder {
+        self.config.tick_rate = tick_rate;
+        self
+    }
+
+    pub fn wall_mode(mut self, wall_mode: WallMode) -> GameConfigBuilder {
+        self.config.wall_mode = wall_mode;
+        self
+    }
+
+    /// How many apples are on the board at once.
+    pub fn apples(mut self, apples: usize) -> GameConfigBuilder {
+        self.config.apples = apples;
+        self
+    }
+
+    /// How eating turns into points.
+    pub fn scoring(mut self, scoring: Scoring) -> GameConfigBuilder {
+        self.config.scoring = scoring;
+        self
+    }
+
+    /// See `GameConfig::casual`.
+    pub fn casual(mut self, casual: bool) -> GameConfigBuilder {
+        self.config.casual = casual;
+        self
+    }
+
+    /// See `GameConfig::rooms`.
+    pub fn rooms(mut self, rooms: bool) -> GameConfigBuilder {
+        self.config.rooms = rooms;
+        self
+    }
+
+    /// See `GameConfig::bugs`.
+    pub fn bugs(mut self, bugs: bool) -> GameConfigBuilder {
+        self.config.bugs = bugs;
+        self
+    }
+
+    /// See `GameConfig::ordered`.
+    pub fn ordered(mut self, ordered: bool) -> GameConfigBuilder {
+        self.config.ordered = ordered;
+        self
+    }
+
+    /// See `GameConfig::decay`.
+    pub fn decay(mut self, decay: bool) -> GameConfigBuilder {
+        self.config.decay = decay;
+        self
+    }
+
+    /// See `GameConfig::obstacles`.
+    pub fn obstacles(mut self, obstacles: bool) -> GameConfigBuilder {
+        self.config.obstacles = obstacles;
+        self
+    }
+
+    pub fn spawn_pattern(mut self, spawn_pattern: SpawnPattern) -> GameConfigBuilder {
+        self.config.spawn_pattern = spawn_pattern;
+        self
+    }
+
+    /// See `GameConfig::slow_vertical`.
+    pub fn slow_vertical(mut self, slow_vertical: bool) -> GameConfigBuilder {
+        self.config.slow_vertical = slow_vertical;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> GameConfigBuilder {
+        self.config.theme = theme;
+        self
+    }
+
+    /// Makes apple spawns reproducible. Without a seed every game is
+    /// different.
+    pub fn seed(mut self, seed: u64) -> GameConfigBuilder {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Where the ice and mud go, say from `Level::load`.
+    pub fn zones(mut self, zones: Vec<Zone>) -> GameConfigBuilder {
+        self.config.zones = zones;
+        self
+    }
+
+    /// Lays the board out like `level`: walls, spawn, portals, gates,
+    /// zones, and where apples come out.
+    pub fn level(mut self, level: Level) -> GameConfigBuilder {
+        level.apply(&mut self.config);
+        self
+    }
+
+    /// What each food is worth and how often it turns up.
+    pub fn foods(mut self, foods: Foods) -> GameConfigBuilder {
+        self.config.foods = foods;
+        self
+    }
+
+    pub fn build(self) -> Result<GameConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticking(ms: u64) -> GameConfig {
+        GameConfig::builder()
+            .tick_rate(time::Duration::from_millis(ms))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn flat_scoring_is_just_the_points() {
+        assert_eq!(Scoring::Flat.score(3, &ticking(35), 80, 0), 3);
+    }
+
+    #[test]
+    fn speed_scoring_goes_up_with_the_speed() {
+        let score = |ms| Scoring::Speed.score(1, &ticking(ms), 3, 0);
+        assert_eq!(score(200), 1);
+        assert_eq!(score(100), 2);
+        assert_eq!(score(speed_level(10).unwrap().as_millis() as u64), 6);
+        // slower than the base is never worth less than the points
+        assert_eq!(score(1000), 1);
+        assert_eq!(Scoring::Speed.score(u16::MAX, &ticking(35), 3, 0), u16::MAX);
+    }
+
+    #[test]
+    fn length_scoring_goes_up_every_ten_cells() {
+        let score = |length| Scoring::Length.score(2, &ticking(100), length, 0);
+        assert_eq!([score(3), score(9), score(10), score(25)], [2, 2, 4, 6]);
+    }
+
+    #[test]
+    fn time_scoring_pays_for_being_quick() {
+        // ticks of 100ms, so ten to the second
+        let score = |since| Scoring::Time.score(1, &ticking(100), 3, since);
+        assert_eq!([score(0), score(9), score(20), score(49)], [6, 6, 4, 2]);
+        assert_eq!([score(50), score(10_000)], [1, 1]);
+    }
+}
@@ -0,0 +1,148 @@
+//! A drop-down console for poking at a game while it's paused, with
+//! `--dev`.
+//!
+//! ```text
+//! spawn apple 5 7    an extra apple at column 5, row 7 (a cherry, or say
+//!                    `spawn melon 5 7`)
+//! speed 50           50 milliseconds a tick
+//! teleport 10 3      the snake's head to column 10, row 3, body and all
+//! seed 42            apples from seed 42 from here on
+//! ```
+
+use crate::Result;
+use crate::config::{Food, MAX_TICK_RATE};
+use crate::game::Game;
+use crate::grid::Coord;
+use crate::render::Renderer;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use std::time;
+
+/// Whether `event` opens or closes the console.
+pub fn is_toggle(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('~'),
+            kind: KeyEventKind::Press,
+            ..
+        })
+    )
+}
+
+/// What's been typed so far, and what the last command had to say.
+#[derive(Clone, Debug)]
+pub struct Console {
+    line: String,
+    output: String,
+}
+
+impl Console {
+    pub fn new() -> Console {
+        Console {
+            line: String::new(),
+            output: "spawn apple X Y, speed MS, teleport X Y, seed N".into(),
+        }
+    }
+
+    /// Takes a key press. Enter runs the line on `game`. Returns `false`
+    /// once the console's been closed, with Esc or `~`.
+    pub fn key(&mut self, key: KeyEvent, game: &mut Game) -> bool {
+        if key.kind != KeyEventKind::Press {
+            return true;
+        }
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('~') => return false,
+            KeyCode::Enter => {
+                let line = std::mem::take(&mut self.line);
+                self.output = match run(&line, game) {
+                    Ok(done) => done,
+                    Err(complaint) => complaint,
+                };
+            }
+            KeyCode::Backspace => {
+                self.line.pop();
+            }
+            KeyCode::Char(c) => self.line.push(c),
+            _ => {}
+        }
+        true
+    }
+
+    /// Draws the console over the top two rows of `game`'s board: the
+    /// last output and then the line being typed.
+    pub fn draw(&self, renderer: &mut impl Renderer, game: &Game) -> Result<()> {
+        let width = game.width() as usize;
+        let color = game.config().theme.text;
+        let prompt = format!("> {}_", self.line);
+        // the end of a long line, since that's where the typing is
+        let skip = prompt.chars().count().saturating_sub(width);
+        let prompt: String = prompt.chars().skip(skip).collect();
+        let output = &self.output;
+        renderer.draw_text(0, 0, &format!("{output:<width$.width$}"), color)?;
+        renderer.draw_text(0, 1, &format!("{prompt:<width$}"), color)?;
+        Ok(())
+    }
+}
+
+impl Default for Console {
+    fn default() -> Console {
+        Console::new()
+    }
+}
+
+/// Runs one console command on `game`, saying what it did or what was
+/// wrong with it.
+pub fn run(line: &str, game: &mut Game) -> std::result::Result<String, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        [] => Ok(String::new()),
+        ["spawn", food, x, y] => {
+            let food = match *food {
+                "apple" => Food::Cherry,
+                name => Food::ALL
+                    .into_iter()
+                    .find(|food| food.name() == name)
+                    .ok_or_else(|| format!("there's no food called {name:?}"))?,
+            };
+            let at = coord(x, y)?;
+            if game.spawn_apple(at, food) {
+                Ok(format!("{} at {},{}", food.name(), at[0], at[1]))
+            } else {
+                Err(format!("{},{} isn't empty", at[0], at[1]))
+            }
+        }
+        ["speed", ms] => {
+            let ms: u64 = number(ms)?;
+            let rate = time::Duration::from_millis(ms);
+            if ms == 0 || rate > MAX_TICK_RATE {
+                let most = MAX_TICK_RATE.as_millis();
+                return Err(format!("speed is 1 to {most} ms, got {ms}"));
+            }
+            game.set_tick_rate(rate);
+            Ok(format!("{ms} ms a tick"))
+        }
+        ["teleport", x, y] => {
+            let at = coord(x, y)?;
+            if game.teleport(at) {
+                Ok(format!("head at {},{}", at[0], at[1]))
+            } else {
+                Err(format!("the snake doesn't fit at {},{}", at[0], at[1]))
+            }
+        }
+        ["seed", seed] => {
+            let seed = number(seed)?;
+            game.reseed(seed);
+            Ok(format!("seeded with {seed}"))
+        }
+        _ => Err(format!("unknown command {line:?}")),
+    }
+}
+
+fn coord(x: &str, y: &str) -> std::result::Result<Coord, String> {
+    Ok([number(x)?, number(y)?])
+}
+
+fn number<T: std::str::FromStr>(word: &str) -> std::result::Result<T, String> {
+    word.parse()
+        .map_err(|_| format!("{word:?} isn't a number that fits"))
+}
@@ -0,0 +1,102 @@
+//! Two snakes on one keyboard, playing for the same score.
+
+use crate::arena::Arena;
+use crate::config::GameConfig;
+use crate::game::Direction;
+use crate::lang::Lang;
+use crate::render::Renderer;
+use crate::{RakeError, Result};
+use std::sync::Arc;
+
+/// When a co-op game is over.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CoopEnd {
+    /// Once both snakes are dead.
+    #[default]
+    Both,
+    /// As soon as either snake dies.
+    Either,
+}
+
+pub struct Coop {
+    arena: Arena,
+    end: CoopEnd,
+    lang: Arc<Lang>,
+}
+
+impl Coop {
+    /// With `friendly_fire` the snakes crash into each other like they do
+    /// into themselves; without it they slide through one another.
+    pub fn new(config: GameConfig, end: CoopEnd, friendly_fire: bool) -> Result<Coop> {
+        let mut arena = Arena::new(config);
+        arena.set_friendly_fire(friendly_fire);
+        for name in ["1", "2"] {
+            if arena.add_player(name).is_none() {
+                return Err(RakeError::Config(
+                    "the board is too small for two snakes".into(),
+                ));
+            }
+        }
+        Ok(Coop {
+            arena,
+            end,
+            lang: Lang::english(),
+        })
+    }
+
+    /// What the score line is written in.
+    pub fn set_lang(&mut self, lang: Arc<Lang>) {
+        self.lang = lang;
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        self.arena.config()
+    }
+
+    /// `player` is 0 or 1.
+    pub fn turn(&mut self, player: usize, direction: Direction) {
+        self.arena.turn(player, direction);
+    }
+
+    pub fn tick(&mut self) {
+        self.arena.tick();
+    }
+
+    /// Both snakes' apples together.
+    pub fn score(&self) -> u16 {
+        self.arena.view().players.iter().map(|p| p.score).sum()
+    }
+
+    pub fn is_over(&self) -> bool {
+        match self.end {
+            CoopEnd::Both => self.arena.alive() == 0,
+            CoopEnd::Either => self.arena.alive() < 2,
+        }
+    }
+
+    /// Draws the whole board, player one in the theme's colors, and the
+    /// shared score under it.
+    pub fn draw(&self, renderer: &mut impl Renderer) -> Result<()> {
+        let view = self.arena.view();
+        let theme = &self.config().theme;
+        view.draw_board(renderer, theme, Some(0))?;
+
+        let width = view.width as usize;
+        let lang = &self.lang;
+        let left = lang.format("hud-score", &[("score", &self.score())]);
+        let help = [
+            lang.format("hud-coop-help", &[("quit", &"ESC")]),
+            lang.format("hud-help-exit", &[("quit", &"ESC")]),
+        ];
+        let left_len = left.chars().count();
+        let line = match help
+            .iter()
+            .find(|right| left_len + 1 + right.chars().count() <= width)
+        {
+            Some(right) => format!("{left:<0$}{right}", width - right.chars().count()),
+            None => format!("{left:<width$.width$}"),
+        };
+        renderer.draw_text(0, view.height, &line, theme.text)?;
+        Ok(())
+    }
+}
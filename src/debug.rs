@@ -0,0 +1,36 @@
+//! What's going on inside a game, for `--step-debug`.
+//!
+//! Shown under the board a tick at a time, so a change to movement or
+//! collisions can be watched doing the wrong thing. The occupancy counts
+//! come straight from the grid, and a snake cell count that doesn't match
+//! the length is a grid gone out of sync.
+
+use crate::game::{Direction, Game};
+use crate::grid::Tile;
+use rand::Rng;
+
+/// How many lines `overlay` takes.
+pub const OVERLAY_LINES: u16 = 3;
+
+pub fn overlay<R: Rng>(game: &Game<R>) -> [String; OVERLAY_LINES as usize] {
+    let snake = game.snake();
+    let [x, y] = snake.head();
+    let direction = match snake.direction() {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    };
+    let grid = game.grid();
+    let [wall, body, apple, empty] =
+        [Tile::Wall, Tile::Snake, Tile::Apple, Tile::Empty].map(|tile| grid.count(tile));
+    [
+        format!(
+            "tick {}  head {x},{y} {direction}  length {}",
+            game.ticks(),
+            snake.len()
+        ),
+        format!("used {}  free {empty}", wall + body + apple),
+        format!("wall {wall}  snake {body}  apple {apple}"),
+    ]
+}
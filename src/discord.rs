@@ -0,0 +1,126 @@
+//! Discord Rich Presence, behind the `discord` feature: the board being
+//! played, the score and how long the run's been going, on your Discord
+//! profile while a game's on. Nothing is sent unless `discord.client_id`
+//! is set to the id of a Discord application of your own, which is what
+//! the presence shows up as.
+//!
+//! Discord listens on a local socket, `discord-ipc-0` in the runtime or
+//! temp directory (a named pipe on Windows), for frames of a little endian
+//! opcode and length followed by that many bytes of JSON. The presence
+//! goes when the connection does, so there's nothing to clear at the end.
+
+use crate::game::Game;
+use crate::lang::Lang;
+use crate::scores::board_key;
+use crate::{RakeError, Result};
+use serde_json::{Value, json};
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time;
+
+// frame opcodes
+const HANDSHAKE: u32 = 0;
+const FRAME: u32 = 1;
+
+// Discord tries each of these in turn, for when there's more than one
+// client running
+const PIPES: u32 = 10;
+
+#[cfg(unix)]
+type Pipe = std::os::unix::net::UnixStream;
+#[cfg(not(unix))]
+type Pipe = std::fs::File;
+
+/// A connection to the Discord client, keeping the presence up to date.
+pub struct Presence {
+    pipe: Pipe,
+    // the score last shown, so nothing's sent until it changes
+    score: Option<u16>,
+    nonce: u64,
+}
+
+impl Presence {
+    /// Connects to the Discord client running on this machine as the
+    /// application `client_id`.
+    pub fn connect(client_id: &str) -> Result<Presence> {
+        let mut pipe = (0..PIPES)
+            .find_map(|n| open(n).ok())
+            .ok_or_else(|| RakeError::Connection("couldn't find Discord running".into()))?;
+        // whatever comes back, the ready message and a reply to every
+        // update, is only read so it can't fill the socket up
+        let mut replies = pipe.try_clone().map_err(discord_error)?;
+        thread::spawn(move || {
+            let mut buffer = [0; 1024];
+            while matches!(replies.read(&mut buffer), Ok(read) if read > 0) {}
+        });
+        send(
+            &mut pipe,
+            HANDSHAKE,
+            &json!({ "v": 1, "client_id": client_id }),
+        )?;
+        Ok(Presence {
+            pipe,
+            score: None,
+            nonce: 0,
+        })
+    }
+
+    /// Shows `game`'s board and score, in `lang`, and how long it's been
+    /// going. Only sends anything when the score's changed since last time.
+    pub fn update(&mut self, game: &Game, lang: &Lang) -> Result<()> {
+        if self.score == Some(game.score()) {
+            return Ok(());
+        }
+        self.score = Some(game.score());
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap_or_default();
+        // Discord counts up from whenever the run would have started
+        let start = now.saturating_sub(game.elapsed()).as_secs();
+        let state = lang.format("discord-state", &[("score", &game.score())]);
+        self.nonce += 1;
+        let activity = json!({
+            "cmd": "SET_ACTIVITY",
+            "args": {
+                "pid": std::process::id(),
+                "activity": {
+                    "details": board_key(game.config()),
+                    "state": state,
+                    "timestamps": { "start": start },
+                },
+            },
+            "nonce": self.nonce.to_string(),
+        });
+        send(&mut self.pipe, FRAME, &activity)
+    }
+}
+
+fn send(pipe: &mut Pipe, opcode: u32, payload: &Value) -> Result<()> {
+    let payload = payload.to_string();
+    let mut frame = Vec::with_capacity(8 + payload.len());
+    frame.extend_from_slice(&opcode.to_le_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload.as_bytes());
+    pipe.write_all(&frame).map_err(discord_error)
+}
+
+#[cfg(unix)]
+fn open(n: u32) -> io::Result<Pipe> {
+    let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .into_iter()
+        .find_map(|name| std::env::var_os(name).filter(|dir| !dir.is_empty()))
+        .unwrap_or_else(|| "/tmp".into());
+    Pipe::connect(std::path::Path::new(&dir).join(format!("discord-ipc-{n}")))
+}
+
+#[cfg(not(unix))]
+fn open(n: u32) -> io::Result<Pipe> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(format!(r"\\?\pipe\discord-ipc-{n}"))
+}
+
+fn discord_error(e: io::Error) -> RakeError {
+    RakeError::Connection(format!("lost touch with Discord: {e}"))
+}
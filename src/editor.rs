@@ -0,0 +1,350 @@
+//! `rake edit`: laying a level out on the board with the cursor keys, and
+//! saving it as a level file for `--level`.
+//!
+//! Everything goes wherever the cursor is: walls a cell at a time, where
+//! the snake starts, and portals and zones over two presses, one for each
+//! end of a portal or corner of a zone. Gates go in the border next to the
+//! cursor, along with the one across from it. Saving checks the level can be
+//! played, which includes the snake being able to get to every cell that
+//! isn't a wall.
+
+use crate::config::GameConfig;
+use crate::game::{self, Direction};
+use crate::grid::{Coord, Ground, Tile};
+use crate::lang::Lang;
+use crate::level::{self, Level, Portal, Spawn, Zone};
+use crate::render::Renderer;
+use crate::{RakeError, Result};
+use crossterm::style::Color;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How many rows the editor takes up under the board.
+pub const STATUS_LINES: u16 = 3;
+
+// behind the cell under the cursor, and the first end or corner of
+// whatever's halfway placed
+const CURSOR: Color = Color::Grey;
+const MARK: Color = Color::DarkGrey;
+
+// the first end of a portal or corner of a zone, waiting on the second
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pending {
+    Portal(Coord),
+    Zone(Ground, Coord),
+}
+
+/// A level being edited, on the board it's going to be played on.
+pub struct Editor {
+    config: GameConfig,
+    lang: Arc<Lang>,
+    cursor: Coord,
+    pending: Option<Pending>,
+    // said under the board until the next thing that happens
+    message: String,
+    // whether there's anything that isn't in the file yet
+    changed: bool,
+}
+
+impl Editor {
+    /// Edits the level `config` is laid out with, which has to be one
+    /// that passes `GameConfig::validate`.
+    pub fn new(config: GameConfig, lang: Arc<Lang>) -> Editor {
+        Editor {
+            cursor: config.spawn().at,
+            config,
+            lang,
+            pending: None,
+            message: String::new(),
+            changed: false,
+        }
+    }
+
+    /// The board with the level as it stands.
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn level(&self) -> Level {
+        Level::of(&self.config)
+    }
+
+    /// Whether there's been anything done since the last save.
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Puts `message` under the board until the next key.
+    pub fn say(&mut self, message: String) {
+        self.message = message;
+    }
+
+    /// Moves the cursor a cell, as far as the border.
+    pub fn move_cursor(&mut self, direction: Direction) {
+        let [dx, dy] = direction.delta();
+        let (width, height) = (self.config.width as i16, self.config.height as i16);
+        self.cursor = [
+            (self.cursor[0] + dx).clamp(1, width - 2),
+            (self.cursor[1] + dy).clamp(1, height - 2),
+        ];
+        self.message.clear();
+    }
+
+    /// Puts a wall under the cursor, or takes away the one there.
+    pub fn toggle_wall(&mut self) {
+        let cursor = self.cursor;
+        self.edit(
+            |config| match config.walls.iter().position(|cell| *cell == cursor) {
+                Some(i) => {
+                    config.walls.remove(i);
+                }
+                None => config.walls.push(cursor),
+            },
+        );
+    }
+
+    /// Starts the snake at the cursor. If it starts there already, it's
+    /// turned round to the next way it fits, clockwise.
+    pub fn place_spawn(&mut self) {
+        let cursor = self.cursor;
+        let spawn = self.config.spawn();
+        let mut direction = if spawn.at == cursor {
+            clockwise(spawn.direction)
+        } else {
+            spawn.direction
+        };
+        // the first way round that doesn't run into anything, if any
+        for _ in Direction::ALL {
+            let placed = self.edit(|config| {
+                config.spawn = Some(Spawn {
+                    at: cursor,
+                    direction,
+                })
+            });
+            if placed {
+                return;
+            }
+            direction = clockwise(direction);
+        }
+    }
+
+    /// One end of a portal at the cursor, or the other end if the first
+    /// is already down. The same cell twice gives up on it.
+    pub fn place_portal(&mut self) {
+        let cursor = self.cursor;
+        match self.pending.take() {
+            Some(Pending::Portal(end)) if end != cursor => {
+                self.edit(|config| {
+                    config.portals.push(Portal {
+                        ends: [end, cursor],
+                    })
+                });
+            }
+            Some(Pending::Portal(_)) => self.message.clear(),
+            _ => self.pending = Some(Pending::Portal(cursor)),
+        }
+    }
+
+    /// One corner of a zone of `ground` at the cursor, or the opposite
+    /// corner if the first is already down.
+    pub fn place_zone(&mut self, ground: Ground) {
+        let cursor = self.cursor;
+        match self.pending.take() {
+            Some(Pending::Zone(pending, corner)) if pending == ground => {
+                let at = [corner[0].min(cursor[0]), corner[1].min(cursor[1])];
+                let size = [
+                    corner[0].abs_diff(cursor[0]) + 1,
+                    corner[1].abs_diff(cursor[1]) + 1,
+                ];
+                self.edit(|config| config.zones.push(Zone { ground, at, size }));
+            }
+            _ => self.pending = Some(Pending::Zone(ground, cursor)),
+        }
+    }
+
+    /// Opens a gate in the border next to the cursor, and so the one
+    /// across from it too, or closes them both if they're open. The cursor
+    /// has to be up against the border.
+    pub fn toggle_gate(&mut self) {
+        let [x, y] = self.cursor;
+        let (right, bottom) = (self.config.width as i16 - 2, self.config.height as i16 - 2);
+        let gate = if x == 1 {
+            [0, y]
+        } else if x == right {
+            [x + 1, y]
+        } else if y == 1 {
+            [x, 0]
+        } else if y == bottom {
+            [x, y + 1]
+        } else {
+            self.message = self.lang.text("edit-gate").into();
+            return;
+        };
+        self.pending = None;
+        self.edit(|config| {
+            if config.is_gate(gate) {
+                // whichever end it was that went in the level
+                let across = config.across(gate);
+                config.gates.retain(|cell| *cell != gate && *cell != across);
+            } else {
+                config.gates.push(gate);
+            }
+        });
+    }
+
+    /// Takes away the wall under the cursor, any portal with an end there
+    /// and any zone covering it. The snake has to start somewhere, so it
+    /// stays.
+    pub fn erase(&mut self) {
+        let cursor = self.cursor;
+        self.pending = None;
+        self.edit(|config| {
+            config.walls.retain(|cell| *cell != cursor);
+            config
+                .portals
+                .retain(|portal| portal.exit(cursor).is_none());
+            config
+                .zones
+                .retain(|zone| !zone.cells().any(|cell| cell == cursor));
+        });
+    }
+
+    /// Checks the level's fit to play: that it passes
+    /// `GameConfig::validate`, and that the snake can get everywhere.
+    pub fn check(&self) -> Result<()> {
+        self.config.validate()?;
+        match level::unreachable(&self.config).len() {
+            0 => Ok(()),
+            cells => Err(RakeError::Config(format!(
+                "{cells} cells can't be reached from the start"
+            ))),
+        }
+    }
+
+    /// Checks the level and writes it to `path`, saying how that went
+    /// under the board.
+    pub fn save(&mut self, path: &Path) {
+        self.pending = None;
+        match self.check().and_then(|()| self.level().save(path)) {
+            Ok(()) => {
+                self.changed = false;
+                self.message = self.lang.format("edit-saved", &[("path", &path.display())]);
+            }
+            Err(e) => self.message = describe(e),
+        }
+    }
+
+    /// Draws the board with the level on it, and the status lines under
+    /// it as wide as `columns`. Everything's drawn every time, so there's
+    /// no need to clear first.
+    pub fn draw(&self, renderer: &mut impl Renderer, columns: u16) -> io::Result<()> {
+        let theme = &self.config.theme;
+        let (_, grid) = game::build_board(&self.config);
+        let spawn: Vec<Coord> = self.config.spawn().cells().collect();
+        let mark = match self.pending {
+            Some(Pending::Portal(cell) | Pending::Zone(_, cell)) => Some(cell),
+            None => None,
+        };
+        for y in 0..self.config.height {
+            for x in 0..self.config.width {
+                let cell = [x as i16, y as i16];
+                let ground = theme.ground(grid.ground(cell));
+                let portal = self.config.portals.iter().any(|p| p.exit(cell).is_some());
+                let (symbol, color) = if let Some(n) = spawn.iter().position(|c| *c == cell) {
+                    (theme.snake[n % 2].symbol, theme.snake[n % 2].color)
+                } else if portal {
+                    (theme.portal.symbol, theme.portal.color)
+                } else if grid.get(cell) == Tile::Wall && !self.config.is_gate(cell) {
+                    (theme.wall.symbol, theme.wall.color)
+                } else {
+                    (ground.map_or(' ', |ground| ground.symbol), theme.text)
+                };
+                let background = if cell == self.cursor {
+                    Some(CURSOR)
+                } else if Some(cell) == mark {
+                    Some(MARK)
+                } else {
+                    ground.map(|ground| ground.color)
+                };
+                match background {
+                    Some(background) => renderer.draw_cell_on(x, y, symbol, color, background)?,
+                    None if symbol == ' ' => renderer.clear_cell(x, y)?,
+                    None => renderer.draw_cell(x, y, symbol, color)?,
+                }
+            }
+        }
+
+        let [x, y] = self.cursor;
+        let mut status = self.lang.format("edit-at", &[("x", &x), ("y", &y)]);
+        let hint = match self.pending {
+            Some(Pending::Portal([x, y])) => {
+                self.lang.format("edit-portal", &[("x", &x), ("y", &y)])
+            }
+            Some(Pending::Zone(ground, [x, y])) => {
+                let key = match ground {
+                    Ground::Mud => "edit-mud",
+                    _ => "edit-ice",
+                };
+                self.lang.format(key, &[("x", &x), ("y", &y)])
+            }
+            None => self.message.clone(),
+        };
+        if !hint.is_empty() {
+            status.push_str("  ");
+            status.push_str(&hint);
+        }
+        let lines = [
+            status.as_str(),
+            self.lang.text("edit-help-place"),
+            self.lang.text("edit-help-file"),
+        ];
+        for (n, line) in lines.into_iter().enumerate() {
+            // padded out to blank whatever was there before
+            let line: String = line
+                .chars()
+                .chain(std::iter::repeat(' '))
+                .take(columns as usize)
+                .collect();
+            renderer.draw_text(0, self.config.height + n as u16, &line, theme.text)?;
+        }
+        Ok(())
+    }
+
+    // makes `change` to a copy of the board, and keeps it if it's still a
+    // board that can be played on. if not it says why and leaves things be
+    fn edit(&mut self, change: impl FnOnce(&mut GameConfig)) -> bool {
+        let mut config = self.config.clone();
+        change(&mut config);
+        match config.validate() {
+            Ok(()) => {
+                self.config = config;
+                self.changed = true;
+                self.message.clear();
+                true
+            }
+            Err(e) => {
+                self.message = describe(e);
+                false
+            }
+        }
+    }
+}
+
+fn clockwise(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Right,
+        Direction::Right => Direction::Down,
+        Direction::Down => Direction::Left,
+        Direction::Left => Direction::Up,
+    }
+}
+
+// an error as a line under the board, without the "bad config" that'd
+// start nearly all of them
+fn describe(e: RakeError) -> String {
+    match e {
+        RakeError::Config(message) => message,
+        e => e.to_string(),
+    }
+}
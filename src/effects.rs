@@ -0,0 +1,66 @@
+//! Transient effects: things drawn on the board for a few ticks that
+//! aren't part of the game itself and never get in the snake's way.
+//!
+//! So far that's the trail, the cells the tail has just left fading out
+//! over a few ticks, which makes a fast snake easier to follow. It only
+//! ever goes on empty cells, and steady themes leave it out.
+
+use crate::game::{clear_cell, draw_cell};
+use crate::grid::{Coord, Grid};
+use crate::render::Renderer;
+use crate::theme::Theme;
+use std::io;
+
+// a cell the tail left `age` ticks ago
+#[derive(Clone, Copy, Debug)]
+struct Fade {
+    cell: Coord,
+    age: usize,
+}
+
+/// Whatever effects are on the board.
+#[derive(Clone, Debug, Default)]
+pub struct Effects {
+    trail: Vec<Fade>,
+}
+
+impl Effects {
+    /// Room for as long a trail as `theme` draws, so ticking along never
+    /// allocates.
+    pub fn new(theme: &Theme) -> Effects {
+        Effects {
+            // one more for the cell being blanked once it's faded
+            trail: Vec::with_capacity(theme.trail.len() + 1),
+        }
+    }
+
+    /// Ages everything by a tick, and starts the trail off from `wake`,
+    /// the cell the tail just left, if it left one.
+    pub fn tick(&mut self, theme: &Theme, wake: Option<Coord>) {
+        // a fade that's been blanked is done with
+        self.trail.retain(|fade| fade.age < theme.trail.len());
+        for fade in &mut self.trail {
+            fade.age += 1;
+        }
+        if let Some(cell) = wake
+            && !theme.steady
+        {
+            self.trail.push(Fade { cell, age: 0 });
+        }
+    }
+
+    /// Draws the trail on whichever of its cells are still empty, and
+    /// blanks the ones that have faded all the way.
+    pub fn draw(&self, renderer: &mut impl Renderer, theme: &Theme, grid: &Grid) -> io::Result<()> {
+        for fade in &self.trail {
+            if !grid.is_empty(fade.cell) {
+                continue;
+            }
+            match theme.trail.get(fade.age) {
+                Some(glyph) => draw_cell(renderer, theme, grid, fade.cell, *glyph)?,
+                None => clear_cell(renderer, theme, grid, fade.cell)?,
+            }
+        }
+        Ok(())
+    }
+}
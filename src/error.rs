@@ -0,0 +1,41 @@
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RakeError {
+    #[error("couldn't talk to the terminal: {0}")]
+    Terminal(#[from] io::Error),
+
+    #[error("bad config: {0}")]
+    Config(String),
+
+    #[error("couldn't load level {}: {message}", path.display())]
+    Level { path: PathBuf, message: String },
+
+    #[error("couldn't load replay {}: {message}", path.display())]
+    Replay { path: PathBuf, message: String },
+
+    #[error("bad snapshot: {0}")]
+    Snapshot(String),
+
+    #[error("script error: {0}")]
+    Script(String),
+
+    #[error("plugin error: {0}")]
+    Plugin(String),
+
+    #[error("couldn't reach the leaderboard: {0}")]
+    Network(String),
+
+    #[error("connection problem: {0}")]
+    Connection(String),
+
+    #[error("couldn't access save file {}: {source}", path.display())]
+    Save { path: PathBuf, source: io::Error },
+
+    #[error("couldn't write {}: {source}", path.display())]
+    Write { path: PathBuf, source: io::Error },
+}
+
+pub type Result<T> = std::result::Result<T, RakeError>;
@@ -0,0 +1,383 @@
+//! Boards written out to share. With `--snapshot` the final board goes out
+//! as plain text, the way it looked in the terminal, or as a PNG with a
+//! square of color for every cell; `rake replay --export` draws a whole
+//! run the same way as an animated GIF.
+
+use crate::game::Game;
+use crate::render::{HeadlessRenderer, Renderer};
+use crate::{RakeError, Result};
+use crossterm::style::Color;
+use rand::Rng;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time;
+
+// pixels along each side of a cell, a pixel of which is a gap between it
+// and the next so neighbours don't run together
+const CELL_PIXELS: usize = 8;
+
+// what's behind everything, like a dark terminal
+const BACKGROUND: [u8; 3] = [0, 0, 0];
+
+/// What a snapshot gets written as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Png,
+}
+
+impl Format {
+    /// A PNG for a path ending in `.png`, text for anything else.
+    pub fn for_path(path: &Path) -> Format {
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("png") => Format::Png,
+            _ => Format::Text,
+        }
+    }
+}
+
+/// Draws the whole of `game` onto a renderer of its own: border, board and
+/// the HUD underneath.
+pub fn capture<R: Rng>(game: &Game<R>) -> Result<HeadlessRenderer> {
+    let mut renderer = HeadlessRenderer::new(game.width(), game.height() + 1);
+    game.draw_border(&mut renderer)?;
+    game.draw(&mut renderer)?;
+    renderer.present()?;
+    Ok(renderer)
+}
+
+/// Writes `game`'s board to `path` in whatever format its name asks for.
+/// Text can also go to stdout, with `-`.
+pub fn write<R: Rng>(path: &Path, game: &Game<R>) -> Result<()> {
+    let board = capture(game)?;
+    let bytes = match Format::for_path(path) {
+        Format::Text => {
+            let text = board.snapshot();
+            if path == Path::new("-") {
+                print!("{text}");
+                return Ok(());
+            }
+            text.into_bytes()
+        }
+        // the HUD's text would only come out as a row of blocks
+        Format::Png => png(&board, game.height()),
+    };
+    crate::storage::write_atomic(path, &bytes).map_err(|source| RakeError::Write {
+        path: path.into(),
+        source,
+    })
+}
+
+/// The top `rows` of `board` as a PNG, each cell a square of its glyph's
+/// color with blank cells left as background.
+pub fn png(board: &HeadlessRenderer, rows: u16) -> Vec<u8> {
+    let raster = Raster::new(board, rows);
+
+    // every row of pixels starts with its filter type, 0 for none
+    let mut pixels = Vec::with_capacity(raster.height * (1 + raster.width * 3));
+    for row in raster.pixels.chunks(raster.width) {
+        pixels.push(0);
+        for pixel in row {
+            pixels.extend_from_slice(&pixel.map_or(BACKGROUND, rgb));
+        }
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(raster.width as u32).to_be_bytes());
+    header.extend_from_slice(&(raster.height as u32).to_be_bytes());
+    // 8 bits a channel, RGB, then the only compression, filtering and
+    // (no) interlacing there are
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut out, b"IHDR", &header);
+    chunk(&mut out, b"IDAT", &zlib_stored(&pixels));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+/// An animated GIF built up a frame at a time, each frame the top rows of
+/// a board drawn the same way as `png` draws one.
+pub struct Gif {
+    rows: u16,
+    out: Vec<u8>,
+}
+
+impl Gif {
+    /// A GIF of boards `columns` by `rows` cells, which plays on a loop.
+    pub fn new(columns: u16, rows: u16) -> Gif {
+        let width = (columns as usize * CELL_PIXELS) as u16;
+        let height = (rows as usize * CELL_PIXELS) as u16;
+        let mut out = b"GIF89a".to_vec();
+        out.extend_from_slice(&width.to_le_bytes());
+        out.extend_from_slice(&height.to_le_bytes());
+        // a global color table of 256 entries, the background is entry 0
+        out.extend_from_slice(&[0xf7, 0, 0]);
+        for index in 0..=255 {
+            out.extend_from_slice(&palette(index));
+        }
+        // loop forever
+        out.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+        Gif { rows, out }
+    }
+
+    /// Adds `board` as the next frame, shown for `delay`.
+    pub fn frame(&mut self, board: &HeadlessRenderer, delay: time::Duration) {
+        let raster = Raster::new(board, self.rows);
+        // hundredths of a second, and browsers slow anything under 2 of
+        // them right down
+        let delay = (delay.as_millis() / 10).clamp(2, u16::MAX as u128) as u16;
+        self.out.extend_from_slice(&[0x21, 0xf9, 4, 0x04]);
+        self.out.extend_from_slice(&delay.to_le_bytes());
+        self.out.extend_from_slice(&[0, 0]);
+
+        self.out.extend_from_slice(&[0x2c, 0, 0, 0, 0]);
+        self.out
+            .extend_from_slice(&(raster.width as u16).to_le_bytes());
+        self.out
+            .extend_from_slice(&(raster.height as u16).to_le_bytes());
+        self.out.push(0);
+        let indices: Vec<u8> = raster
+            .pixels
+            .iter()
+            .map(|pixel| pixel.map_or(0, palette_index))
+            .collect();
+        self.out.push(8);
+        for block in lzw(&indices).chunks(255) {
+            self.out.push(block.len() as u8);
+            self.out.extend_from_slice(block);
+        }
+        self.out.push(0);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.out.push(0x3b);
+        self.out
+    }
+}
+
+// a board as pixels, each one the color of the glyph it's part of, or
+// `None` for background
+struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<Option<Color>>,
+}
+
+impl Raster {
+    fn new(board: &HeadlessRenderer, rows: u16) -> Raster {
+        let (columns, rows) = (board.width(), rows.min(board.height()));
+        let width = columns as usize * CELL_PIXELS;
+        let height = rows as usize * CELL_PIXELS;
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (column, row) = ((x / CELL_PIXELS) as u16, (y / CELL_PIXELS) as u16);
+                let gap = x % CELL_PIXELS == CELL_PIXELS - 1 || y % CELL_PIXELS == CELL_PIXELS - 1;
+                pixels.push(match board.cell(column, row) {
+                    Some((glyph, color)) if !gap && glyph != ' ' => Some(color),
+                    _ => None,
+                });
+            }
+        }
+        Raster {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+// the color a terminal would most likely show
+fn rgb(color: Color) -> [u8; 3] {
+    match color {
+        Color::Rgb { r, g, b } => [r, g, b],
+        _ => palette(palette_index(color)),
+    }
+}
+
+// where `color` is in xterm's 256 colors, or the nearest to it there
+fn palette_index(color: Color) -> u8 {
+    match color {
+        Color::Rgb { r, g, b } => {
+            let distance = |index: &u8| {
+                let [pr, pg, pb] = palette(*index);
+                [(r, pr), (g, pg), (b, pb)]
+                    .map(|(a, b)| (a as i32 - b as i32).pow(2))
+                    .iter()
+                    .sum::<i32>()
+            };
+            (0..=255).min_by_key(distance).unwrap_or(0)
+        }
+        Color::AnsiValue(value) => value,
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        // the terminal's own text color, which is usually about this
+        Color::Grey | Color::Reset => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15,
+    }
+}
+
+// xterm's 256 colors
+fn palette(index: u8) -> [u8; 3] {
+    const ANSI: [[u8; 3]; 16] = [
+        [0, 0, 0],
+        [205, 0, 0],
+        [0, 205, 0],
+        [205, 205, 0],
+        [0, 0, 238],
+        [205, 0, 205],
+        [0, 205, 205],
+        [229, 229, 229],
+        [127, 127, 127],
+        [255, 0, 0],
+        [0, 255, 0],
+        [255, 255, 0],
+        [92, 92, 255],
+        [255, 0, 255],
+        [0, 255, 255],
+        [255, 255, 255],
+    ];
+    match index {
+        0..16 => ANSI[index as usize],
+        // a 6x6x6 cube, then 24 greys
+        16..232 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let n = index - 16;
+            [level(n / 36), level(n / 6 % 6), level(n % 6)]
+        }
+        _ => [8 + (index - 232) * 10; 3],
+    }
+}
+
+// GIF's LZW, starting from 8 bit codes, packed least significant bit
+// first
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+    const MAX_CODES: u16 = 4096;
+    let mut bits = Bits::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = END + 1;
+    let mut size = 9;
+    bits.push(CLEAR, size);
+    let Some((first, rest)) = indices.split_first() else {
+        bits.push(END, size);
+        return bits.finish();
+    };
+    let mut prefix = *first as u16;
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        bits.push(prefix, size);
+        if next < MAX_CODES {
+            table.insert((prefix, index), next);
+            next += 1;
+            // the decoder's a code behind, so it widens once it's seen the
+            // code that fills the width up
+            if next > 1 << size && size < 12 {
+                size += 1;
+            }
+        } else {
+            bits.push(CLEAR, size);
+            table.clear();
+            next = END + 1;
+            size = 9;
+        }
+        prefix = index as u16;
+    }
+    bits.push(prefix, size);
+    bits.push(END, size);
+    bits.finish()
+}
+
+#[derive(Default)]
+struct Bits {
+    out: Vec<u8>,
+    pending: u32,
+    count: u32,
+}
+
+impl Bits {
+    fn push(&mut self, code: u16, size: u32) {
+        self.pending |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.out.push(self.pending as u8);
+            self.pending >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.pending as u8);
+        }
+        self.out
+    }
+}
+
+// a length, the type, the data and a CRC of the last two
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// zlib with deflate's "stored" blocks, which is to say not compressed at
+// all; a board's worth of pixels is small enough not to matter
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(u8::from(last));
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65_521;
+        b = (b + a) % 65_521;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,169 @@
+//! A C API for the game on its own, behind the `ffi` feature, for putting
+//! it inside a frontend written in C or C++. `include/rake.h` declares it.
+//!
+//! A game is made with `rake_create`, moved on with `rake_tick`, read with
+//! `rake_board` and `rake_status`, and freed with `rake_destroy`. The
+//! board and directions are encoded the same way wasm plugins see them:
+//! one byte per cell, row by row from the top left, 0 empty, 1 wall,
+//! 2 snake, 3 apple; and 0 up, 1 down, 2 left, 3 right.
+//!
+//! Nothing here draws, reads keys or touches a file. Pacing the ticks is
+//! up to the frontend.
+
+use crate::config::GameConfig;
+use crate::game::{Direction, Game};
+use std::ptr;
+
+/// Set in what `rake_tick` returns when the snake moved.
+pub const RAKE_MOVED: u8 = 1;
+/// Set in what `rake_tick` returns when the snake ate an apple.
+pub const RAKE_ATE: u8 = 2;
+/// Set in what `rake_tick` returns when the snake died.
+pub const RAKE_DIED: u8 = 4;
+/// Set in what `rake_tick` returns when the snake filled the board.
+pub const RAKE_WON: u8 = 8;
+
+/// A game, only ever handled through a pointer.
+pub struct RakeGame(Game);
+
+/// Everything about a game that isn't the board.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct RakeStatus {
+    pub width: u16,
+    pub height: u16,
+    pub head_x: i16,
+    pub head_y: i16,
+    /// The way the snake goes next, 0 up, 1 down, 2 left or 3 right.
+    pub direction: u8,
+    pub length: u32,
+    pub score: u16,
+    /// How many times the snake has moved.
+    pub ticks: u64,
+    /// Whether the game has ended, the snake dead or the board full.
+    pub over: bool,
+}
+
+/// A new game on a `width` by `height` board, border included, with
+/// apples coming from `seed`. Returns null for a board that's too small
+/// or too big to play on.
+#[unsafe(no_mangle)]
+pub extern "C" fn rake_create(width: u16, height: u16, seed: u64) -> *mut RakeGame {
+    let config = GameConfig {
+        width,
+        height,
+        seed: Some(seed),
+        ..GameConfig::default()
+    };
+    if config.validate().is_err() {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(RakeGame(Game::new(config))))
+}
+
+/// Moves the game on a tick, turning first if `direction` is 0 to 3.
+/// Anything else carries on straight. Returns `RAKE_MOVED`, `RAKE_ATE`,
+/// `RAKE_DIED` and `RAKE_WON` or'd together for whatever happened.
+///
+/// # Safety
+///
+/// `game` has to be from `rake_create` and not destroyed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rake_tick(game: *mut RakeGame, direction: i32) -> u8 {
+    let Some(RakeGame(game)) = (unsafe { game.as_mut() }) else {
+        return 0;
+    };
+    let direction = match direction {
+        0 => Some(Direction::Up),
+        1 => Some(Direction::Down),
+        2 => Some(Direction::Left),
+        3 => Some(Direction::Right),
+        _ => None,
+    };
+    let tick = game.tick(direction);
+    [
+        (tick.moved, RAKE_MOVED),
+        (tick.ate, RAKE_ATE),
+        (tick.died, RAKE_DIED),
+        (tick.won, RAKE_WON),
+    ]
+    .into_iter()
+    .filter(|(happened, _)| *happened)
+    .fold(0, |flags, (_, flag)| flags | flag)
+}
+
+/// Copies the board into `buffer` if `len` bytes is room enough, and
+/// returns how many bytes it takes: width times height. Passing a null
+/// `buffer` just asks how big it needs to be.
+///
+/// # Safety
+///
+/// `game` has to be from `rake_create` and not destroyed yet, and
+/// `buffer` either null or `len` bytes that can be written to.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rake_board(game: *const RakeGame, buffer: *mut u8, len: usize) -> usize {
+    let Some(RakeGame(game)) = (unsafe { game.as_ref() }) else {
+        return 0;
+    };
+    let tiles = game.grid().tiles();
+    if !buffer.is_null() && len >= tiles.len() {
+        let buffer = unsafe { std::slice::from_raw_parts_mut(buffer, tiles.len()) };
+        for (byte, tile) in buffer.iter_mut().zip(tiles) {
+            *byte = *tile as u8;
+        }
+    }
+    tiles.len()
+}
+
+/// Where the snake is, the score and so on. A null `game` gives all
+/// zeroes.
+///
+/// # Safety
+///
+/// `game` has to be from `rake_create` and not destroyed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rake_status(game: *const RakeGame) -> RakeStatus {
+    let Some(RakeGame(game)) = (unsafe { game.as_ref() }) else {
+        return RakeStatus {
+            width: 0,
+            height: 0,
+            head_x: 0,
+            head_y: 0,
+            direction: 0,
+            length: 0,
+            score: 0,
+            ticks: 0,
+            over: false,
+        };
+    };
+    let snake = game.snake();
+    let [head_x, head_y] = snake.head();
+    RakeStatus {
+        width: game.width(),
+        height: game.height(),
+        head_x,
+        head_y,
+        direction: match snake.direction() {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        },
+        length: snake.len() as u32,
+        score: game.score(),
+        ticks: game.ticks(),
+        over: game.is_over(),
+    }
+}
+
+/// Frees a game. Null is fine and does nothing.
+///
+/// # Safety
+///
+/// `game` has to be from `rake_create`, and not used again afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rake_destroy(game: *mut RakeGame) {
+    if !game.is_null() {
+        drop(unsafe { Box::from_raw(game) });
+    }
+}
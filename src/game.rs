@@ -0,0 +1,1791 @@
+use crate::bug::{self, Bug};
+use crate::clock::Clock;
+use crate::config::{self, Food, GameConfig, WallMode};
+use crate::effects::Effects;
+use crate::grid::{Coord, Grid, Tile};
+use crate::lang::Lang;
+use crate::level::Spawn;
+use crate::render::Renderer;
+use crate::rooms::{self, Room};
+use crate::spawning::{Board, SpawnPolicy};
+use crate::theme::{Glyph, Theme};
+use crate::{RakeError, Result};
+use crossterm::style::Color;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::sync::Arc;
+use std::{io, time};
+
+/// The RNG games use unless told otherwise. Its state serializes, so a
+/// snapshot carries on exactly where the game left off.
+pub type GameRng = ChaCha8Rng;
+
+// bump whenever `Snapshot` changes shape
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// How far one step in this direction moves, as `[dx, dy]`.
+    pub fn delta(self) -> [i16; 2] {
+        match self {
+            Direction::Up => [0, -1],
+            Direction::Down => [0, 1],
+            Direction::Left => [-1, 0],
+            Direction::Right => [1, 0],
+        }
+    }
+
+    pub fn is_vertical(self) -> bool {
+        matches!(self, Direction::Up | Direction::Down)
+    }
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+/// Why a game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathCause {
+    /// The border.
+    Wall,
+    OwnBody,
+    /// A wall inside the border, from a level or rooms.
+    Hazard,
+    /// Another snake, in multiplayer.
+    Enemy,
+    /// Called off for going on too long, by `Game::time_out`.
+    Timeout,
+}
+
+impl DeathCause {
+    pub const ALL: [DeathCause; 5] = [
+        DeathCause::Wall,
+        DeathCause::OwnBody,
+        DeathCause::Hazard,
+        DeathCause::Enemy,
+        DeathCause::Timeout,
+    ];
+
+    /// What it's called in `death-*` lang keys.
+    pub fn name(self) -> &'static str {
+        match self {
+            DeathCause::Wall => "wall",
+            DeathCause::OwnBody => "self",
+            DeathCause::Hazard => "hazard",
+            DeathCause::Enemy => "enemy",
+            DeathCause::Timeout => "timeout",
+        }
+    }
+}
+
+/// Something notable that happened inside the simulation. Subsystems
+/// that react to the game (drawing effects, sound, stats, replays) read
+/// these instead of hooking into `tick` directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    AppleSpawned {
+        at: Coord,
+    },
+    AppleEaten {
+        at: Coord,
+    },
+    /// The snake moved off in a new direction.
+    Turned {
+        direction: Direction,
+    },
+    Died {
+        cause: DeathCause,
+    },
+    /// The snake hit a wall with `WallMode::Bounce` and turned end to end,
+    /// now facing `direction`.
+    Bounced {
+        direction: Direction,
+    },
+    /// The snake filled the board, leaving nowhere for another apple.
+    Won,
+    /// Every room's quota was eaten and stage `stage` has been laid out,
+    /// with the snake back at its start.
+    Cleared {
+        stage: u32,
+    },
+    /// A bonus bug came out, `at` being its top or left cell.
+    BugSpawned {
+        at: Coord,
+    },
+    /// The head went into the bug at `at`, for `points`.
+    BugEaten {
+        at: Coord,
+        points: u16,
+    },
+    /// The apple just eaten at `at` wasn't the next in order, and cost
+    /// `points` instead of scoring them.
+    OutOfOrder {
+        at: Coord,
+        points: u16,
+    },
+    /// A block went down at `at` with `GameConfig::obstacles`, for good.
+    ObstacleSpawned {
+        at: Coord,
+    },
+}
+
+/// What happened during a single call to `Game::tick`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TickResult {
+    pub moved: bool,
+    pub ate: bool,
+    pub died: bool,
+    pub won: bool,
+}
+
+// how long "New high score!" stays up
+const BANNER_TICKS: u64 = 30;
+
+/// How many numbered apples come out at once with `GameConfig::ordered`.
+pub const ORDERED_APPLES: u8 = 5;
+
+/// With `GameConfig::decay`, how many times its points a fresh apple is
+/// worth, and how many ticks it takes to be worth one less.
+pub const DECAY_START: u16 = 9;
+pub const DECAY_TICKS: u64 = 5;
+
+/// With `GameConfig::obstacles`, how many moves go by between blocks: ten
+/// seconds at the default speed. Moves rather than time, so slow motion,
+/// ice, mud and turn-based play don't change where they are in a run, and
+/// a replay puts them down on the same moves.
+pub const OBSTACLE_TICKS: u64 = 100;
+
+/// The whole simulation. Anything random (apple spawns for now) draws
+/// from `rng`, so two games built with the same seed play out identically
+/// given the same inputs.
+pub struct Game<R = GameRng> {
+    config: GameConfig,
+    wall: Vec<Coord>,
+    grid: Grid,
+    score: u16,
+    ticks: u64,
+    // the tick the last apple was eaten on, for `Scoring::Time`
+    fed: u64,
+    snake: Snake,
+    // where a casual game's snake is on a cell more than once
+    crossings: Vec<Coord>,
+    effects: Effects,
+    apples: Vec<Apple>,
+    // how many have come out, and where the last one did, for the spawn
+    // pattern, and anything put in place of it
+    spawned: u32,
+    last_apple: Option<Coord>,
+    spawn_policy: Option<Box<dyn SpawnPolicy + Send + Sync>>,
+    rng: R,
+    over: bool,
+    // whether it was over because the board filled up, or else what
+    // killed the snake
+    won: bool,
+    death: Option<DeathCause>,
+    // whether the last tick sat out a vertical move, with `slow_vertical`
+    rested: bool,
+    // slow motion, and half a point scored in it that's still owed
+    slow: bool,
+    half_point: bool,
+    // the stage it's on and its rooms, when it's rooms and corridors
+    stage: u32,
+    rooms: Vec<Room>,
+    // the blocks that have gone down with `obstacles`, which are walls on
+    // the grid too
+    obstacles: Vec<Coord>,
+    // the bonus bug if one's out, how many apples until the next, and the
+    // last one if it left this tick, for blanking its cells
+    bug: Option<Bug>,
+    until_bug: u16,
+    bug_gone: Option<Bug>,
+    events: Vec<GameEvent>,
+    high_score: Option<u16>,
+    // when the score went past `high_score`, for the banner
+    record_tick: Option<u64>,
+    // what the HUD can say on the right, longest first
+    help: Vec<String>,
+    lang: Arc<Lang>,
+    // how long it's been played, and whether anyone's timing it; the HUD
+    // leaves out a clock that isn't going
+    elapsed: time::Duration,
+    timed: bool,
+    // the HUD's clock to a tenth of a second
+    speedrun: bool,
+    // how fast it's going on the HUD
+    show_speed: bool,
+}
+
+impl Game {
+    /// Seeds from `config.seed` if there is one. Otherwise it picks a seed
+    /// at random and puts it in the config, so any run can be played again.
+    pub fn new(mut config: GameConfig) -> Game {
+        let seed = *config.seed.get_or_insert_with(|| rand::rng().random());
+        let rng = GameRng::seed_from_u64(seed);
+        Game::with_rng(config, rng)
+    }
+
+    /// Starts the random numbers over from `seed`, as if the game had
+    /// been started with it. Apples already out stay where they are.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = GameRng::seed_from_u64(seed);
+        self.config.seed = Some(seed);
+    }
+
+    /// Copies out everything needed to carry this game on later.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            config: self.config.clone(),
+            score: self.score,
+            ticks: self.ticks,
+            fed: self.fed,
+            snake: self.snake.clone(),
+            apples: self.apples.clone(),
+            spawned: self.spawned,
+            last_apple: self.last_apple,
+            rng: self.rng.clone(),
+            over: self.over,
+            won: self.won,
+            death: self.death,
+            rested: self.rested,
+            slow: self.slow,
+            half_point: self.half_point,
+            stage: self.stage,
+            rooms: self.rooms.clone(),
+            obstacles: self.obstacles.clone(),
+            bug: self.bug,
+            until_bug: self.until_bug,
+            elapsed_ms: self.elapsed.as_millis().try_into().unwrap_or(u64::MAX),
+        }
+    }
+
+    /// Rebuilds a game from a snapshot, checking that it describes a board
+    /// that could actually exist.
+    pub fn restore(snapshot: Snapshot) -> Result<Game> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(RakeError::Snapshot(format!(
+                "unsupported version {} (expected {SNAPSHOT_VERSION})",
+                snapshot.version
+            )));
+        }
+        snapshot.config.validate()?;
+        if snapshot.snake.body.len() < 2 {
+            return Err(RakeError::Snapshot("snake is too short".into()));
+        }
+
+        let (wall, mut grid) = build_board(&snapshot.config);
+        // ahead of the snake, which can have its head in one if it died
+        for cell in &snapshot.obstacles {
+            if !grid.is_empty(*cell) {
+                return Err(RakeError::Snapshot(format!(
+                    "obstacle overlaps something at {cell:?}"
+                )));
+            }
+            grid.set(*cell, Tile::Wall);
+        }
+        let mut crossings = vec![];
+        for cell in &snapshot.snake.body {
+            if snapshot.config.casual && grid.get(*cell) == Tile::Snake {
+                if !crossings.contains(cell) {
+                    crossings.push(*cell);
+                }
+                continue;
+            }
+            if !grid.is_empty(*cell) && !snapshot.over {
+                return Err(RakeError::Snapshot(format!(
+                    "snake overlaps something at {cell:?}"
+                )));
+            }
+            grid.set(*cell, Tile::Snake);
+        }
+        for apple in &snapshot.apples {
+            if !grid.is_empty(apple.position) {
+                return Err(RakeError::Snapshot(format!(
+                    "apple overlaps something at {:?}",
+                    apple.position
+                )));
+            }
+            grid.set(apple.position, Tile::Apple);
+        }
+        for cell in snapshot.bug.iter().flat_map(|bug| bug.cells) {
+            if !grid.is_empty(cell) {
+                return Err(RakeError::Snapshot(format!(
+                    "bug overlaps something at {cell:?}"
+                )));
+            }
+            grid.set(cell, Tile::Apple);
+        }
+
+        let mut snake = snapshot.snake;
+        snake
+            .body
+            .reserve(interior(&snapshot.config).saturating_sub(snake.body.len()));
+        let mut obstacles = snapshot.obstacles;
+        obstacles.reserve(obstacle_room(&snapshot.config));
+        Ok(Game {
+            events: Vec::with_capacity(max_events(&snapshot.config)),
+            effects: Effects::new(&snapshot.config.theme),
+            config: snapshot.config,
+            wall,
+            grid,
+            score: snapshot.score,
+            ticks: snapshot.ticks,
+            fed: snapshot.fed,
+            snake,
+            crossings,
+            apples: snapshot.apples,
+            spawned: snapshot.spawned,
+            last_apple: snapshot.last_apple,
+            spawn_policy: None,
+            rng: snapshot.rng,
+            over: snapshot.over,
+            won: snapshot.won,
+            death: snapshot.death,
+            rested: snapshot.rested,
+            slow: snapshot.slow,
+            half_point: snapshot.half_point,
+            stage: snapshot.stage,
+            rooms: snapshot.rooms,
+            obstacles,
+            bug: snapshot.bug,
+            until_bug: snapshot.until_bug,
+            bug_gone: None,
+            high_score: None,
+            record_tick: None,
+            help: default_help(),
+            lang: Lang::english(),
+            elapsed: time::Duration::from_millis(snapshot.elapsed_ms),
+            timed: false,
+            speedrun: false,
+            show_speed: false,
+        })
+    }
+
+    /// Puts the game back how it was at `snapshot`, say from
+    /// `rewind::History`. Unlike `restore`, the HUD keeps its high score,
+    /// help and language.
+    pub fn rewind(&mut self, snapshot: Snapshot) -> Result<()> {
+        let mut earlier = Game::restore(snapshot)?;
+        earlier.high_score = self.high_score;
+        earlier.record_tick = self.record_tick.filter(|tick| *tick <= earlier.ticks);
+        earlier.help = std::mem::take(&mut self.help);
+        earlier.lang = self.lang.clone();
+        earlier.timed = self.timed;
+        earlier.speedrun = self.speedrun;
+        earlier.show_speed = self.show_speed;
+        earlier.spawn_policy = self.spawn_policy.take();
+        *self = earlier;
+        Ok(())
+    }
+}
+
+/// The canonical serialized form of a game: saves, replays and network
+/// sync all use this.
+///
+/// Only the state that can't be worked out from the rest is stored; the
+/// walls and occupancy grid are rebuilt by `Game::restore`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    version: u32,
+    config: GameConfig,
+    score: u16,
+    ticks: u64,
+    snake: Snake,
+    apples: Vec<Apple>,
+    rng: GameRng,
+    over: bool,
+    // older saves predate winning
+    #[serde(default)]
+    won: bool,
+    #[serde(default)]
+    rested: bool,
+    #[serde(default)]
+    elapsed_ms: u64,
+    #[serde(default)]
+    fed: u64,
+    #[serde(default)]
+    slow: bool,
+    #[serde(default)]
+    half_point: bool,
+    #[serde(default)]
+    stage: u32,
+    #[serde(default)]
+    rooms: Vec<Room>,
+    #[serde(default)]
+    bug: Option<Bug>,
+    #[serde(default)]
+    until_bug: u16,
+    #[serde(default)]
+    spawned: u32,
+    #[serde(default)]
+    last_apple: Option<Coord>,
+    #[serde(default)]
+    death: Option<DeathCause>,
+    #[serde(default)]
+    obstacles: Vec<Coord>,
+}
+
+impl Snapshot {
+    pub fn to_json(&self) -> String {
+        // nothing in here can fail to serialize
+        serde_json::to_string(self).expect("snapshot serializes")
+    }
+
+    pub fn from_json(json: &str) -> Result<Snapshot> {
+        serde_json::from_str(json).map_err(|e| RakeError::Snapshot(e.to_string()))
+    }
+}
+
+fn default_help() -> Vec<String> {
+    let english = Lang::english();
+    vec![
+        english.format("hud-help", &[("moves", &"WASD"), ("quit", &"ESC")]),
+        english.format("hud-help-exit", &[("quit", &"ESC")]),
+    ]
+}
+
+// how many cells there are inside the border, which is as long as a snake
+// can get
+fn interior(config: &GameConfig) -> usize {
+    (config.width as usize - 2) * (config.height as usize - 2)
+}
+
+// how many apples can be out at once
+fn apple_count(config: &GameConfig) -> usize {
+    if config.ordered {
+        ORDERED_APPLES as usize
+    } else {
+        config.apples
+    }
+}
+
+// room for a block on every cell, with `obstacles`, so they never
+// reallocate mid-game either
+fn obstacle_room(config: &GameConfig) -> usize {
+    match config.obstacles {
+        true => interior(config),
+        false => 0,
+    }
+}
+
+// the most events one tick can make: a turn, an apple eaten out of order,
+// a bug or a new stage and all of them respawned and a block, a turn, the
+// last apple and the win, or a turn and a death
+fn max_events(config: &GameConfig) -> usize {
+    apple_count(config) + 6
+}
+
+// the border cells and the level's walls, and a grid with them marked as
+// walls. portals and gates are walls on the grid too, since the head jumps
+// straight past them and nothing else should land there, but gates are
+// left out of the cells to draw, so they look like the gaps they are
+pub(crate) fn build_board(config: &GameConfig) -> (Vec<Coord>, Grid) {
+    let (width, height) = (config.width, config.height);
+    let mut wall: Vec<Coord> = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            if (y == 0 || y == height - 1) || (x == 0 || x == width - 1) {
+                wall.push([x as i16, y as i16]);
+            }
+        }
+    }
+    wall.extend(&config.walls);
+    let mut grid = Grid::new(width, height);
+    for cell in &wall {
+        grid.set(*cell, Tile::Wall);
+    }
+    wall.retain(|cell| !config.is_gate(*cell));
+    for portal in &config.portals {
+        for cell in portal.ends {
+            grid.set(cell, Tile::Wall);
+        }
+    }
+    for zone in &config.zones {
+        for cell in zone.cells() {
+            grid.set_ground(cell, zone.ground);
+        }
+    }
+    (wall, grid)
+}
+
+// stage `stage` of rooms and corridors, with its walls and spawn put in
+// `config` so the board gets built, and a snapshot restored, like any other
+fn lay_out(config: &mut GameConfig, stage: u32, rng: &mut impl Rng) -> Vec<Room> {
+    let stage = rooms::generate(config.width, config.height, stage, rng);
+    config.walls = stage.walls;
+    config.spawn = Some(stage.spawn);
+    stage.rooms
+}
+
+pub(crate) fn neighbour(config: &GameConfig, cell: Coord, direction: Direction) -> Coord {
+    let next = step(config, cell, direction);
+    // into a portal and out of the other end, a step on
+    match config.portals.iter().find_map(|portal| portal.exit(next)) {
+        Some(exit) => step(config, exit, direction),
+        None => next,
+    }
+}
+
+fn step(config: &GameConfig, cell: Coord, direction: Direction) -> Coord {
+    let delta = direction.delta();
+    let cell = [cell[0] + delta[0], cell[1] + delta[1]];
+    if config.wall_mode != WallMode::Wrap && !config.is_gate(cell) {
+        return cell;
+    }
+    // in wrap mode the border is only decoration, and so is a gate in any
+    // mode: stepping onto it puts the head on the first playable cell on
+    // the other side
+    let (width, height) = (config.width as i16, config.height as i16);
+    let mut cell = cell;
+    if cell[0] <= 0 {
+        cell[0] = width - 2;
+    } else if cell[0] >= width - 1 {
+        cell[0] = 1;
+    }
+    if cell[1] <= 0 {
+        cell[1] = height - 2;
+    } else if cell[1] >= height - 1 {
+        cell[1] = 1;
+    }
+    cell
+}
+
+impl<R: Rng> Game<R> {
+    /// Like `new`, but with a caller-supplied RNG. `config.seed` is
+    /// ignored.
+    pub fn with_rng(mut config: GameConfig, mut rng: R) -> Game<R> {
+        let mut stage = 0;
+        let mut rooms = vec![];
+        if config.rooms {
+            stage = 1;
+            rooms = lay_out(&mut config, stage, &mut rng);
+        }
+        let (wall, mut grid) = build_board(&config);
+        let mut snake = Snake::spawn(config.spawn());
+        // room to fill the board, so growing never reallocates mid-game
+        snake
+            .body
+            .reserve(interior(&config).saturating_sub(snake.body.len()));
+        for cell in snake.body() {
+            grid.set(*cell, Tile::Snake);
+        }
+        let mut game = Game {
+            apples: Vec::with_capacity(apple_count(&config)),
+            events: Vec::with_capacity(max_events(&config)),
+            obstacles: Vec::with_capacity(obstacle_room(&config)),
+            effects: Effects::new(&config.theme),
+            config,
+            wall,
+            grid,
+            score: 0,
+            ticks: 0,
+            fed: 0,
+            snake,
+            crossings: vec![],
+            spawned: 0,
+            last_apple: None,
+            spawn_policy: None,
+            rng,
+            over: false,
+            won: false,
+            death: None,
+            rested: false,
+            slow: false,
+            half_point: false,
+            stage,
+            rooms,
+            bug: None,
+            until_bug: bug::EVERY,
+            bug_gone: None,
+            high_score: None,
+            record_tick: None,
+            help: default_help(),
+            lang: Lang::english(),
+            elapsed: time::Duration::ZERO,
+            timed: false,
+            speedrun: false,
+            show_speed: false,
+        };
+        game.fill_apples();
+        game
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn height(&self) -> u16 {
+        self.config.height
+    }
+
+    pub fn width(&self) -> u16 {
+        self.config.width
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn score(&self) -> u16 {
+        self.score
+    }
+
+    /// How many times the snake has moved.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// How long the next tick lasts: the configured rate, sped up or
+    /// slowed down by whatever ground the head is on, and twice that in
+    /// slow motion.
+    pub fn tick_rate(&self) -> time::Duration {
+        let ground = self.grid.ground(self.snake.head());
+        let tick_rate = ground.tick_rate(self.config.tick_rate);
+        if self.slow { tick_rate * 2 } else { tick_rate }
+    }
+
+    /// Turns slow motion on or off. It isn't free: whatever's eaten while
+    /// it's on scores half, an odd point carried over to the next apple.
+    pub fn set_slow(&mut self, slow: bool) {
+        self.slow = slow;
+    }
+
+    pub fn is_slow(&self) -> bool {
+        self.slow
+    }
+
+    /// Changes the speed mid-game, before ice or mud have their say.
+    /// Takes effect from the next tick.
+    pub fn set_tick_rate(&mut self, tick_rate: time::Duration) {
+        self.config.tick_rate = tick_rate;
+    }
+
+    /// Changes the look mid-game. Nothing already on screen changes until
+    /// the whole board is drawn again.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.config.theme = theme;
+    }
+
+    /// Adds (or with a negative value, takes away) points. The score
+    /// never goes below zero.
+    pub fn add_score(&mut self, points: i32) {
+        self.score = (self.score as i32 + points).clamp(0, u16::MAX as i32) as u16;
+        self.check_record();
+    }
+
+    /// The best score from earlier games, for the HUD. Going past it puts
+    /// up a banner for a few seconds.
+    pub fn set_high_score(&mut self, best: u16) {
+        self.high_score = Some(best);
+    }
+
+    pub fn high_score(&self) -> Option<u16> {
+        self.high_score
+    }
+
+    /// What the HUD says about the controls, say from `Keymap::help`.
+    /// Give it shorter and shorter versions: the first that fits next to
+    /// the score is shown, and nothing if none of them do.
+    pub fn set_help(&mut self, help: Vec<String>) {
+        self.help = help;
+    }
+
+    /// How long the game has been played, which whoever's running it keeps
+    /// up to date. It goes in snapshots, so a saved game carries on timing
+    /// from where it was.
+    pub fn elapsed(&self) -> time::Duration {
+        self.elapsed
+    }
+
+    /// Moves the clock on to `elapsed`. The HUD shows it from the first
+    /// time this is called.
+    pub fn set_elapsed(&mut self, elapsed: time::Duration) {
+        self.elapsed = elapsed;
+        self.timed = true;
+    }
+
+    /// Puts apples wherever `policy` says from now on, instead of going
+    /// by `GameConfig::spawn_pattern`. Snapshots don't keep it, so a
+    /// restored game goes back to the pattern; rewinding keeps it.
+    pub fn set_spawn_policy(&mut self, policy: impl SpawnPolicy + Send + Sync + 'static) {
+        self.spawn_policy = Some(Box::new(policy));
+    }
+
+    /// Shows the HUD's clock to a tenth of a second, for `--speedrun`.
+    pub fn set_speedrun(&mut self, speedrun: bool) {
+        self.speedrun = speedrun;
+    }
+
+    /// Shows on the HUD how fast the game's going, as a `--speed` level and
+    /// milliseconds a move, which changes on ice, mud and in slow motion.
+    pub fn set_show_speed(&mut self, show_speed: bool) {
+        self.show_speed = show_speed;
+    }
+
+    /// What the HUD's score and banners are written in. The help line is
+    /// separate, from `set_help`.
+    pub fn set_lang(&mut self, lang: Arc<Lang>) {
+        self.lang = lang;
+    }
+
+    /// Puts an extra `food` at `at` if that cell is free, on top of the
+    /// configured count of apples. Returns whether it did.
+    pub fn spawn_apple(&mut self, at: Coord, food: Food) -> bool {
+        if !self.grid.is_empty(at) {
+            return false;
+        }
+        let mut apple = Apple::new(at);
+        apple.food = food;
+        self.place_apple(apple);
+        true
+    }
+
+    /// Moves the whole snake so its head is at `head`, keeping its shape.
+    /// Does nothing and returns `false` if any of it would land on
+    /// something other than itself.
+    pub fn teleport(&mut self, head: Coord) -> bool {
+        // on the board, so the shifted body can't overflow a coordinate
+        if self.grid.get(head) == Tile::Wall {
+            return false;
+        }
+        let from = self.snake.head();
+        let (dx, dy) = (head[0] - from[0], head[1] - from[1]);
+        let shifted = |cell: &Coord| [cell[0] + dx, cell[1] + dy];
+        let fits = self
+            .snake
+            .body
+            .iter()
+            .map(shifted)
+            .all(|cell| matches!(self.grid.get(cell), Tile::Empty | Tile::Snake));
+        if !fits {
+            return false;
+        }
+        for cell in &self.snake.body {
+            self.grid.set(*cell, Tile::Empty);
+        }
+        for cell in self.snake.body.iter_mut() {
+            *cell = shifted(cell);
+            self.grid.set(*cell, Tile::Snake);
+        }
+        // the old cells are somebody else's problem to redraw
+        self.snake.wake = None;
+        true
+    }
+
+    pub fn snake(&self) -> &Snake {
+        &self.snake
+    }
+
+    pub fn apples(&self) -> &[Apple] {
+        &self.apples
+    }
+
+    /// Whether the game has ended, by dying or by winning.
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// The stage of rooms and corridors it's on, counting from one, or
+    /// zero when it isn't that kind of game.
+    pub fn stage(&self) -> u32 {
+        self.stage
+    }
+
+    /// The bonus bug, if one's out.
+    pub fn bug(&self) -> Option<&Bug> {
+        self.bug.as_ref()
+    }
+
+    /// This stage's rooms, and how many apples each has had.
+    pub fn rooms(&self) -> &[Room] {
+        &self.rooms
+    }
+
+    /// Whether the snake filled the board.
+    pub fn has_won(&self) -> bool {
+        self.won
+    }
+
+    /// What killed the snake, once it's dead.
+    pub fn death(&self) -> Option<DeathCause> {
+        self.death
+    }
+
+    /// Ends a game that's gone on too long, as a death by
+    /// `DeathCause::Timeout`. Does nothing to one that's over already.
+    pub fn time_out(&mut self) {
+        if !self.over {
+            self.die(DeathCause::Timeout);
+        }
+    }
+
+    /// Everything that happened during the most recent tick, in order.
+    /// Before the first tick this holds the events from setting up the
+    /// board.
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Queues a turn for the next tick. Reversing straight into the
+    /// snake's own neck is ignored.
+    pub fn turn(&mut self, direction: Direction) {
+        self.snake.turn(direction);
+    }
+
+    /// Advances the simulation by one step, optionally turning first.
+    ///
+    /// Once the game is over this does nothing and keeps reporting `died`
+    /// or `won`, whichever it was.
+    pub fn tick(&mut self, input: Option<Direction>) -> TickResult {
+        let mut result = TickResult::default();
+        self.events.clear();
+        self.bug_gone = None;
+        if self.over {
+            result.died = !self.won;
+            result.won = self.won;
+            return result;
+        }
+
+        if let Some(direction) = input {
+            self.turn(direction);
+        }
+
+        // a turn up or down goes straight away, it's carrying on that's
+        // slowed down
+        if self.config.slow_vertical
+            && self.snake.direction.is_vertical()
+            && self.snake.heading == self.snake.direction
+            && !self.rested
+        {
+            self.rested = true;
+            return result;
+        }
+        self.rested = false;
+
+        if self.snake.direction != self.snake.heading {
+            self.events.push(GameEvent::Turned {
+                direction: self.snake.direction,
+            });
+        }
+        if self.snake.bounce(&self.config, &self.grid) {
+            self.events.push(GameEvent::Bounced {
+                direction: self.snake.direction,
+            });
+            return result;
+        }
+        let next = self.neighbour(self.snake.head(), self.snake.direction);
+        let mut hit = self.snake.slither(&mut self.grid, next);
+        if self.config.casual {
+            hit = self.cross(next, hit);
+        }
+        self.effects.tick(&self.config.theme, self.snake.wake);
+        self.ticks += 1;
+        result.moved = true;
+
+        match hit {
+            Tile::Empty => {}
+            Tile::Apple if self.bug.is_some_and(|bug| bug.covers(next)) => {
+                self.eat_bug(next);
+                result.ate = true;
+            }
+            Tile::Apple => {
+                let apple = self.apples.iter().find(|apple| apple.position == next);
+                let (food, number) =
+                    apple.map_or((Food::Cherry, 0), |apple| (apple.food, apple.number));
+                let times = apple.and_then(|apple| self.decay(apple)).unwrap_or(1);
+                let wrong = self.config.ordered && self.next_number() != Some(number);
+                let rule = self.config.foods.rule(food);
+                let points = self.config.scoring.score(
+                    rule.points.saturating_mul(times),
+                    &self.config,
+                    self.snake.body.len(),
+                    self.ticks - self.fed,
+                );
+                self.fed = self.ticks;
+                let points = match self.slow {
+                    true => self.halve(points),
+                    false => points,
+                };
+                self.apples.retain(|apple| apple.position != next);
+                self.snake.grow(rule.growth as usize);
+                self.events.push(GameEvent::AppleEaten { at: next });
+                if wrong {
+                    self.score = self.score.saturating_sub(points);
+                    self.events.push(GameEvent::OutOfOrder { at: next, points });
+                } else {
+                    self.increase_score(points);
+                }
+                if let Some(room) = self.rooms.iter_mut().find(|room| room.contains(next)) {
+                    room.eaten += 1;
+                }
+                if !self.rooms.is_empty() && self.rooms.iter().all(Room::is_done) {
+                    self.next_stage();
+                } else {
+                    self.fill_apples();
+                }
+                if self.config.bugs {
+                    self.count_down_bug();
+                }
+                result.ate = true;
+                // not even one apple found room, so the snake is everywhere
+                if self.apples.is_empty() {
+                    self.events.push(GameEvent::Won);
+                    self.over = true;
+                    self.won = true;
+                    result.won = true;
+                }
+            }
+            Tile::Wall | Tile::Snake => {
+                let cause = match hit {
+                    Tile::Snake => DeathCause::OwnBody,
+                    _ if self.config.walls.contains(&next) || self.obstacles.contains(&next) => {
+                        DeathCause::Hazard
+                    }
+                    _ => DeathCause::Wall,
+                };
+                self.die(cause);
+                result.died = true;
+            }
+        }
+        if let Some(bug) = self.bug
+            && bug.left(self.ticks) == 0
+        {
+            self.bug_leaves(bug);
+        }
+        if self.config.obstacles && !self.over && self.ticks.is_multiple_of(OBSTACLE_TICKS) {
+            self.drop_obstacle();
+        }
+
+        result
+    }
+
+    // a block on a free cell, but never the one the head's about to go
+    // into, which is only one cell so a couple of goes nearly always does
+    fn drop_obstacle(&mut self) {
+        let ahead = self.neighbour(self.snake.head(), self.snake.direction);
+        let Some(cell) = (0..4)
+            .filter_map(|_| self.grid.random_free(&mut self.rng))
+            .find(|cell| *cell != ahead)
+        else {
+            return;
+        };
+        self.grid.set(cell, Tile::Wall);
+        self.obstacles.push(cell);
+        self.events.push(GameEvent::ObstacleSpawned { at: cell });
+    }
+
+    // a bug every `bug::EVERY` apples, if there's anywhere for it
+    fn count_down_bug(&mut self) {
+        self.until_bug = self.until_bug.saturating_sub(1);
+        if self.until_bug > 0 || self.bug.is_some() {
+            return;
+        }
+        self.until_bug = bug::EVERY;
+        if let Some(bug) = Bug::spawn(&self.grid, self.ticks, &mut self.rng) {
+            for cell in bug.cells {
+                self.grid.set(cell, Tile::Apple);
+            }
+            self.events.push(GameEvent::BugSpawned { at: bug.cells[0] });
+            self.bug = Some(bug);
+        }
+    }
+
+    // the head's gone into one end of the bug at `at`, which takes the
+    // whole thing
+    fn eat_bug(&mut self, at: Coord) {
+        let Some(bug) = self.bug else {
+            return;
+        };
+        let points = self.config.scoring.score(
+            bug.points(self.ticks),
+            &self.config,
+            self.snake.body.len(),
+            self.ticks - self.fed,
+        );
+        let points = match self.slow {
+            true => self.halve(points),
+            false => points,
+        };
+        self.bug_leaves(bug);
+        self.increase_score(points);
+        self.events.push(GameEvent::BugEaten { at, points });
+    }
+
+    fn bug_leaves(&mut self, bug: Bug) {
+        for cell in bug.cells {
+            if self.grid.get(cell) == Tile::Apple {
+                self.grid.set(cell, Tile::Empty);
+            }
+        }
+        self.bug = None;
+        self.bug_gone = Some(bug);
+    }
+
+    // in a casual game the snake can be on a cell twice over: running into
+    // itself is just a crossing, and lifting its tail off a cell it's
+    // crossed doesn't leave the cell empty
+    fn cross(&mut self, head: Coord, hit: Tile) -> Tile {
+        if let Some(wake) = self.snake.wake
+            && self.crossings.contains(&wake)
+        {
+            self.grid.set(wake, Tile::Snake);
+        }
+        if hit == Tile::Snake && !self.crossings.contains(&head) {
+            self.crossings.push(head);
+        }
+        let body = &self.snake.body;
+        self.crossings
+            .retain(|cell| body.iter().filter(|c| *c == cell).nth(1).is_some());
+        match hit {
+            Tile::Snake => Tile::Empty,
+            hit => hit,
+        }
+    }
+
+    /// The cell one step from `cell` in `direction`, taking the wall mode
+    /// into account.
+    pub fn neighbour(&self, cell: Coord, direction: Direction) -> Coord {
+        neighbour(&self.config, cell, direction)
+    }
+
+    // as many as there's room for, which near the end isn't all of them.
+    // numbered apples come out a whole set at a time, once the last set's
+    // all gone
+    fn fill_apples(&mut self) {
+        if self.config.ordered && !self.apples.is_empty() {
+            return;
+        }
+        let mut number = 0;
+        while self.apples.len() < apple_count(&self.config) {
+            // in a room that still wants apples, unless the snake's filling
+            // all of them, when anywhere will do
+            let board = Board {
+                config: &self.config,
+                grid: &self.grid,
+                head: self.snake.head(),
+                last: self.last_apple,
+                spawned: self.spawned,
+            };
+            let policy = match &self.spawn_policy {
+                Some(policy) => policy.as_ref(),
+                None => &self.config.spawn_pattern as &dyn SpawnPolicy,
+            };
+            let spawned = rooms::random_free(&self.rooms, &self.grid, &mut self.rng)
+                .or_else(|| policy.place(&board, &mut self.rng))
+                .map(Apple::new)
+                .or_else(|| Apple::spawn(&self.grid, &mut self.rng));
+            let Some(mut apple) = spawned else {
+                break;
+            };
+            self.spawned += 1;
+            self.last_apple = Some(apple.position);
+            apple.food = self.config.foods.pick(&mut self.rng);
+            if self.config.ordered {
+                number += 1;
+                apple.number = number;
+            }
+            self.place_apple(apple);
+        }
+    }
+
+    /// How many times its points `apple` is worth right now, with
+    /// `GameConfig::decay`.
+    pub fn decay(&self, apple: &Apple) -> Option<u16> {
+        let age = self.ticks.saturating_sub(apple.born) / DECAY_TICKS;
+        let lost = age.min(u64::from(DECAY_START - 1)) as u16;
+        self.config.decay.then_some(DECAY_START - lost)
+    }
+
+    /// The number of the apple to eat next, with `GameConfig::ordered`.
+    pub fn next_number(&self) -> Option<u8> {
+        self.apples
+            .iter()
+            .map(|apple| apple.number)
+            .filter(|number| *number > 0)
+            .min()
+    }
+
+    /// The apples worth going for: the next in order with
+    /// `GameConfig::ordered`, or otherwise all of them.
+    pub fn targets(&self) -> impl Iterator<Item = &Apple> {
+        let next = self.next_number();
+        self.apples
+            .iter()
+            .filter(move |apple| next.is_none_or(|next| apple.number == next))
+    }
+
+    // lays the next stage out and starts the snake over at the start of
+    // it, as long as it was to begin with
+    fn next_stage(&mut self) {
+        self.stage += 1;
+        self.rooms = lay_out(&mut self.config, self.stage, &mut self.rng);
+        (self.wall, self.grid) = build_board(&self.config);
+        // in the old body's room, so growing still never reallocates
+        let mut body = std::mem::take(&mut self.snake.body);
+        let spawned = Snake::spawn(self.config.spawn());
+        body.clear();
+        body.extend(&spawned.body);
+        self.snake = Snake { body, ..spawned };
+        for cell in self.snake.body() {
+            self.grid.set(*cell, Tile::Snake);
+        }
+        self.crossings.clear();
+        self.apples.clear();
+        self.obstacles.clear();
+        self.bug = None;
+        self.effects = Effects::new(&self.config.theme);
+        self.events.push(GameEvent::Cleared { stage: self.stage });
+        self.fill_apples();
+    }
+
+    fn place_apple(&mut self, mut apple: Apple) {
+        apple.born = self.ticks;
+        self.grid.set(apple.position, Tile::Apple);
+        self.events
+            .push(GameEvent::AppleSpawned { at: apple.position });
+        self.apples.push(apple);
+    }
+
+    // half of `points` for slow motion, keeping any half point for later
+    fn halve(&mut self, points: u16) -> u16 {
+        let halves = u32::from(points) + u32::from(self.half_point);
+        self.half_point = halves % 2 == 1;
+        (halves / 2) as u16
+    }
+
+    fn die(&mut self, cause: DeathCause) {
+        self.events.push(GameEvent::Died { cause });
+        self.over = true;
+        self.death = Some(cause);
+    }
+
+    fn increase_score(&mut self, points: u16) {
+        self.score = self.score.saturating_add(points);
+        self.check_record();
+    }
+
+    fn check_record(&mut self) {
+        // beating a score of zero isn't worth a fanfare
+        if let Some(best) = self.high_score
+            && best > 0
+            && self.score > best
+            && self.record_tick.is_none()
+        {
+            self.record_tick = Some(self.ticks);
+        }
+    }
+
+    /// Draws the border, and any ice or mud. Neither ever changes, so
+    /// this only needs doing once onto a blank screen, and again if the
+    /// screen gets wiped by a resize.
+    pub fn draw_border(&self, renderer: &mut impl Renderer) -> Result<()> {
+        let theme = &self.config.theme;
+        let wall = theme.wall;
+        for cell in self.wall.iter().chain(&self.obstacles) {
+            renderer.draw_cell(cell[0] as u16, cell[1] as u16, wall.symbol, wall.color)?;
+        }
+        let portal = theme.portal;
+        for cell in self.config.portals.iter().flat_map(|portal| portal.ends) {
+            renderer.draw_cell(cell[0] as u16, cell[1] as u16, portal.symbol, portal.color)?;
+        }
+        for zone in &self.config.zones {
+            for cell in zone.cells().filter(|cell| self.grid.is_empty(*cell)) {
+                clear_cell(renderer, theme, &self.grid, cell)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draws everything that changes between ticks. The border is drawn
+    /// separately by `draw_border`.
+    pub fn draw(&self, renderer: &mut impl Renderer) -> Result<()> {
+        let theme = &self.config.theme;
+        for cell in self.bug_gone.iter().flat_map(|bug| bug.cells) {
+            if self.grid.is_empty(cell) {
+                clear_cell(renderer, theme, &self.grid, cell)?;
+            }
+        }
+        for event in &self.events {
+            if let GameEvent::ObstacleSpawned { at: [x, y] } = *event {
+                let wall = theme.wall;
+                renderer.draw_cell(x as u16, y as u16, wall.symbol, wall.color)?;
+            }
+        }
+        // what they were worth goes with the apples eaten this tick
+        if self.config.decay {
+            for event in &self.events {
+                if let GameEvent::AppleEaten { at: [x, y] } = *event
+                    && self.grid.is_empty([x + 1, y])
+                {
+                    clear_cell(renderer, theme, &self.grid, [x + 1, y])?;
+                }
+            }
+        }
+        for apple in &self.apples {
+            apple.draw(renderer, theme, &self.grid)?;
+            // what it's worth just to the right of it, if there's room
+            let [x, y] = apple.position;
+            if let Some(times) = self.decay(apple)
+                && self.grid.is_empty([x + 1, y])
+                && let Some(digit) = char::from_digit(times.into(), 10)
+            {
+                let color = theme.food(apple.food).color;
+                draw_cell(
+                    renderer,
+                    theme,
+                    &self.grid,
+                    [x + 1, y],
+                    Glyph::new(digit, color),
+                )?;
+            }
+        }
+        if let Some(bug) = &self.bug {
+            bug.draw(renderer, theme, &self.grid)?;
+        }
+        self.snake.draw(renderer, theme, &self.grid)?;
+        self.effects.draw(renderer, theme, &self.grid)?;
+        for cell in &self.crossings {
+            let symbol = theme.snake[0].symbol;
+            draw_cell(
+                renderer,
+                theme,
+                &self.grid,
+                *cell,
+                Glyph::new(symbol, theme.crossing),
+            )?;
+        }
+        self.display_score(renderer)?;
+        Ok(())
+    }
+
+    // written straight onto the renderer, since it's redrawn every frame
+    // and building strings for it would be most of a tick's allocations
+    fn display_score(&self, renderer: &mut impl Renderer) -> Result<()> {
+        let (width, height) = (self.config.width as usize, self.config.height);
+        let best = self.high_score.map(|best| best.max(self.score));
+        let lang = &*self.lang;
+        let score: [(&str, &dyn fmt::Display); 1] = [("score", &self.score)];
+        let clock = self
+            .timed
+            .then(|| Clock::new(self.elapsed).tenths(self.speedrun));
+        let stage: [(&str, &dyn fmt::Display); 1] = [("stage", &self.stage)];
+        let next = self.config.ordered.then(|| self.next_number()).flatten();
+        let next = next
+            .as_ref()
+            .map(|next| [("next", next as &dyn fmt::Display)]);
+        let tick_rate = self.tick_rate();
+        let (tier, ms) = (config::speed_tier(tick_rate), tick_rate.as_millis());
+        let speed: [(&str, &dyn fmt::Display); 2] = [("speed", &tier), ("ms", &ms)];
+        let speed = self.show_speed.then_some(speed);
+        let bug_left = self.bug.map(|bug| bug.left(self.ticks));
+        let bug_left = bug_left
+            .as_ref()
+            .map(|left| [("left", left as &dyn fmt::Display)]);
+        let left_len = lang.measure("hud-score", &score)
+            + if self.stage > 0 {
+                lang.measure("hud-stage", &stage)
+            } else {
+                0
+            }
+            + next.map_or(0, |next| lang.measure("hud-next", &next))
+            + bug_left.map_or(0, |left| lang.measure("hud-bug", &left))
+            + best.map_or(0, |best| lang.measure("hud-high", &[("high", &best)]))
+            + clock.map_or(0, |clock| lang.measure("hud-time", &[("time", &clock)]))
+            + speed.map_or(0, |speed| lang.measure("hud-speed", &speed))
+            + if self.slow {
+                lang.measure("hud-slow", &[])
+            } else {
+                0
+            };
+        let celebrating = !self.config.theme.steady
+            && self
+                .record_tick
+                .is_some_and(|tick| self.ticks - tick < BANNER_TICKS);
+        let fits = |right: &&str| left_len + 1 + right.chars().count() <= width;
+        let right = if celebrating {
+            [lang.text("hud-new-record"), lang.text("hud-record")]
+                .into_iter()
+                .find(fits)
+        } else {
+            self.help.iter().map(String::as_str).find(fits)
+        };
+        let right_len = right.map_or(0, |right| right.chars().count());
+        let color = self.config.theme.text;
+        let mut line = Cells {
+            renderer: &mut *renderer,
+            x: 0,
+            y: height,
+            end: (width - right_len) as u16,
+            color,
+            error: None,
+        };
+        let written = lang
+            .write(&mut line, "hud-score", &score)
+            .and_then(|()| match best {
+                Some(best) => lang.write(&mut line, "hud-high", &[("high", &best)]),
+                None => Ok(()),
+            })
+            .and_then(|()| match self.stage {
+                0 => Ok(()),
+                _ => lang.write(&mut line, "hud-stage", &stage),
+            })
+            .and_then(|()| match next {
+                Some(next) => lang.write(&mut line, "hud-next", &next),
+                None => Ok(()),
+            })
+            .and_then(|()| match bug_left {
+                Some(left) => lang.write(&mut line, "hud-bug", &left),
+                None => Ok(()),
+            })
+            .and_then(|()| match clock {
+                Some(clock) => lang.write(&mut line, "hud-time", &[("time", &clock)]),
+                None => Ok(()),
+            })
+            .and_then(|()| match speed {
+                Some(speed) => lang.write(&mut line, "hud-speed", &speed),
+                None => Ok(()),
+            })
+            .and_then(|()| match self.slow {
+                true => lang.write(&mut line, "hud-slow", &[]),
+                false => Ok(()),
+            });
+        // the whole line every time, so nothing is left over from a longer one
+        if written.and_then(|()| line.pad()).is_err() {
+            let e = line.error.take().expect("only drawing can fail");
+            return Err(e.into());
+        }
+        if let Some(right) = right {
+            renderer.draw_text((width - right_len) as u16, height, right, color)?;
+        }
+        Ok(())
+    }
+}
+
+/// Draws `glyph` at `cell` over whatever ground is there.
+pub(crate) fn draw_cell(
+    renderer: &mut impl Renderer,
+    theme: &Theme,
+    grid: &Grid,
+    cell: Coord,
+    glyph: Glyph,
+) -> io::Result<()> {
+    let (x, y) = (cell[0] as u16, cell[1] as u16);
+    match theme.ground(grid.ground(cell)) {
+        Some(ground) => renderer.draw_cell_on(x, y, glyph.symbol, glyph.color, ground.color),
+        None => renderer.draw_cell(x, y, glyph.symbol, glyph.color),
+    }
+}
+
+/// Blanks `cell` back to bare ground.
+pub(crate) fn clear_cell(
+    renderer: &mut impl Renderer,
+    theme: &Theme,
+    grid: &Grid,
+    cell: Coord,
+) -> io::Result<()> {
+    let (x, y) = (cell[0] as u16, cell[1] as u16);
+    match theme.ground(grid.ground(cell)) {
+        Some(ground) => renderer.draw_cell_on(x, y, ground.symbol, theme.text, ground.color),
+        None => renderer.clear_cell(x, y),
+    }
+}
+
+// draws whatever's written to it a cell at a time along a row, dropping
+// anything that would go past `end`
+struct Cells<'a, R: Renderer + ?Sized> {
+    renderer: &'a mut R,
+    x: u16,
+    y: u16,
+    end: u16,
+    color: Color,
+    // `fmt::Error` can't carry the reason
+    error: Option<io::Error>,
+}
+
+impl<R: Renderer + ?Sized> Cells<'_, R> {
+    // blanks out the rest of the row up to `end`
+    fn pad(&mut self) -> fmt::Result {
+        while self.x < self.end {
+            self.write_char(' ')?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Renderer + ?Sized> fmt::Write for Cells<'_, R> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        for glyph in text.chars() {
+            if self.x >= self.end {
+                break;
+            }
+            if let Err(e) = self.renderer.draw_cell(self.x, self.y, glyph, self.color) {
+                self.error = Some(e);
+                return Err(fmt::Error);
+            }
+            self.x += 1;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snake {
+    // head at the front, tail at the back
+    body: VecDeque<Coord>,
+    // the cell the tail just left, blanked on the next draw
+    #[serde(skip)]
+    wake: Option<Coord>,
+    // how many more moves the tail stays put for
+    growth: usize,
+    direction: Direction,
+    // the direction of the last move actually made
+    heading: Direction,
+}
+
+impl Snake {
+    pub(crate) fn new(body: VecDeque<Coord>, direction: Direction) -> Snake {
+        Snake {
+            body,
+            wake: None,
+            growth: 0,
+            direction,
+            heading: direction,
+        }
+    }
+
+    fn spawn(spawn: Spawn) -> Snake {
+        Snake::new(spawn.cells().collect(), spawn.direction)
+    }
+
+    pub fn head(&self) -> Coord {
+        self.body[0]
+    }
+
+    /// Every cell the snake covers, head first.
+    pub fn body(&self) -> &VecDeque<Coord> {
+        &self.body
+    }
+
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    /// The way the snake will move on the next tick.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    // input can arrive several times per tick, so turns are checked against
+    // the way the snake actually last moved rather than the queued direction,
+    // otherwise two quick presses could reverse it into its own neck
+    pub(crate) fn turn(&mut self, direction: Direction) {
+        if self.heading != direction.opposite() {
+            self.direction = direction;
+        }
+    }
+
+    /// Stretches the tail out by `cells` over the next few moves.
+    pub(crate) fn grow(&mut self, cells: usize) {
+        self.growth += cells;
+    }
+
+    fn draw(&self, renderer: &mut impl Renderer, theme: &Theme, grid: &Grid) -> Result<()> {
+        for (i, cell) in self.body.iter().enumerate() {
+            draw_cell(renderer, theme, grid, *cell, theme.snake[i % 2])?;
+        }
+        // unless something's moved straight in, like the head, or the tail
+        // left a cell the snake was crossing itself on
+        if let Some(wake) = self.wake
+            && grid.is_empty(wake)
+        {
+            clear_cell(renderer, theme, grid, wake)?;
+        }
+
+        Ok(())
+    }
+
+    // turns the snake end to end, so the tail leads off the way it was
+    // pointing
+    // with `WallMode::Bounce`, a snake about to go into a wall turns round
+    // where it is instead, and that's its move gone. whether it did
+    pub(crate) fn bounce(&mut self, config: &GameConfig, grid: &Grid) -> bool {
+        let next = neighbour(config, self.head(), self.direction);
+        if config.wall_mode != WallMode::Bounce || grid.get(next) != Tile::Wall {
+            return false;
+        }
+        self.reverse(config);
+        true
+    }
+
+    fn reverse(&mut self, config: &GameConfig) {
+        self.body.make_contiguous().reverse();
+        let (head, neck) = (self.body[0], self.body[1]);
+        // through a portal the two aren't side by side
+        self.direction = Direction::ALL
+            .into_iter()
+            .find(|direction| neighbour(config, neck, *direction) == head)
+            .unwrap_or(self.direction.opposite());
+        self.heading = self.direction;
+        self.wake = None;
+    }
+
+    // moves the head to `head`, keeping `grid` in sync, and returns whatever
+    // was in that cell. the tail is lifted first so chasing it closely isn't
+    // a collision
+    fn slither(&mut self, grid: &mut Grid, head: Coord) -> Tile {
+        self.lift_tail(grid);
+        self.push_head(grid, head)
+    }
+
+    // the first half of a move, split out so several snakes can all lift
+    // their tails before any of them lands
+    pub(crate) fn lift_tail(&mut self, grid: &mut Grid) {
+        if self.growth > 0 {
+            self.growth -= 1;
+            self.wake = None;
+        } else {
+            self.wake = self.body.pop_back();
+            if let Some(wake) = self.wake {
+                grid.set(wake, Tile::Empty);
+            }
+        }
+    }
+
+    pub(crate) fn push_head(&mut self, grid: &mut Grid, head: Coord) -> Tile {
+        let hit = grid.get(head);
+        self.body.push_front(head);
+        self.heading = self.direction;
+        if hit != Tile::Wall {
+            grid.set(head, Tile::Snake);
+        }
+        hit
+    }
+}
+
+/// Something to eat on the board. Whatever the food, it's an apple to
+/// the grid.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Apple {
+    position: Coord,
+    // older saves only had cherries
+    #[serde(default)]
+    food: Food,
+    // which it is in order, from one, or zero when the order doesn't matter
+    #[serde(default)]
+    number: u8,
+    // the tick it came out on
+    #[serde(default)]
+    born: u64,
+}
+
+impl Apple {
+    pub(crate) fn new(position: Coord) -> Apple {
+        Apple {
+            position,
+            food: Food::Cherry,
+            number: 0,
+            born: 0,
+        }
+    }
+
+    pub fn position(&self) -> Coord {
+        self.position
+    }
+
+    pub fn food(&self) -> Food {
+        self.food
+    }
+
+    /// Which it is in order, with `GameConfig::ordered`.
+    pub fn number(&self) -> Option<u8> {
+        (self.number > 0).then_some(self.number)
+    }
+
+    // a cherry somewhere free, unless nowhere is
+    pub(crate) fn spawn(grid: &Grid, rng: &mut impl Rng) -> Option<Apple> {
+        grid.random_free(rng).map(Apple::new)
+    }
+
+    fn draw(&self, renderer: &mut impl Renderer, theme: &Theme, grid: &Grid) -> Result<()> {
+        let mut glyph = theme.food(self.food);
+        // its number instead, in the food's color
+        if self.number > 0
+            && let Some(digit) = char::from_digit(self.number.into(), 10)
+        {
+            glyph.symbol = digit;
+        }
+        draw_cell(renderer, theme, grid, self.position, glyph)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot;
+    use crate::config::SpawnPattern;
+    use crate::render::{HeadlessRenderer, Renderer};
+
+    // everything on the screen, drawn from scratch, a line a row
+    fn drawn(game: &Game) -> Vec<String> {
+        let mut renderer = HeadlessRenderer::new(game.width(), game.height() + 1);
+        game.draw_border(&mut renderer).unwrap();
+        game.draw(&mut renderer).unwrap();
+        renderer.present().unwrap();
+        renderer.snapshot().lines().map(String::from).collect()
+    }
+
+    // a snake two cells short of an apple, with another out of the way
+    fn lined_up(width: u16) -> Game {
+        let mut config = GameConfig::builder()
+            .size(width, 6)
+            .seed(1)
+            .build()
+            .unwrap();
+        let [x, y] = Game::new(config.clone()).snake().head();
+        config.spawn_pattern = SpawnPattern::Sequence;
+        config.apple_cells = vec![[x + 2, y], [2, 1]];
+        Game::new(config)
+    }
+
+    #[test]
+    fn the_board_draws_as_expected() {
+        let mut game = lined_up(12);
+        assert_eq!(
+            drawn(&game),
+            [
+                "████████████",
+                "█          █",
+                "█          █",
+                "█ $$$ @    █",
+                "█          █",
+                "████████████",
+                "Score: 0    ",
+            ]
+        );
+        game.tick(None);
+        game.tick(None);
+        // the apple's eaten, the next one's out, and the trail's fading
+        assert_eq!(
+            drawn(&game),
+            [
+                "████████████",
+                "█ @        █",
+                "█          █",
+                "█ .:$$$    █",
+                "█          █",
+                "████████████",
+                "Score: 1    ",
+            ]
+        );
+    }
+
+    #[test]
+    fn the_score_line_keeps_to_whatever_help_fits() {
+        let help = vec!["? for help, Esc to exit".into(), "? for help".into()];
+        let mut wide = lined_up(45);
+        let mut narrow = lined_up(30);
+        for game in [&mut wide, &mut narrow] {
+            game.set_high_score(40);
+            game.set_help(help.clone());
+            game.tick(None);
+            game.tick(None);
+        }
+        assert_eq!(
+            drawn(&wide).last().unwrap(),
+            "Score: 1  High: 40    ? for help, Esc to exit"
+        );
+        assert_eq!(
+            drawn(&narrow).last().unwrap(),
+            "Score: 1  High: 40  ? for help"
+        );
+        // and the high score keeps up with a better one
+        narrow.set_high_score(0);
+        assert_eq!(
+            drawn(&narrow).last().unwrap(),
+            "Score: 1  High: 1   ? for help"
+        );
+    }
+
+    #[test]
+    fn blocks_come_down_every_hundred_moves_however_slow() {
+        let config = GameConfig::builder()
+            .size(20, 12)
+            .seed(3)
+            .obstacles(true)
+            .build()
+            .unwrap();
+        let mut game = Game::new(config);
+        game.set_slow(true);
+        let mut bot = bot::named("bfs").unwrap();
+        let mut dropped = vec![];
+        for _ in 0..250 {
+            let direction = bot.decide(&game);
+            game.tick(direction);
+            if game
+                .events()
+                .iter()
+                .any(|event| matches!(event, GameEvent::ObstacleSpawned { .. }))
+            {
+                dropped.push(game.ticks());
+            }
+        }
+        assert!(!game.is_over());
+        assert_eq!(dropped, [OBSTACLE_TICKS, 2 * OBSTACLE_TICKS]);
+    }
+
+    #[test]
+    fn a_restored_game_carries_on_the_same() {
+        let config = GameConfig::builder().size(16, 10).seed(42).build().unwrap();
+        let mut game = Game::new(config);
+        // the bot steers the original, and the copy gets the same moves
+        let mut bot = bot::named("bfs").unwrap();
+        for _ in 0..40 {
+            let direction = bot.decide(&game);
+            game.tick(direction);
+        }
+        let json = game.snapshot().to_json();
+        let mut restored = Game::restore(Snapshot::from_json(&json).unwrap()).unwrap();
+        assert_eq!(restored.snapshot().to_json(), json);
+        for _ in 0..200 {
+            let direction = bot.decide(&game);
+            let (one, two) = (game.tick(direction), restored.tick(direction));
+            assert_eq!(
+                (one.moved, one.ate, one.died),
+                (two.moved, two.ate, two.died)
+            );
+            assert_eq!(game.score(), restored.score());
+            assert_eq!(game.snapshot().to_json(), restored.snapshot().to_json());
+        }
+        assert!(game.score() > 0, "nothing was eaten to compare");
+    }
+}
@@ -0,0 +1,106 @@
+//! Racing an earlier run.
+//!
+//! A ghost is a `RunSummary` played back on a game of its own, turn for
+//! turn on the same seed, and drawn dimly under the live snake.
+
+use crate::config::GameConfig;
+use crate::game;
+use crate::grid::{Coord, Grid};
+use crate::render::Renderer;
+use crate::replay::Playback;
+use crate::results::RunSummary;
+use crate::scores::board_key;
+use crate::theme::Glyph;
+use crate::{RakeError, Result};
+use crossterm::style::Color;
+
+pub struct Ghost {
+    playback: Playback,
+    // what was drawn last time, to rub out what's moved off
+    drawn: Vec<Coord>,
+    // what's being drawn this time, kept to save allocating it every frame
+    body: Vec<Coord>,
+}
+
+impl Ghost {
+    /// Sets `config` up to race `run`, which means playing on its seed,
+    /// and errors if it was recorded on some other board.
+    pub fn new(run: &RunSummary, config: &mut GameConfig) -> Result<Ghost> {
+        let recorded = GameConfig {
+            width: run.width,
+            height: run.height,
+            wall_mode: run.mode,
+            scoring: run.scoring,
+            ..config.clone()
+        };
+        if board_key(&recorded) != board_key(config) {
+            return Err(RakeError::Config(format!(
+                "the ghost ran on {}, not {}",
+                board_key(&recorded),
+                board_key(config)
+            )));
+        }
+        let Some(seed) = run.seed else {
+            return Err(RakeError::Config(
+                "the ghost's run has no seed, so it can't be played back".into(),
+            ));
+        };
+        if config.seed.is_some_and(|chosen| chosen != seed) {
+            return Err(RakeError::Config(format!(
+                "the ghost ran on seed {seed}, so --seed has to match or go"
+            )));
+        }
+        config.seed = Some(seed);
+        Ok(Ghost {
+            playback: Playback::new(run, config.clone()),
+            drawn: vec![],
+            body: vec![],
+        })
+    }
+
+    /// Whether the ghost's run has ended, one way or the other.
+    pub fn is_done(&self) -> bool {
+        self.playback.is_done()
+    }
+
+    /// Moves the ghost on one step, making whatever turn it made then.
+    pub fn tick(&mut self) {
+        self.playback.tick();
+    }
+
+    /// Steps without drawing until the ghost is at tick `ticks`, for
+    /// picking up a saved game partway.
+    pub fn advance_to(&mut self, ticks: u64) {
+        self.playback.advance_to(ticks);
+    }
+
+    /// Draws the ghost onto cells that are empty on `live`, the board being
+    /// played, so it never covers anything real.
+    pub fn draw(&mut self, renderer: &mut impl Renderer, live: &Grid) -> Result<()> {
+        let game = self.playback.game();
+        let theme = &game.config().theme;
+        // without color, a different shape is the only way to look faint
+        let glyph = if theme.snake[0].color == Color::Reset {
+            Glyph::new('.', Color::Reset)
+        } else {
+            Glyph::new(theme.snake[0].symbol, Color::DarkGrey)
+        };
+        // a run that was quit rather than lost stays where it stopped
+        self.body.clear();
+        if !game.is_over() {
+            self.body.extend(game.snake().body());
+        }
+        for cell in &self.drawn {
+            if !self.body.contains(cell) && live.is_empty(*cell) {
+                game::clear_cell(renderer, theme, live, *cell)?;
+            }
+        }
+        for cell in &self.body {
+            if live.is_empty(*cell) {
+                game::draw_cell(renderer, theme, live, *cell, glyph)?;
+            }
+        }
+        std::mem::swap(&mut self.drawn, &mut self.body);
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+//! Letter grades for finished runs, S at the top down to D, so a run on a
+//! small fast board can be held up against one on a big slow one.
+//!
+//! A run's rating is its score for every hundred cells inside the border,
+//! times how many times faster than a gentle pace the game went. The grade
+//! is the best one whose threshold the rating gets to.
+
+use crate::config::{GameConfig, Scoring};
+use crate::{RakeError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time;
+
+// the pace a game is taken to go at when working out its rating
+const PACE: time::Duration = time::Duration::from_millis(200);
+
+/// Best first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Grade {
+    S,
+    A,
+    B,
+    C,
+    D,
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = match self {
+            Grade::S => "S",
+            Grade::A => "A",
+            Grade::B => "B",
+            Grade::C => "C",
+            Grade::D => "D",
+        };
+        f.write_str(letter)
+    }
+}
+
+/// The rating each grade above D starts at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grades {
+    pub s: u32,
+    pub a: u32,
+    pub b: u32,
+    pub c: u32,
+}
+
+impl Default for Grades {
+    fn default() -> Grades {
+        Grades {
+            s: 40,
+            a: 25,
+            b: 12,
+            c: 5,
+        }
+    }
+}
+
+impl Grades {
+    /// Checks every grade asks for more than the one under it.
+    pub fn validate(&self) -> Result<()> {
+        if self.s > self.a && self.a > self.b && self.b > self.c {
+            return Ok(());
+        }
+        Err(RakeError::Config(format!(
+            "grades have to go down from s to c, got {}, {}, {} and {}",
+            self.s, self.a, self.b, self.c
+        )))
+    }
+
+    /// What scoring `score` on `config` earns.
+    pub fn grade(&self, score: u16, config: &GameConfig) -> Grade {
+        let rating = rating(score, config);
+        [
+            (self.s, Grade::S),
+            (self.a, Grade::A),
+            (self.b, Grade::B),
+            (self.c, Grade::C),
+        ]
+        .into_iter()
+        .find(|(threshold, _)| rating >= u64::from(*threshold))
+        .map_or(Grade::D, |(_, grade)| grade)
+    }
+}
+
+/// `score` for every hundred cells of `config`'s board, times how much
+/// faster than `PACE` it goes. Scoring by speed has that in the score
+/// already, so it isn't counted twice.
+pub fn rating(score: u16, config: &GameConfig) -> u64 {
+    let cells = u64::from(config.width - 2) * u64::from(config.height - 2);
+    let (pace, tick) = match config.scoring {
+        Scoring::Speed => (1, 1),
+        _ => (
+            PACE.as_millis() as u64,
+            config.tick_rate.as_millis().max(1) as u64,
+        ),
+    };
+    u64::from(score) * 100 * pace / (cells * tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a hundred cells inside the border
+    fn board(ms: u64, scoring: Scoring) -> GameConfig {
+        GameConfig::builder()
+            .size(12, 12)
+            .tick_rate(time::Duration::from_millis(ms))
+            .scoring(scoring)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn the_rating_is_per_hundred_cells_and_goes_up_with_the_pace() {
+        assert_eq!(rating(30, &board(200, Scoring::Flat)), 30);
+        assert_eq!(rating(30, &board(100, Scoring::Flat)), 60);
+        assert_eq!(rating(30, &board(400, Scoring::Flat)), 15);
+        // a board four times the size needs four times the score
+        let big = GameConfig::builder().size(22, 22).build().unwrap();
+        assert_eq!(rating(30, &big), rating(30, &board(100, Scoring::Flat)) / 4);
+    }
+
+    #[test]
+    fn speed_scoring_isnt_counted_twice() {
+        assert_eq!(rating(30, &board(100, Scoring::Speed)), 30);
+    }
+
+    #[test]
+    fn each_grade_starts_at_its_threshold() {
+        let grades = Grades::default();
+        let config = board(200, Scoring::Flat);
+        let grade = |score| grades.grade(score, &config);
+        assert_eq!(
+            [grade(40), grade(39), grade(25), grade(12)],
+            [Grade::S, Grade::A, Grade::A, Grade::B]
+        );
+        assert_eq!(
+            [grade(5), grade(4), grade(0)],
+            [Grade::C, Grade::D, Grade::D]
+        );
+    }
+
+    #[test]
+    fn grades_have_to_go_down() {
+        assert!(Grades::default().validate().is_ok());
+        let grades = Grades {
+            b: 25,
+            ..Grades::default()
+        };
+        assert!(grades.validate().is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::config::MAX_TICK_RATE;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time;
+
+/// A board position as `[x, y]`, with `[0, 0]` in the top left corner.
+pub type Coord = [i16; 2];
+
+// the discriminants are part of the plugin interface, don't reorder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Tile {
+    Empty = 0,
+    Wall = 1,
+    Snake = 2,
+    Apple = 3,
+}
+
+/// What's underfoot on a cell, as opposed to whatever's sitting on it.
+/// Levels lay it out in zones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ground {
+    #[default]
+    Plain,
+    /// Twice as fast while the head's on it.
+    Ice,
+    /// Half as fast while the head's on it.
+    Mud,
+}
+
+impl Ground {
+    /// How long a tick lasts with the head on this ground, when it'd
+    /// usually be `tick_rate`.
+    pub fn tick_rate(self, tick_rate: time::Duration) -> time::Duration {
+        match self {
+            Ground::Plain => tick_rate,
+            Ground::Ice => tick_rate / 2,
+            Ground::Mud => (tick_rate * 2).min(MAX_TICK_RATE),
+        }
+    }
+}
+
+/// What's sitting on every cell of the board, kept up to date as things
+/// move so lookups never have to scan the snake or the wall list.
+///
+/// Anything outside the board reads as `Tile::Wall`.
+pub struct Grid {
+    width: u16,
+    height: u16,
+    tiles: Vec<Tile>,
+    // never changes once the level's laid out, unlike `tiles`
+    ground: Vec<Ground>,
+    // a Fenwick tree counting the empty cells by index, so a random one
+    // can be picked by counting along to it rather than hunting for it.
+    // which one a pick lands on only depends on what's where, so a
+    // restored game picks the same as the one it came from
+    free: Vec<u32>,
+    empty: usize,
+}
+
+impl Grid {
+    pub fn new(width: u16, height: u16) -> Grid {
+        let size = width as usize * height as usize;
+        // with everything empty, each node counts the whole span it covers
+        let free = (0..=size).map(|node| (node & node.wrapping_neg()) as u32);
+        Grid {
+            width,
+            height,
+            tiles: vec![Tile::Empty; size],
+            ground: vec![Ground::Plain; size],
+            free: free.collect(),
+            empty: size,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Every tile, row by row from the top left.
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    fn index(&self, cell: Coord) -> Option<usize> {
+        if cell[0] >= 0
+            && cell[1] >= 0
+            && (cell[0] as u16) < self.width
+            && (cell[1] as u16) < self.height
+        {
+            Some(cell[1] as usize * self.width as usize + cell[0] as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, cell: Coord) -> Tile {
+        self.index(cell).map_or(Tile::Wall, |i| self.tiles[i])
+    }
+
+    /// Writes outside the board are ignored.
+    pub fn set(&mut self, cell: Coord, tile: Tile) {
+        let Some(i) = self.index(cell) else {
+            return;
+        };
+        match (self.tiles[i] == Tile::Empty, tile == Tile::Empty) {
+            (true, false) => {
+                self.empty -= 1;
+                self.count_free(i, u32::wrapping_sub);
+            }
+            (false, true) => {
+                self.empty += 1;
+                self.count_free(i, u32::wrapping_add);
+            }
+            _ => {}
+        }
+        self.tiles[i] = tile;
+    }
+
+    // one more or one fewer empty cell at index `i`
+    fn count_free(&mut self, i: usize, change: fn(u32, u32) -> u32) {
+        let mut node = i + 1;
+        while let Some(count) = self.free.get_mut(node) {
+            *count = change(*count, 1);
+            node += node & node.wrapping_neg();
+        }
+    }
+
+    // the index of the `rank`th empty cell, from 0, counting row by row
+    fn nth_free(&self, mut rank: usize) -> usize {
+        let size = self.tiles.len();
+        let mut at = 0;
+        let mut step = if size == 0 { 0 } else { 1 << size.ilog2() };
+        while step > 0 {
+            if at + step <= size && (self.free[at + step] as usize) <= rank {
+                at += step;
+                rank -= self.free[at] as usize;
+            }
+            step >>= 1;
+        }
+        at
+    }
+
+    /// The ground at `cell`. Off the board it's all plain.
+    pub fn ground(&self, cell: Coord) -> Ground {
+        self.index(cell).map_or(Ground::Plain, |i| self.ground[i])
+    }
+
+    /// Writes outside the board are ignored.
+    pub fn set_ground(&mut self, cell: Coord, ground: Ground) {
+        if let Some(i) = self.index(cell) {
+            self.ground[i] = ground;
+        }
+    }
+
+    /// How many cells hold `tile`. Scans the whole board.
+    pub fn count(&self, tile: Tile) -> usize {
+        self.tiles.iter().filter(|t| **t == tile).count()
+    }
+
+    pub fn is_empty(&self, cell: Coord) -> bool {
+        self.get(cell) == Tile::Empty
+    }
+
+    /// An empty cell picked at random, or `None` if the board is full.
+    pub fn random_free(&self, rng: &mut (impl Rng + ?Sized)) -> Option<Coord> {
+        if self.empty == 0 {
+            return None;
+        }
+        let i = self.nth_free(rng.random_range(0..self.empty));
+        let width = self.width as usize;
+        Some([(i % width) as i16, (i / width) as i16])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    // `free` counts exactly the empty cells, and counting along it finds
+    // each of them in turn
+    fn check(grid: &Grid) {
+        let empty: Vec<usize> = (0..grid.tiles.len())
+            .filter(|i| grid.tiles[*i] == Tile::Empty)
+            .collect();
+        assert_eq!(grid.empty, empty.len());
+        for (rank, i) in empty.iter().enumerate() {
+            assert_eq!(grid.nth_free(rank), *i);
+        }
+    }
+
+    #[test]
+    fn setting_the_same_cell_again_keeps_the_free_cells_right() {
+        let mut grid = Grid::new(4, 3);
+        grid.set([1, 1], Tile::Wall);
+        grid.set([1, 1], Tile::Snake);
+        check(&grid);
+        grid.set([1, 1], Tile::Empty);
+        grid.set([1, 1], Tile::Empty);
+        check(&grid);
+        grid.set([1, 1], Tile::Apple);
+        check(&grid);
+        assert_eq!(grid.empty, 11);
+    }
+
+    #[test]
+    fn the_free_cells_follow_random_changes() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut grid = Grid::new(9, 6);
+        let tiles = [Tile::Empty, Tile::Wall, Tile::Snake, Tile::Apple];
+        for _ in 0..2000 {
+            let cell = [rng.random_range(-1..10), rng.random_range(-1..7)];
+            grid.set(cell, tiles[rng.random_range(0..tiles.len())]);
+            check(&grid);
+        }
+        while let Some(cell) = grid.random_free(&mut rng) {
+            grid.set(cell, Tile::Wall);
+            check(&grid);
+        }
+        assert_eq!(grid.count(Tile::Empty), 0);
+    }
+
+    #[test]
+    fn the_same_tiles_pick_the_same_cell_however_they_got_there() {
+        let mut one = Grid::new(5, 5);
+        let mut two = Grid::new(5, 5);
+        for cell in [[1, 1], [3, 2], [0, 4]] {
+            one.set(cell, Tile::Wall);
+        }
+        for cell in [[0, 4], [2, 2], [1, 1], [3, 2]] {
+            two.set(cell, Tile::Wall);
+        }
+        two.set([2, 2], Tile::Empty);
+        check(&one);
+        check(&two);
+        let (mut a, mut b) = (ChaCha8Rng::seed_from_u64(3), ChaCha8Rng::seed_from_u64(3));
+        for _ in 0..20 {
+            assert_eq!(one.random_free(&mut a), two.random_free(&mut b));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Where snakes die, and of what, tallied across every game on each
+//! board.
+
+use crate::Result;
+use crate::config::GameConfig;
+use crate::game::DeathCause;
+use crate::grid::Coord;
+use crate::scores::board_key;
+use crate::storage::{self, Versioned};
+use crossterm::style::{Color, Stylize};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Death counts for one board, one per cell, row by row from the top left,
+/// and how many of them each cause was.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Heatmap {
+    width: u16,
+    height: u16,
+    counts: Vec<u32>,
+    // deaths from before causes were kept aren't in here
+    #[serde(default)]
+    causes: BTreeMap<DeathCause, u32>,
+}
+
+impl Heatmap {
+    pub fn new(width: u16, height: u16) -> Heatmap {
+        Heatmap {
+            width,
+            height,
+            counts: vec![0; width as usize * height as usize],
+            causes: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, cell: Coord) -> u32 {
+        self.index(cell).map_or(0, |i| self.counts[i])
+    }
+
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Every cause there's been a death of and how many, most first.
+    pub fn causes(&self) -> Vec<(DeathCause, u32)> {
+        let mut causes: Vec<(DeathCause, u32)> = self
+            .causes
+            .iter()
+            .map(|(cause, count)| (*cause, *count))
+            .collect();
+        causes.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        causes
+    }
+
+    fn index(&self, cell: Coord) -> Option<usize> {
+        let (x, y) = (cell[0], cell[1]);
+        if x >= 0 && y >= 0 && (x as u16) < self.width && (y as u16) < self.height {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The board as text, hotter cells in denser shades (and warmer colors
+    /// unless `color` is off). Ends every row with a newline.
+    pub fn render(&self, color: bool, ascii: bool) -> String {
+        let shades = if ascii {
+            ['.', ':', '+', '#', '@']
+        } else {
+            ['·', '░', '▒', '▓', '█']
+        };
+        const COLORS: [Color; 5] = [
+            Color::DarkGrey,
+            Color::Blue,
+            Color::Yellow,
+            Color::DarkYellow,
+            Color::Red,
+        ];
+        let hottest = self.counts.iter().copied().max().unwrap_or(0) as usize;
+        let mut out = String::new();
+        for y in 0..self.height as i16 {
+            for x in 0..self.width as i16 {
+                let count = self.get([x, y]);
+                // anything at all gets at least the faintest shade
+                let level = if count == 0 {
+                    0
+                } else {
+                    1 + (count as usize - 1) * (shades.len() - 2) / (hottest - 1).max(1)
+                };
+                if color {
+                    let _ = write!(out, "{}", shades[level].with(COLORS[level]));
+                } else {
+                    out.push(shades[level]);
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// A `Heatmap` for every board that's been played, kept in
+/// `deaths.json` next to the high scores.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeathLog {
+    boards: BTreeMap<String, Heatmap>,
+}
+
+impl DeathLog {
+    pub fn default_path() -> Option<PathBuf> {
+        storage::data_dir().map(|dir| dir.join("deaths.json"))
+    }
+
+    /// Reads `path`. A file that doesn't exist yet has no deaths in it.
+    pub fn load(path: &Path) -> Result<DeathLog> {
+        storage::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        storage::store(path, self)
+    }
+
+    /// Counts a death at `at` on boards like `config`'s, of `cause` if
+    /// it's known.
+    pub fn record(&mut self, config: &GameConfig, at: Coord, cause: Option<DeathCause>) {
+        let map = self
+            .boards
+            .entry(board_key(config))
+            .or_insert_with(|| Heatmap::new(config.width, config.height));
+        if let Some(i) = map.index(at) {
+            map.counts[i] += 1;
+        }
+        if let Some(cause) = cause {
+            *map.causes.entry(cause).or_default() += 1;
+        }
+    }
+
+    pub fn heatmap(&self, config: &GameConfig) -> Option<&Heatmap> {
+        self.boards.get(&board_key(config))
+    }
+}
+
+impl Versioned for DeathLog {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, data: Value) -> std::result::Result<Value, String> {
+        match version {
+            // the same shape, from before the file had a version
+            0 | 1 => Ok(data),
+            _ => Err(format!("don't know how to read version {version}")),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! The help overlay: every key and what it does, with whatever's been
+//! remapped, then how this board plays and anything switched on that
+//! changes the game, over the top of the board until a key's pressed.
+
+use crate::Result;
+use crate::config::{GameConfig, SpawnPattern, WallMode};
+use crate::game::{Direction, Game};
+use crate::input::{self, InputAction, Keymap};
+use crate::lang::Lang;
+use crate::render::Renderer;
+
+// what everything but turning is called in the list, in the order they're
+// listed
+const ACTIONS: [(InputAction, &str); 8] = [
+    (InputAction::Slow, "help-slow"),
+    (InputAction::Hint, "help-hint"),
+    (InputAction::Rewind, "help-rewind"),
+    (InputAction::Mute, "help-mute"),
+    (InputAction::Save, "help-save"),
+    (InputAction::Pause, "help-pause"),
+    (InputAction::Help, "help-help"),
+    (InputAction::Quit, "help-quit"),
+];
+
+/// The overlay's lines, in `lang`: the keys bound in `keymap`, the rules
+/// of `config`'s board, then a line for each of `modifiers`, which are
+/// `lang` keys for anything else the game's been started with.
+pub fn lines(keymap: &Keymap, config: &GameConfig, modifiers: &[&str], lang: &Lang) -> Vec<String> {
+    let keys = |action| {
+        let keys: Vec<String> = keymap.keys_for(action).map(input::key_name).collect();
+        keys.join("/")
+    };
+    // all four turns on one line, WASD order, to leave room for the rest
+    let moves = [
+        Direction::Up,
+        Direction::Left,
+        Direction::Down,
+        Direction::Right,
+    ]
+    .map(|direction| keys(InputAction::Turn(direction)));
+    let mut bound = vec![(lang.text("help-move"), moves.join(" "))];
+    bound.extend(ACTIONS.iter().filter_map(|(action, key)| {
+        let keys = keys(*action);
+        (!keys.is_empty()).then(|| (lang.text(key), keys))
+    }));
+    // labels padded to the longest, whatever language they're in
+    let label_width = bound
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = vec![lang.text("help-keys").to_string()];
+    lines.extend(
+        bound
+            .iter()
+            .map(|(label, keys)| format!("  {label:<label_width$}  {keys}")),
+    );
+
+    lines.extend([String::new(), lang.text("help-rules").to_string()]);
+    let walls = match config.wall_mode {
+        WallMode::Fatal => "help-fatal",
+        WallMode::Wrap => "help-wrap",
+        WallMode::Bounce => "help-bounce",
+    };
+    let rule = lang
+        .text(&format!("scoring-{}", config.scoring.name()))
+        .to_string();
+    let mut rules = vec![
+        lang.text(walls).to_string(),
+        lang.format("run-scoring", &[("rule", &rule)]),
+    ];
+    for (on, key) in [
+        (config.casual, "help-casual"),
+        (config.rooms, "help-rooms"),
+        (config.bugs, "help-bugs"),
+        (config.ordered, "help-ordered"),
+        (config.decay, "help-decay"),
+        (config.obstacles, "help-obstacles"),
+        (config.slow_vertical, "help-slow-vertical"),
+    ] {
+        if on {
+            rules.push(lang.text(key).to_string());
+        }
+    }
+    if config.spawn_pattern != SpawnPattern::Random {
+        rules.push(
+            lang.text(&format!("help-spawn-{}", config.spawn_pattern.name()))
+                .to_string(),
+        );
+    }
+    rules.extend(modifiers.iter().map(|key| lang.text(key).to_string()));
+    lines.extend(rules.into_iter().map(|rule| format!("  {rule}")));
+
+    lines.extend([String::new(), lang.text("win-continue").to_string()]);
+    lines
+}
+
+/// Draws `lines` over the middle of `game`'s board, cutting off whatever
+/// doesn't fit.
+pub fn draw(renderer: &mut impl Renderer, game: &Game, lines: &[String]) -> Result<()> {
+    let widest = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    // a margin either side, unless the board's too narrow for one
+    let width = (widest + 4).min(game.width() as usize);
+    let left = (game.width() - width as u16) / 2;
+    let shown = lines.len().min(game.height() as usize);
+    let top = (game.height() - shown as u16) / 2;
+    let color = game.config().theme.text;
+    for (i, line) in lines.iter().take(shown).enumerate() {
+        let line = format!("  {line}");
+        renderer.draw_text(
+            left,
+            top + i as u16,
+            &format!("{line:<width$.width$}"),
+            color,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! A training aid: a dotted line from the head to an apple, the way the
+//! `astar` AI would go, to follow or not. It goes where the snake could
+//! still get out of afterwards, so following it never leads into a trap.
+
+use crate::Result;
+use crate::bot;
+use crate::game::{self, Game};
+use crate::grid::Coord;
+use crate::render::Renderer;
+use crate::theme::Glyph;
+use crossterm::style::Color;
+
+/// The line, whether it's showing or not.
+#[derive(Clone, Debug, Default)]
+pub struct Hint {
+    shown: bool,
+    // the cells dotted last time, to wipe when the line moves on
+    drawn: Vec<Coord>,
+}
+
+impl Hint {
+    pub fn new() -> Hint {
+        Hint::default()
+    }
+
+    /// Shows the line, or hides it if it's showing.
+    pub fn toggle(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Draws the line as it is for `game` now, if it's showing, onto
+    /// empty cells only, and wipes whatever's left of the last one.
+    pub fn draw(&mut self, renderer: &mut impl Renderer, game: &Game) -> Result<()> {
+        let theme = &game.config().theme;
+        let grid = game.grid();
+        // dim like the ghost, or without color, as small as it gets
+        let glyph = if theme.snake[0].color == Color::Reset {
+            Glyph::new('.', Color::Reset)
+        } else {
+            Glyph::new('.', Color::DarkGrey)
+        };
+        let path = match self.shown && !game.is_over() {
+            true => bot::safe_path(game).unwrap_or_default(),
+            false => vec![],
+        };
+        for cell in &self.drawn {
+            if !path.contains(cell) && grid.is_empty(*cell) {
+                game::clear_cell(renderer, theme, grid, *cell)?;
+            }
+        }
+        for cell in &path {
+            if grid.is_empty(*cell) {
+                game::draw_cell(renderer, theme, grid, *cell, glyph)?;
+            }
+        }
+        self.drawn = path;
+        Ok(())
+    }
+}
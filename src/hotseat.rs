@@ -0,0 +1,250 @@
+//! Hotseat: two players on one keyboard taking turns at whole runs, both
+//! on the same seed, best of however many rounds. Each round goes to
+//! whoever scores more on it, and who goes first swaps every round so
+//! neither always has a score to beat.
+//!
+//! The series is over once someone's won more rounds than the other could
+//! still catch up with, or every round's been played.
+
+use crate::config::GameConfig;
+use crate::game::Game;
+use crate::lang::Lang;
+use crate::{RakeError, Result};
+use rand::Rng;
+
+/// Two players' runs, round by round.
+pub struct Series {
+    config: GameConfig,
+    names: [String; 2],
+    rounds: u32,
+    // each round's scores, player one's first, with the round being played
+    // last until both have had a go
+    scores: Vec<[Option<u16>; 2]>,
+}
+
+impl Series {
+    /// Every run is `config`, on its seed or, without one, the same one
+    /// picked at random.
+    pub fn new(mut config: GameConfig, names: [String; 2], rounds: u32) -> Result<Series> {
+        if rounds == 0 {
+            return Err(RakeError::Config(
+                "a series needs at least one round".into(),
+            ));
+        }
+        config.seed.get_or_insert_with(|| rand::rng().random());
+        Ok(Series {
+            config,
+            names,
+            rounds,
+            scores: vec![],
+        })
+    }
+
+    /// `player` is 0 or 1.
+    pub fn name(&self, player: usize) -> &str {
+        &self.names[player]
+    }
+
+    pub fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    /// The round being played, from one.
+    pub fn round(&self) -> u32 {
+        match self.scores.last() {
+            Some([Some(_), Some(_)]) | None => self.scores.len() as u32 + 1,
+            Some(_) => self.scores.len() as u32,
+        }
+    }
+
+    /// Whose run is next, or `None` once it's over. Player one starts the
+    /// odd rounds and player two the even ones.
+    pub fn next(&self) -> Option<usize> {
+        if self.is_over() {
+            return None;
+        }
+        let first = (self.round() as usize + 1) % 2;
+        match self.scores.last() {
+            Some([one, two]) if one.is_some() != two.is_some() => Some(1 - first),
+            _ => Some(first),
+        }
+    }
+
+    /// What the player up next has to beat, if the other's already been.
+    pub fn to_beat(&self) -> Option<u16> {
+        let player = self.next()?;
+        match self.scores.last() {
+            Some(scores) if scores[player].is_none() => scores[1 - player],
+            _ => None,
+        }
+    }
+
+    /// A fresh game for the next run.
+    pub fn game(&self) -> Game {
+        Game::new(self.config.clone())
+    }
+
+    /// Puts `score` down for whoever's run it was.
+    pub fn record(&mut self, score: u16) {
+        let Some(player) = self.next() else {
+            return;
+        };
+        if self.round() > self.scores.len() as u32 {
+            self.scores.push([None, None]);
+        }
+        if let Some(scores) = self.scores.last_mut() {
+            scores[player] = Some(score);
+        }
+    }
+
+    /// Who won round `round`, from one, once both have played it. `None`
+    /// for a tie or a round that isn't done.
+    pub fn round_winner(&self, round: u32) -> Option<usize> {
+        let [Some(one), Some(two)] = *self.scores.get(round.checked_sub(1)? as usize)? else {
+            return None;
+        };
+        match one.cmp(&two) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// How many rounds each has won.
+    pub fn wins(&self) -> [u32; 2] {
+        let mut wins = [0; 2];
+        for round in 1..=self.scores.len() as u32 {
+            if let Some(winner) = self.round_winner(round) {
+                wins[winner] += 1;
+            }
+        }
+        wins
+    }
+
+    /// Once every round's been played, or one player's too far ahead to be
+    /// caught in the rounds left.
+    pub fn is_over(&self) -> bool {
+        let done = self
+            .scores
+            .iter()
+            .filter(|scores| scores.iter().all(Option::is_some))
+            .count() as u32;
+        let [one, two] = self.wins();
+        done == self.rounds || one.abs_diff(two) > self.rounds - done
+    }
+
+    /// Whoever's won more rounds, once it's over. `None` for a tie.
+    pub fn winner(&self) -> Option<usize> {
+        if !self.is_over() {
+            return None;
+        }
+        let [one, two] = self.wins();
+        match one.cmp(&two) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// The scoreboard, in `lang`: a row for each round so far with both
+    /// scores and a `*` by the better one, then the rounds each has won.
+    pub fn scoreboard(&self, lang: &Lang) -> Vec<String> {
+        let label = lang.text("hotseat-round");
+        let wins = lang.text("hotseat-wins");
+        let label_width = label.chars().count().max(wins.chars().count()) + 3;
+        // wide enough for the names and any score, and a star
+        let widths = self
+            .names
+            .each_ref()
+            .map(|name| name.chars().count().max(5) + 1);
+        let [w1, w2] = widths;
+        // everything lined up with the scores, not their stars
+        let [one, two] = self.names.each_ref().map(|name| format!("{name} "));
+        let mut lines = vec![format!("{:<label_width$}  {one:>w1$}  {two:>w2$}", "")];
+        for (i, scores) in self.scores.iter().enumerate() {
+            let round = i as u32 + 1;
+            let winner = self.round_winner(round);
+            let [s1, s2] = [0, 1].map(|player| {
+                let star = if winner == Some(player) { "*" } else { " " };
+                scores[player].map_or(String::from("- "), |score| format!("{score}{star}"))
+            });
+            lines.push(format!(
+                "{:<label_width$}  {s1:>w1$}  {s2:>w2$}",
+                format!("{label} {round}")
+            ));
+        }
+        let [won1, won2] = self.wins().map(|won| format!("{won} "));
+        lines.push(format!("{wins:<label_width$}  {won1:>w1$}  {won2:>w2$}"));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(rounds: u32) -> Series {
+        let config = GameConfig::builder().seed(1).build().unwrap();
+        Series::new(config, ["Player 1".into(), "Player 2".into()], rounds).unwrap()
+    }
+
+    #[test]
+    fn best_of_three_stops_once_someone_cant_be_caught() {
+        let mut series = series(3);
+        assert_eq!((series.next(), series.to_beat()), (Some(0), None));
+        series.record(10);
+        assert_eq!((series.next(), series.to_beat()), (Some(1), Some(10)));
+        series.record(5);
+        assert_eq!(series.round_winner(1), Some(0));
+        // player two goes first in the second round
+        assert_eq!(series.round(), 2);
+        assert_eq!((series.next(), series.to_beat()), (Some(1), None));
+        series.record(8);
+        assert_eq!((series.next(), series.to_beat()), (Some(0), Some(8)));
+        assert!(!series.is_over());
+        assert_eq!(series.winner(), None);
+        series.record(12);
+        assert_eq!(series.wins(), [2, 0]);
+        assert!(series.is_over());
+        assert_eq!(series.next(), None);
+        assert_eq!(series.winner(), Some(0));
+        // nobody's up, so there's nobody to put it down for
+        series.record(99);
+        assert_eq!(series.wins(), [2, 0]);
+    }
+
+    #[test]
+    fn a_tied_round_goes_to_nobody() {
+        let mut series = series(3);
+        for score in [5, 5, 3, 7] {
+            series.record(score);
+        }
+        assert_eq!(series.round_winner(1), None);
+        assert_eq!(series.round_winner(2), Some(0));
+        // one up with one to play could still be caught
+        assert!(!series.is_over());
+        assert_eq!(series.next(), Some(0));
+        series.record(2);
+        series.record(9);
+        assert_eq!(series.wins(), [1, 1]);
+        assert!(series.is_over());
+        assert_eq!(series.winner(), None);
+    }
+
+    #[test]
+    fn there_has_to_be_a_round() {
+        let config = GameConfig::builder().build().unwrap();
+        assert!(Series::new(config, ["a".into(), "b".into()], 0).is_err());
+    }
+
+    #[test]
+    fn the_scoreboard_lines_up() {
+        let mut series = series(3);
+        for score in [10, 5, 120] {
+            series.record(score);
+        }
+        let lines = series.scoreboard(&Lang::english());
+        let widths: Vec<usize> = lines.iter().map(|line| line.chars().count()).collect();
+        assert!(widths.iter().all(|width| *width == widths[0]), "{lines:#?}");
+    }
+}
@@ -0,0 +1,103 @@
+//! Pausing a game nobody's playing: once no key has been pressed for a
+//! while, a countdown goes up, and if still nothing's pressed by the end
+//! of it the game stops where it is instead of running the snake into a
+//! wall.
+
+use std::time;
+
+/// How long the countdown runs before pausing.
+pub const WARNING: time::Duration = time::Duration::from_secs(3);
+
+/// What to do about how long it's been since a key was pressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Nudge {
+    /// Say it's pausing in this many seconds.
+    Warn(u64),
+    /// A key's been pressed, so take the warning down.
+    Clear,
+    Pause,
+}
+
+/// Keeps track of when a key was last pressed.
+#[derive(Clone, Copy, Debug)]
+pub struct Idle {
+    limit: time::Duration,
+    last_input: time::Instant,
+    // the seconds the warning's showing, so it only changes when they do
+    warned: Option<u64>,
+}
+
+impl Idle {
+    /// Pauses once nothing's been pressed for `limit`, counting from `now`.
+    pub fn new(limit: time::Duration, now: time::Instant) -> Idle {
+        Idle {
+            limit,
+            last_input: now,
+            warned: None,
+        }
+    }
+
+    /// Notes a key pressed `now`, which starts the wait over.
+    pub fn input(&mut self, now: time::Instant) {
+        self.last_input = now;
+    }
+
+    /// What needs doing `now`, if anything has changed since last time.
+    pub fn check(&mut self, now: time::Instant) -> Option<Nudge> {
+        let idle = now.saturating_duration_since(self.last_input);
+        if idle >= self.limit {
+            self.warned = None;
+            self.last_input = now;
+            return Some(Nudge::Pause);
+        }
+        let left = self.limit - idle;
+        if left <= WARNING {
+            // rounded up, so it never says 0
+            let seconds = left.as_millis().div_ceil(1000) as u64;
+            if self.warned == Some(seconds) {
+                return None;
+            }
+            self.warned = Some(seconds);
+            return Some(Nudge::Warn(seconds));
+        }
+        self.warned.take().map(|_| Nudge::Clear)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: time::Duration = time::Duration::from_secs(10);
+
+    fn after(start: time::Instant, millis: u64) -> time::Instant {
+        start + time::Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn counts_down_then_pauses() {
+        let start = time::Instant::now();
+        let mut idle = Idle::new(LIMIT, start);
+        assert_eq!(idle.check(after(start, 5000)), None);
+        assert_eq!(idle.check(after(start, 7000)), Some(Nudge::Warn(3)));
+        // nothing new to say until the seconds change
+        assert_eq!(idle.check(after(start, 7500)), None);
+        assert_eq!(idle.check(after(start, 8001)), Some(Nudge::Warn(2)));
+        assert_eq!(idle.check(after(start, 9999)), Some(Nudge::Warn(1)));
+        assert_eq!(idle.check(after(start, 10_000)), Some(Nudge::Pause));
+        // and the wait starts over from the pause
+        assert_eq!(idle.check(after(start, 12_000)), None);
+        assert_eq!(idle.check(after(start, 17_000)), Some(Nudge::Warn(3)));
+    }
+
+    #[test]
+    fn a_key_takes_the_warning_down() {
+        let start = time::Instant::now();
+        let mut idle = Idle::new(LIMIT, start);
+        assert_eq!(idle.check(after(start, 8000)), Some(Nudge::Warn(2)));
+        idle.input(after(start, 8500));
+        assert_eq!(idle.check(after(start, 9000)), Some(Nudge::Clear));
+        assert_eq!(idle.check(after(start, 10_000)), None);
+        assert_eq!(idle.check(after(start, 18_500)), Some(Nudge::Pause));
+    }
+}
@@ -0,0 +1,363 @@
+use crate::game::Direction;
+use crate::lang::Lang;
+use crate::{RakeError, Result};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, read};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Something the player asked for, decoupled from whichever key produced it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputAction {
+    Turn(Direction),
+    /// Writes the game to disk to carry on with later, and keeps playing.
+    Save,
+    Quit,
+    /// Silences sound and music, or brings them back.
+    Mute,
+    /// Takes the last few moves back, in practice mode.
+    Rewind,
+    /// Shows a safe way to the apple, or stops showing it.
+    Hint,
+    /// Slows the game down to half speed, or back up again.
+    Slow,
+    /// Lists the controls and the rules over the board.
+    Help,
+    /// Stops the game until a key's pressed.
+    Pause,
+}
+
+/// A keyboard layout, for which letters the default controls turn with:
+/// whichever sit where W, A, S and D do on a QWERTY keyboard.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    #[default]
+    Qwerty,
+    /// ZQSD.
+    Azerty,
+    /// ,AOE.
+    Dvorak,
+    /// WARS.
+    Colemak,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 4] = [
+        Layout::Qwerty,
+        Layout::Azerty,
+        Layout::Dvorak,
+        Layout::Colemak,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Qwerty => "qwerty",
+            Layout::Azerty => "azerty",
+            Layout::Dvorak => "dvorak",
+            Layout::Colemak => "colemak",
+        }
+    }
+
+    /// The letters for up, left, down and right, in WASD order.
+    pub fn moves(self) -> [char; 4] {
+        match self {
+            Layout::Qwerty => ['w', 'a', 's', 'd'],
+            Layout::Azerty => ['z', 'q', 's', 'd'],
+            Layout::Dvorak => [',', 'a', 'o', 'e'],
+            Layout::Colemak => ['w', 'a', 'r', 's'],
+        }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = RakeError;
+
+    fn from_str(name: &str) -> Result<Layout> {
+        Layout::ALL
+            .into_iter()
+            .find(|layout| layout.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Layout::ALL.iter().map(|layout| layout.name()).collect();
+                RakeError::Config(format!(
+                    "unknown layout {name:?}, pick one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// Whether `event` is Ctrl+Z, for handing to `terminal::suspend`. It
+/// isn't in `Keymap` since it's the shell's key, not the game's.
+pub fn is_suspend(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('z' | 'Z'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            ..
+        })
+    )
+}
+
+/// Which keys do what.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<(KeyCode, InputAction)>,
+}
+
+impl Keymap {
+    /// A keymap with nothing bound.
+    pub fn empty() -> Keymap {
+        Keymap { bindings: vec![] }
+    }
+
+    /// Makes `key` do `action`, replacing whatever it did before.
+    pub fn bind(&mut self, key: KeyCode, action: InputAction) {
+        let key = normalize(key);
+        self.bindings.retain(|(bound, _)| *bound != key);
+        self.bindings.push((key, action));
+    }
+
+    /// Unbinds every key for `action`.
+    pub fn clear(&mut self, action: InputAction) {
+        self.bindings.retain(|(_, bound)| *bound != action);
+    }
+
+    pub fn action(&self, event: &Event) -> Option<InputAction> {
+        let Event::Key(key) = event else {
+            return None;
+        };
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        let code = normalize(key.code);
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == code)
+            .map(|(_, action)| *action)
+    }
+
+    /// A line on the controls for the HUD, then shorter versions of it,
+    /// for `Game::set_help`, in `lang`. Goes by the first key bound to each
+    /// action. With a key for the help overlay, that's all it points to,
+    /// since the overlay has the rest.
+    pub fn help(&self, lang: &Lang) -> Vec<String> {
+        let first = |action| self.keys_for(action).next();
+        let quit = first(InputAction::Quit).map(|key| key_name(key).to_uppercase());
+        if let Some(help) = first(InputAction::Help).map(key_name) {
+            let mut lines = vec![];
+            if let Some(quit) = &quit {
+                lines.push(lang.format("hud-help-keys", &[("help", &help), ("quit", quit)]));
+            }
+            lines.push(lang.format("hud-help-only", &[("help", &help)]));
+            return lines;
+        }
+        let moves = Direction::ALL.map(|direction| first(InputAction::Turn(direction)));
+        let moves = match moves {
+            [
+                Some(KeyCode::Up),
+                Some(KeyCode::Down),
+                Some(KeyCode::Left),
+                Some(KeyCode::Right),
+            ] => Some(lang.text("hud-arrows").to_string()),
+            // WASD order, whatever the letters are
+            [Some(up), Some(down), Some(left), Some(right)] => {
+                let names = [up, left, down, right].map(key_name);
+                if names.iter().all(|name| name.chars().count() == 1) {
+                    Some(names.concat())
+                } else {
+                    Some(names.join("/"))
+                }
+            }
+            _ => None,
+        };
+        match (moves, quit) {
+            (Some(moves), Some(quit)) => vec![
+                lang.format("hud-help", &[("moves", &moves), ("quit", &quit)]),
+                lang.format("hud-help-exit", &[("quit", &quit)]),
+            ],
+            (Some(moves), None) => vec![lang.format("hud-help-move", &[("moves", &moves)])],
+            (None, Some(quit)) => vec![lang.format("hud-help-exit", &[("quit", &quit)])],
+            (None, None) => vec![],
+        }
+    }
+
+    /// Every key bound to `action`, in the order they were bound.
+    pub fn keys_for(&self, action: InputAction) -> impl Iterator<Item = KeyCode> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+    }
+}
+
+impl Keymap {
+    /// The default controls, turning with `layout`'s WASD.
+    pub fn for_layout(layout: Layout) -> Keymap {
+        let mut keymap = Keymap::empty();
+        let [up, left, down, right] = layout.moves();
+        keymap.bind(KeyCode::Char(up), InputAction::Turn(Direction::Up));
+        keymap.bind(KeyCode::Char(left), InputAction::Turn(Direction::Left));
+        keymap.bind(KeyCode::Char(down), InputAction::Turn(Direction::Down));
+        keymap.bind(KeyCode::Char(right), InputAction::Turn(Direction::Right));
+        keymap.bind(KeyCode::F(5), InputAction::Save);
+        keymap.bind(KeyCode::Esc, InputAction::Quit);
+        keymap.bind(KeyCode::Char('m'), InputAction::Mute);
+        keymap.bind(KeyCode::Char('u'), InputAction::Rewind);
+        keymap.bind(KeyCode::Char('h'), InputAction::Hint);
+        keymap.bind(KeyCode::Char('x'), InputAction::Slow);
+        keymap.bind(KeyCode::Char('?'), InputAction::Help);
+        keymap.bind(KeyCode::Char('p'), InputAction::Pause);
+        keymap
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap::for_layout(Layout::Qwerty)
+    }
+}
+
+// letters match regardless of shift or caps lock
+fn normalize(key: KeyCode) -> KeyCode {
+    match key {
+        KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+        key => key,
+    }
+}
+
+/// Parses a key the way config files spell them: a single character
+/// (`"w"`), or a name like `"Up"`, `"Esc"`, `"Space"` or `"F5"`. Names
+/// ignore case.
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let name = name.to_ascii_lowercase();
+    if let Some(n) = name.strip_prefix('f').and_then(|n| n.parse().ok())
+        && (1..=12).contains(&n)
+    {
+        return Some(KeyCode::F(n));
+    }
+    match name.as_str() {
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "esc" | "escape" => Some(KeyCode::Esc),
+        "enter" | "return" => Some(KeyCode::Enter),
+        "space" => Some(KeyCode::Char(' ')),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        _ => None,
+    }
+}
+
+/// The inverse of `parse_key`, for showing bindings to the player.
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".into(),
+        KeyCode::Char(c) => c.to_ascii_uppercase().to_string(),
+        KeyCode::Up => "Up".into(),
+        KeyCode::Down => "Down".into(),
+        KeyCode::Left => "Left".into(),
+        KeyCode::Right => "Right".into(),
+        KeyCode::Esc => "Esc".into(),
+        KeyCode::Enter => "Enter".into(),
+        KeyCode::Tab => "Tab".into(),
+        KeyCode::Backspace => "Backspace".into(),
+        key => key.to_string(),
+    }
+}
+
+/// Turns raw bytes from a terminal into key events, for input that doesn't
+/// come through crossterm, like an SSH session. Understands printable
+/// characters, Enter, Tab, Backspace, Ctrl+letter, arrow keys and F1 to
+/// F12; anything else is skipped.
+///
+/// An Esc at the very end of `bytes` counts as the Esc key, so sequences
+/// split across two reads come out as Esc and some letters.
+pub fn decode(bytes: &[u8]) -> Vec<Event> {
+    let mut events = vec![];
+    let mut key = |code: KeyCode, modifiers: KeyModifiers| {
+        events.push(Event::Key(KeyEvent::new(code, modifiers)));
+    };
+    let text = String::from_utf8_lossy(bytes);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.peek() {
+                Some('[' | 'O') => {
+                    chars.next();
+                    // parameters, then the letter or ~ that ends it
+                    let mut params = String::new();
+                    let end = loop {
+                        match chars.next() {
+                            Some(c) if c.is_ascii_digit() || c == ';' => params.push(c),
+                            other => break other,
+                        }
+                    };
+                    let code = match (end, params.as_str()) {
+                        (Some('A'), _) => Some(KeyCode::Up),
+                        (Some('B'), _) => Some(KeyCode::Down),
+                        (Some('C'), _) => Some(KeyCode::Right),
+                        (Some('D'), _) => Some(KeyCode::Left),
+                        (Some('P'), _) => Some(KeyCode::F(1)),
+                        (Some('Q'), _) => Some(KeyCode::F(2)),
+                        (Some('R'), _) => Some(KeyCode::F(3)),
+                        (Some('S'), _) => Some(KeyCode::F(4)),
+                        (Some('~'), n) => match n.split(';').next() {
+                            Some("15") => Some(KeyCode::F(5)),
+                            Some("17") => Some(KeyCode::F(6)),
+                            Some("18") => Some(KeyCode::F(7)),
+                            Some("19") => Some(KeyCode::F(8)),
+                            Some("20") => Some(KeyCode::F(9)),
+                            Some("21") => Some(KeyCode::F(10)),
+                            Some("23") => Some(KeyCode::F(11)),
+                            Some("24") => Some(KeyCode::F(12)),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    if let Some(code) = code {
+                        key(code, KeyModifiers::NONE);
+                    }
+                }
+                _ => key(KeyCode::Esc, KeyModifiers::NONE),
+            },
+            '\r' | '\n' => key(KeyCode::Enter, KeyModifiers::NONE),
+            '\t' => key(KeyCode::Tab, KeyModifiers::NONE),
+            '\x7f' | '\x08' => key(KeyCode::Backspace, KeyModifiers::NONE),
+            '\x01'..='\x1a' => {
+                let letter = (b'a' + c as u8 - 1) as char;
+                key(KeyCode::Char(letter), KeyModifiers::CONTROL);
+            }
+            c if !c.is_control() => key(KeyCode::Char(c), KeyModifiers::NONE),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Starts a thread that blocks on terminal events and forwards them, so
+/// the game loop never has to wait on `read()`. Run them through a
+/// `Keymap` to find out what the player meant.
+///
+/// If reading from the terminal fails the thread stops and the channel
+/// disconnects. It also stops once the receiver is dropped and another
+/// event arrives.
+pub fn spawn() -> Receiver<Event> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(event) = read() {
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
@@ -0,0 +1,145 @@
+//! What the player reads, in their language.
+//!
+//! Strings come from catalogs: TOML files of `key = "text"`, with `{name}`
+//! wherever a value goes. English is built in and fills in anything a
+//! translation leaves out, so half a translation still plays. Others are
+//! read from a `lang` directory next to the settings file, as
+//! `<code>.toml`; `lang/en.toml` in the source is the one to copy.
+
+use crate::settings::Settings;
+use crate::{RakeError, Result};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Write};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+const ENGLISH: &str = include_str!("../lang/en.toml");
+
+/// A catalog of every string, for the `format` family to fill in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lang {
+    strings: BTreeMap<String, String>,
+}
+
+impl Lang {
+    /// The built-in catalog. Parsed once and shared after that, since
+    /// every game starts out with it.
+    pub fn english() -> Arc<Lang> {
+        static PARSED: OnceLock<Arc<Lang>> = OnceLock::new();
+        PARSED
+            .get_or_init(|| {
+                let strings = toml::from_str(ENGLISH).expect("the English catalog parses");
+                Arc::new(Lang { strings })
+            })
+            .clone()
+    }
+
+    /// Where translations are looked for by code, if anywhere.
+    pub fn dir() -> Option<PathBuf> {
+        Some(Settings::default_path()?.parent()?.join("lang"))
+    }
+
+    /// The catalog for `code`, like `"de"`, from `dir`. Anything ending
+    /// in `.toml` is taken as a path instead. `"en"` is always there.
+    pub fn load(code: &str) -> Result<Arc<Lang>> {
+        if code == "en" {
+            return Ok(Lang::english());
+        }
+        let path = if code.ends_with(".toml") {
+            PathBuf::from(code)
+        } else {
+            Lang::dir()
+                .ok_or_else(|| {
+                    RakeError::Config(format!(
+                        "couldn't work out where to look for language {code:?}"
+                    ))
+                })?
+                .join(format!("{code}.toml"))
+        };
+        let text = fs::read_to_string(&path).map_err(|e| {
+            RakeError::Config(format!("couldn't read language {}: {e}", path.display()))
+        })?;
+        match Lang::parse(&text) {
+            Ok(lang) => Ok(Arc::new(lang)),
+            Err(RakeError::Config(reason)) => {
+                Err(RakeError::Config(format!("{}: {reason}", path.display())))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// A translation on top of English. Keys English doesn't have are
+    /// errors, since they're most likely typos.
+    pub fn parse(text: &str) -> Result<Lang> {
+        let translated: BTreeMap<String, String> =
+            toml::from_str(text).map_err(|e| RakeError::Config(e.message().into()))?;
+        let english = Lang::english();
+        if let Some(key) = translated
+            .keys()
+            .find(|key| !english.strings.contains_key(*key))
+        {
+            return Err(RakeError::Config(format!(
+                "there's no string called {key:?}"
+            )));
+        }
+        let mut strings = english.strings.clone();
+        strings.extend(translated);
+        Ok(Lang { strings })
+    }
+
+    /// The string for `key` as written, placeholders and all. A key that
+    /// doesn't exist comes back as itself, which is hard to miss on screen.
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key).map_or(key, String::as_str)
+    }
+
+    /// The string for `key` with each `{name}` replaced by its value in
+    /// `args`. Placeholders missing from `args` are left as they are.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut out = String::new();
+        self.write(&mut out, key, args)
+            .expect("writing to a String can't fail");
+        out
+    }
+
+    /// `format` straight into `out`, without building a string first.
+    pub fn write(
+        &self,
+        out: &mut impl Write,
+        key: &str,
+        args: &[(&str, &dyn Display)],
+    ) -> fmt::Result {
+        let mut rest = self.text(key);
+        while let Some(open) = rest.find('{') {
+            out.write_str(&rest[..open])?;
+            let Some(close) = rest[open..].find('}') else {
+                break;
+            };
+            let name = &rest[open + 1..open + close];
+            match args.iter().find(|(arg, _)| *arg == name) {
+                Some((_, value)) => write!(out, "{value}")?,
+                None => out.write_str(&rest[open..=open + close])?,
+            }
+            rest = &rest[open + close + 1..];
+        }
+        out.write_str(rest)
+    }
+
+    /// How many characters `format` would come to.
+    pub fn measure(&self, key: &str, args: &[(&str, &dyn Display)]) -> usize {
+        let mut count = Count(0);
+        self.write(&mut count, key, args)
+            .expect("counting can't fail");
+        count.0
+    }
+}
+
+struct Count(usize);
+
+impl Write for Count {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.0 += text.chars().count();
+        Ok(())
+    }
+}
@@ -0,0 +1,308 @@
+//! Levels: boards with more to them than a border.
+//!
+//! Walls inside the border, where the snake starts, portals, gates in the
+//! border, where apples come out and zones of ground the snake speeds up
+//! or slows down on, read from a TOML file with `--level` and made with
+//! `rake edit`:
+//!
+//! ```toml
+//! walls = [[10, 4], [10, 5], [10, 6]]
+//! spawn = { at = [5, 7], direction = "Right" }   # where the head starts
+//! gates = [[0, 7], [20, 0]]   # out of one and in at the one across from it
+//!
+//! [[portal]]
+//! ends = [[3, 3], [30, 10]]   # into either one, out of the other
+//!
+//! apples = [[8, 3], [30, 3], [30, 11]]   # where apples come out, in turn
+//! spawn_pattern = "ring"   # or another way of putting them out
+//!
+//! [[zone]]
+//! ground = "ice"   # twice as fast, or "mud" for half as fast
+//! at = [5, 3]      # the top left corner
+//! size = [8, 4]    # width and height
+//! ```
+
+use crate::config::{GameConfig, SpawnPattern};
+use crate::game::{self, Direction};
+use crate::grid::{Coord, Ground, Tile};
+use crate::{RakeError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// How long the snake is when it starts out.
+pub const SPAWN_LENGTH: usize = 3;
+
+/// A rectangle of one kind of ground.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Zone {
+    pub ground: Ground,
+    /// The top left corner.
+    pub at: Coord,
+    /// `[width, height]` in cells.
+    pub size: [u16; 2],
+}
+
+impl Zone {
+    /// Every cell in the zone, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = Coord> {
+        let [x, y] = self.at;
+        let [width, height] = self.size.map(|side| side as i16);
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| [x, y]))
+    }
+}
+
+/// Where the snake starts: its head, with the rest of it trailing back
+/// the way it came.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spawn {
+    pub at: Coord,
+    /// Which way it's going.
+    pub direction: Direction,
+}
+
+impl Spawn {
+    /// Every cell the snake starts on, head first.
+    pub fn cells(self) -> impl Iterator<Item = Coord> {
+        let [dx, dy] = self.direction.delta();
+        let [x, y] = self.at;
+        (0..SPAWN_LENGTH as i16).map(move |n| [x - dx * n, y - dy * n])
+    }
+}
+
+/// Two cells joined up: the head goes into one and comes out of the other,
+/// carrying on the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Portal {
+    pub ends: [Coord; 2],
+}
+
+impl Portal {
+    /// The other end, if `cell` is one of them.
+    pub fn exit(&self, cell: Coord) -> Option<Coord> {
+        match self.ends {
+            [a, b] if a == cell => Some(b),
+            [a, b] if b == cell => Some(a),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Level {
+    /// Walls inside the border, a cell each.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub walls: Vec<Coord>,
+    /// Where the snake starts, if not a third of the way across.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spawn: Option<Spawn>,
+    #[serde(rename = "portal", skip_serializing_if = "Vec::is_empty")]
+    pub portals: Vec<Portal>,
+    /// Gaps in the border, a cell each. Each opens the cell across from
+    /// it too, on the opposite side, and the snake goes out through one
+    /// and comes back in through the other, wall mode or not.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub gates: Vec<Coord>,
+    #[serde(rename = "zone", skip_serializing_if = "Vec::is_empty")]
+    pub zones: Vec<Zone>,
+    /// Cells for apples to come out on, one after the other.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub apples: Vec<Coord>,
+    /// Where apples come out, if not wherever the player's settings say.
+    /// Listing `apples` is enough for `SpawnPattern::Sequence`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spawn_pattern: Option<SpawnPattern>,
+}
+
+impl Level {
+    /// Reads a level file. Whether it fits the board is up to
+    /// `GameConfig::validate`.
+    pub fn load(path: &Path) -> Result<Level> {
+        let error = |message: String| RakeError::Level {
+            path: path.into(),
+            message,
+        };
+        let text = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        toml::from_str(&text).map_err(|e| match e.span() {
+            // counting from one, like an editor does
+            Some(span) => {
+                let line = text[..span.start].matches('\n').count() + 1;
+                error(format!("line {line}: {}", e.message()))
+            }
+            None => error(e.message().into()),
+        })
+    }
+
+    /// Writes the level out in the same format `load` reads.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = toml::to_string(self).map_err(|e| RakeError::Level {
+            path: path.into(),
+            message: e.to_string(),
+        })?;
+        crate::storage::write_atomic(path, text.as_bytes()).map_err(|source| RakeError::Write {
+            path: path.into(),
+            source,
+        })
+    }
+
+    /// The level `config` is laid out with.
+    pub fn of(config: &GameConfig) -> Level {
+        Level {
+            walls: config.walls.clone(),
+            spawn: config.spawn,
+            portals: config.portals.clone(),
+            gates: config.gates.clone(),
+            zones: config.zones.clone(),
+            apples: config.apple_cells.clone(),
+            spawn_pattern: (config.spawn_pattern != SpawnPattern::Random)
+                .then_some(config.spawn_pattern),
+        }
+    }
+
+    /// Lays `config` out like this level, in place of whatever level it
+    /// had. Whether it fits is up to `GameConfig::validate`.
+    pub fn apply(self, config: &mut GameConfig) {
+        config.walls = self.walls;
+        config.spawn = self.spawn;
+        config.portals = self.portals;
+        config.gates = self.gates;
+        config.zones = self.zones;
+        if let Some(pattern) = self.spawn_pattern {
+            config.spawn_pattern = pattern;
+        } else if !self.apples.is_empty() {
+            config.spawn_pattern = SpawnPattern::Sequence;
+        }
+        config.apple_cells = self.apples;
+    }
+}
+
+/// Every cell off the walls the snake can never get to from where it
+/// starts on `config`, going round walls, wrapping if the board does and
+/// through portals. A level with any is probably a mistake.
+pub fn unreachable(config: &GameConfig) -> Vec<Coord> {
+    let (_, grid) = game::build_board(config);
+    let (width, height) = (grid.width() as i16, grid.height() as i16);
+    let index = |cell: Coord| cell[1] as usize * width as usize + cell[0] as usize;
+    let mut seen = vec![false; width as usize * height as usize];
+    let start = config.spawn().at;
+    let mut todo = vec![start];
+    seen[index(start)] = true;
+    while let Some(cell) = todo.pop() {
+        for direction in Direction::ALL {
+            let next = game::neighbour(config, cell, direction);
+            if grid.get(next) != Tile::Wall && !seen[index(next)] {
+                seen[index(next)] = true;
+                todo.push(next);
+            }
+        }
+    }
+    (1..height - 1)
+        .flat_map(|y| (1..width - 1).map(move |x| [x, y]))
+        .filter(|cell| grid.get(*cell) == Tile::Empty && !seen[index(*cell)])
+        .collect()
+}
+
+/// Everything wrong with the level `config` is laid out with, each a line
+/// saying what and where, for `rake check-level`. Unlike
+/// `GameConfig::validate`, which stops at the first thing that'd keep the
+/// game from starting, this keeps going, and also finds things that only
+/// spoil the level: cells the snake can't get to, and a snake that starts
+/// out facing a wall.
+pub fn lint(config: &GameConfig) -> Vec<String> {
+    let mut problems = vec![];
+    let (width, height) = (config.width as i16, config.height as i16);
+    let inside = |[x, y]: Coord| x >= 1 && y >= 1 && x < width - 1 && y < height - 1;
+    let area = format!(
+        "the {}x{} board, from 1,1 to {},{}",
+        config.width,
+        config.height,
+        width - 2,
+        height - 2
+    );
+
+    // whatever's already on each cell, to tell what a clash is with
+    let mut taken: Vec<(Coord, String)> = vec![];
+    let spawn = config.spawn();
+    let facing = format!("{:?}", spawn.direction).to_lowercase();
+    let [x, y] = spawn.at;
+    let things = spawn
+        .cells()
+        .map(|cell| {
+            (
+                cell,
+                format!("the snake starting at {x},{y} heading {facing}"),
+            )
+        })
+        .chain(
+            config
+                .walls
+                .iter()
+                .map(|cell| (*cell, "a wall".to_string())),
+        )
+        .chain(config.portals.iter().flat_map(|portal| {
+            let [[ax, ay], [bx, by]] = portal.ends;
+            let name = format!("the portal from {ax},{ay} to {bx},{by}");
+            portal.ends.map(|cell| (cell, name.clone()))
+        }));
+    for (cell @ [x, y], what) in things {
+        if !inside(cell) {
+            problems.push(format!("{what} has {x},{y} outside {area}"));
+        } else if let Some((_, other)) = taken.iter().find(|(taken, _)| *taken == cell) {
+            if *other == what {
+                problems.push(format!("{what} is at {x},{y} twice"));
+            } else {
+                problems.push(format!("{what} is at {x},{y}, where {other} already is"));
+            }
+        }
+        taken.push((cell, what));
+    }
+    for zone in &config.zones {
+        let [x, y] = zone.at;
+        let [across, down] = zone.size;
+        if across == 0 || down == 0 {
+            problems.push(format!(
+                "the zone at {x},{y} is {across}x{down}, which is no cells at all"
+            ));
+        } else if !zone.cells().all(inside) {
+            problems.push(format!(
+                "the {across}x{down} zone at {x},{y} goes outside {area}"
+            ));
+        }
+    }
+    // the rest needs a board the game would actually start on
+    if problems.is_empty()
+        && let Err(e) = config.validate()
+    {
+        problems.push(match e {
+            RakeError::Config(message) => message,
+            e => e.to_string(),
+        });
+    }
+    if !problems.is_empty() {
+        return problems;
+    }
+
+    let [x, y] = game::neighbour(config, spawn.at, spawn.direction);
+    let (_, grid) = game::build_board(config);
+    if grid.get([x, y]) == Tile::Wall {
+        problems.push(format!(
+            "the snake starting at {},{} heading {facing} runs into {x},{y} on its first move",
+            spawn.at[0], spawn.at[1]
+        ));
+    }
+    match unreachable(config).as_slice() {
+        [] => {}
+        [[x, y]] => problems.push(format!(
+            "the snake can't get to {x},{y} from where it starts"
+        )),
+        cells @ [[x, y], ..] => problems.push(format!(
+            "the snake can't get to {} cells from where it starts, like {x},{y}",
+            cells.len()
+        )),
+    }
+    problems
+}
@@ -0,0 +1,71 @@
+pub mod announce;
+pub mod arena;
+pub mod bench;
+pub mod bot;
+pub mod bug;
+pub mod card;
+pub mod clock;
+pub mod config;
+pub mod console;
+pub mod coop;
+pub mod debug;
+#[cfg(feature = "discord")]
+pub mod discord;
+pub mod editor;
+pub mod effects;
+pub mod error;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod game;
+pub mod ghost;
+pub mod grade;
+pub mod grid;
+pub mod heatmap;
+pub mod help;
+pub mod hint;
+pub mod hotseat;
+pub mod idle;
+pub mod input;
+pub mod lang;
+pub mod level;
+pub mod net;
+#[cfg(feature = "online")]
+pub mod online;
+pub mod pacing;
+pub mod planes;
+#[cfg(feature = "wasm")]
+pub mod plugin;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod race;
+pub mod reload;
+pub mod render;
+pub mod replay;
+pub mod results;
+pub mod rewind;
+pub mod rl;
+pub mod rooms;
+pub mod save;
+pub mod scores;
+#[cfg(feature = "lua")]
+pub mod script;
+pub mod settings;
+pub mod skins;
+pub mod smooth;
+pub mod sound;
+pub mod spawning;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+pub mod storage;
+pub mod terminal;
+pub mod theme;
+pub mod tournament;
+pub mod tutorial;
+pub mod twitch;
+pub mod viewer;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+pub mod widget;
+
+pub use error::{RakeError, Result};
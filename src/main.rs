@@ -1,336 +1,2339 @@
-use crossterm::{
-    ExecutableCommand, QueueableCommand, cursor,
-    event::{Event, KeyCode, poll, read},
-    style::{self, Stylize},
-    terminal::{self, disable_raw_mode, enable_raw_mode},
-};
-use rand::Rng;
-use rand::rngs::ThreadRng;
-use std::io::{self, Stdout, Write};
+mod cli;
+
+use clap::Parser;
+use cli::{Cli, Command, ConfigAction};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::{Color, Stylize};
+use crossterm::{ExecutableCommand, cursor, terminal};
+use rake::announce::Announcer;
+use rake::arena::ArenaView;
+use rake::bench::{self, CountingAllocator};
+use rake::bot::{self, Controller};
+use rake::card;
+use rake::clock::{Clock, SPLIT_APPLES};
+use rake::config::{GameConfig, Scoring, WallMode};
+use rake::console::{self, Console};
+use rake::coop::{Coop, CoopEnd};
+use rake::debug;
+use rake::editor::{self, Editor};
+use rake::export;
+use rake::game::{Direction, Game, GameEvent};
+use rake::ghost::Ghost;
+use rake::grid::Ground;
+use rake::heatmap::DeathLog;
+use rake::help;
+use rake::hint::Hint;
+use rake::hotseat::Series;
+use rake::idle::{Idle, Nudge};
+use rake::input::{self, InputAction, Keymap};
+use rake::lang::Lang;
+use rake::level::{self, Level};
+use rake::net::{self, Client, ClientMessage, ServerMessage};
+use rake::pacing::Pacer;
+use rake::race::Race;
+use rake::reload::{self, Watch};
+use rake::render::{self, Frame, Renderer, TerminalRenderer};
+use rake::replay::{self, Playback};
+use rake::results::{RunRecorder, RunSummary, Session};
+use rake::rewind::History;
+use rake::save;
+use rake::scores::{Entry, HighScores, TABLE_SIZE, board_key};
+use rake::settings::{Settings, SettingsDocument};
+use rake::skins::{self, Unlocks};
+use rake::smooth::Smooth;
+use rake::sound::Sound;
+use rake::terminal::TerminalGuard;
+use rake::theme::Theme;
+use rake::tournament::{self, Entrant};
+use rake::tutorial::Tutorial;
+use rake::viewer::{self, Viewer};
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{self, ExitCode};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
 use std::time;
 
-struct Game {
-    height: u16,
-    width: u16,
-    wall: Vec<[i16; 2]>,
-    score: u16,
-    polling_rate: time::Duration,
-}
-
-impl Game {
-    fn new(
-        height: u16,
-        width: u16,
-        wall: Vec<[i16; 2]>,
-        score: u16,
-        polling_rate: time::Duration,
-    ) -> Game {
-        Game {
-            height,
-            width,
-            wall,
-            score,
-            polling_rate,
-        }
-    }
-
-    fn draw_border(&mut self, stdout: &mut Stdout) -> Result<(), std::io::Error> {
-        stdout.execute(terminal::Clear(terminal::ClearType::All))?;
-
-        for y in 0..self.height {
-            for x in 0..self.width {
-                if (y == 0 || y == self.height - 1) || (x == 0 || x == self.width - 1) {
-                    stdout
-                        .queue(cursor::MoveTo(x, y))?
-                        .queue(style::PrintStyledContent("█".magenta()))?;
-                    self.wall.push([x as i16, y as i16]);
+// only counts, so `rake bench` can report allocations per tick
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    // the one with more to say than whether it worked
+    if let Some(Command::CheckLevel { path }) = &cli.command {
+        return run_check_level(&cli.game, path);
+    }
+    // whatever happens in a game, the session's summed up after it. only
+    // solo runs are, since they're the only ones with a `RunSummary`
+    let mut session = Session::default();
+    let playing = matches!(cli.command, Some(Command::Play | Command::Tutorial) | None);
+    let result = match cli.command {
+        Some(Command::Bench { ticks, json }) => run_bench(&cli.game, ticks, json),
+        Some(Command::Tournament {
+            entrants,
+            games,
+            max_ticks,
+        }) => run_tournament(&cli.game, &entrants, games, max_ticks),
+        Some(Command::Config { action }) => run_config(&cli.game, action),
+        #[cfg(feature = "online")]
+        Some(Command::Highscores { global: true }) => run_global_highscores(&cli.game),
+        Some(Command::Highscores { .. }) => run_highscores(&cli.game),
+        Some(Command::Heatmap) => run_heatmap(&cli.game),
+        Some(Command::Replay { path, export }) => run_replay(&cli.game, &path, export.as_deref()),
+        Some(Command::Edit { path }) => run_edit(&cli.game, &path),
+        Some(Command::CheckLevel { .. }) => unreachable!("handled above"),
+        Some(Command::Skins) => run_skins(&cli.game),
+        Some(Command::Coop {
+            either,
+            friendly_fire,
+        }) => run_coop(&cli.game, either, friendly_fire),
+        Some(Command::Race { target, against }) => run_race(&cli.game, target, against.as_deref()),
+        Some(Command::Hotseat { rounds, names }) => run_hotseat(&cli.game, rounds, names),
+        Some(Command::Host {
+            port,
+            players,
+            name,
+        }) => run_host(&cli.game, port, players, name),
+        Some(Command::Serve { port }) => run_serve(&cli.game, port),
+        Some(Command::Join { addr, name }) => run_join(&cli.game, &addr, name),
+        Some(Command::Watch { addr }) => run_watch(&cli.game, &addr),
+        #[cfg(feature = "websocket")]
+        Some(Command::Bridge { addr, port }) => run_bridge(&addr, port),
+        #[cfg(feature = "ssh")]
+        Some(Command::SshServe { port, bind }) => run_ssh_serve(&cli.game, &bind, port),
+        Some(Command::Tutorial) => run(&cli.game, true, &mut session),
+        Some(Command::Play) | None => run(&cli.game, false, &mut session),
+    };
+    // the terminal guard lives inside `run`, so by the time we get here
+    // it's already been restored and the message prints normally
+    if playing {
+        print_session(&cli.game, &session);
+    }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("rake: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// the tutorial's snake never goes faster than this
+const TUTORIAL_TICK_RATE: time::Duration = time::Duration::from_millis(300);
+
+// how far back one press of the rewind key goes in practice, and how far
+// back it can go in all
+const REWIND_TICKS: usize = 10;
+const PRACTICE_HISTORY: usize = 300;
+
+/// The game `settings` make, on `--level`'s board if there is one.
+fn game_config(args: &cli::GameArgs, settings: &Settings) -> rake::Result<GameConfig> {
+    let mut config = settings.game_config()?;
+    if let Some(path) = &args.level {
+        if config.rooms {
+            return Err(rake::RakeError::Config(
+                "rooms lay out their own walls, so they can't go with a level".into(),
+            ));
+        }
+        Level::load(path)?.apply(&mut config);
+        config.validate()?;
+    }
+    Ok(config)
+}
+
+/// A game at home, after the tutorial if `tutorial` is set.
+fn run(args: &cli::GameArgs, tutorial: bool, session: &mut Session) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let mut config = game_config(args, &settings)?;
+    let mut keymap = settings.keymap()?;
+    let lang = settings.lang()?;
+    let grades = settings.grades()?;
+    let screen_reader = settings.screen_reader == Some(true);
+    let turn_based = settings.turn_based == Some(true);
+    let speedrun = settings.speedrun == Some(true);
+    // room under the HUD for the --step-debug readout, then the splits
+    let debug_lines = if args.step_debug {
+        debug::OVERLAY_LINES
+    } else {
+        0
+    };
+    let overlay = debug_lines + u16::from(speedrun);
+    let mut ghost = match &args.ghost {
+        Some(path) => Some(Ghost::new(&replay::load(path)?, &mut config)?),
+        None => None,
+    };
+    #[cfg(feature = "lua")]
+    let script = args
+        .script
+        .as_deref()
+        .map(rake::script::Script::load)
+        .transpose()?;
+    #[cfg(feature = "wasm")]
+    let mut plugin = args
+        .plugin
+        .as_deref()
+        .map(rake::plugin::WasmController::load)
+        .transpose()?;
+
+    let mut autopilot = args
+        .bot
+        .as_deref()
+        .map(|name| bot::named(name).ok_or_else(|| no_such_bot(name)))
+        .transpose()?;
+
+    let mut chat = args
+        .twitch
+        .as_deref()
+        .map(rake::twitch::TwitchChat::connect)
+        .transpose()?;
+
+    // bots and chat don't get to set high scores
+    #[cfg(feature = "wasm")]
+    let scores_path = HighScores::default_path().filter(|_| plugin.is_none());
+    #[cfg(not(feature = "wasm"))]
+    let scores_path = HighScores::default_path();
+    let scores_path = scores_path.filter(|_| chat.is_none() && autopilot.is_none());
+    // and nor does practice, where crashes can be taken back, stepping
+    // through a tick at a time or anything the dev console's been at
+    // (nor a game where hints get turned on, which sees to itself below)
+    let mut scores_path = scores_path.filter(|_| !args.practice && !args.step_debug && !args.dev);
+    let mut scores = match &scores_path {
+        Some(path) => HighScores::load(path)?,
+        None => HighScores::default(),
+    };
+
+    // before the terminal's taken over, in case opening audio is noisy
+    let mut sound = Sound::new(&settings.sound_config()?)?;
+    let guard = TerminalGuard::enter(false)?;
+    let mut renderer = terminal_renderer(&settings);
+    let events = input::spawn();
+
+    if tutorial && !run_tutorial(&mut renderer, &events, &config, &keymap, &lang)? {
+        return Ok(());
+    }
+
+    // a save that can't be read shouldn't stop anyone playing; it gets
+    // mentioned on the way out and replaced by the next save
+    let save_path =
+        save::default_path().filter(|_| !args.practice && !args.step_debug && !args.dev);
+    let mut save_problem = None;
+    let saved = match save_path.as_deref().map(save::load).transpose() {
+        Ok(saved) => saved.flatten(),
+        Err(e) => {
+            save_problem = Some(e);
+            None
+        }
+    };
+    let mut game = None;
+    if let Some(snapshot) = saved
+        && let Some(path) = &save_path
+    {
+        if ask_continue(&mut renderer, &events, &lang)? {
+            match Game::restore(snapshot) {
+                Ok(restored) => game = Some(restored),
+                Err(e) => save_problem = Some(e),
+            }
+        } else {
+            save::discard(path)?;
+        }
+    }
+    // only runs played start to finish here can be replayed from their seed
+    #[cfg(feature = "online")]
+    let resumed = game.is_some();
+    let mut game = game.unwrap_or_else(|| Game::new(config));
+    if let Some(ghost) = &mut ghost {
+        ghost.advance_to(game.ticks());
+    }
+    if scores_path.is_some() {
+        game.set_high_score(scores.best(game.config()));
+    }
+    game.set_lang(lang.clone());
+    game.set_help(keymap.help(&lang));
+    if let Some(channel) = &args.twitch {
+        let channel = channel.trim_start_matches('#');
+        game.set_help(vec![lang.format("twitch-help", &[("channel", &channel)])]);
+    }
+    if let Some(name) = &args.bot {
+        game.set_help(vec![lang.format("bot-help", &[("bot", name)])]);
+    }
+    game.set_speedrun(speedrun);
+    game.set_show_speed(settings.show_speed == Some(true));
+    // the clock only starts with the first tick, but it's on the HUD (at
+    // wherever a saved game got to) from the start
+    game.set_elapsed(game.elapsed());
+    // no board at all for a screen reader, so no need for room to draw one
+    let mut announcer = screen_reader.then(|| Announcer::new(lang.clone(), game.tick_rate()));
+    if let Some(announcer) = &mut announcer {
+        if let Some(help) = keymap.help(&lang).first() {
+            say(help)?;
+        }
+        say(&announcer.start(&game))?;
+    } else {
+        if !wait_for_room(
+            &mut renderer,
+            &events,
+            &keymap,
+            &lang,
+            game.width(),
+            game.height() + overlay,
+        )? {
+            return Ok(());
+        }
+        renderer.clear()?;
+        game.draw_border(&mut renderer)?;
+        game.draw(&mut renderer)?;
+        if args.step_debug {
+            draw_overlay(&mut renderer, &game)?;
+        }
+        if speedrun {
+            draw_splits(&mut renderer, &game, &lang, &[], debug_lines)?;
+        }
+        renderer.present()?;
+    }
+
+    let mut history = args.practice.then(|| History::new(PRACTICE_HISTORY));
+    if let Some(history) = &mut history {
+        history.record(&game);
+    }
+    let mut pacer = if turn_based || args.step_debug {
+        Pacer::on_demand()
+    } else {
+        Pacer::new(game.tick_rate())
+    };
+    let mut hint = Hint::new();
+    // and whether this tick's in-between frame has been drawn yet
+    let mut smooth = (settings.smooth == Some(true)
+        && settings.ascii != Some(true)
+        && !screen_reader
+        && !pacer.is_on_demand())
+    .then(Smooth::new);
+    let mut halfway = false;
+    // only for people playing, who might have wandered off
+    #[cfg(feature = "wasm")]
+    let driven = plugin.is_some();
+    #[cfg(not(feature = "wasm"))]
+    let driven = false;
+    let mut idle = settings
+        .idle_pause
+        .filter(|seconds| *seconds > 0)
+        .filter(|_| !driven && chat.is_none() && autopilot.is_none() && !pacer.is_on_demand())
+        .map(|seconds| Idle::new(time::Duration::from_secs(seconds), time::Instant::now()));
+    // the settings file, for picking up changes to it while paused
+    let mut watch = args.config_path().ok().map(Watch::new);
+    // the pause key, for the top of the loop to stop at
+    let mut pausing = false;
+    // a replay doesn't know when slow motion was on, so it can't get to
+    // the same score
+    #[cfg(feature = "online")]
+    let mut slowed = false;
+    let mut recorder = RunRecorder::resume(time::Instant::now(), game.elapsed());
+    // opt in only: nothing goes to Discord unless a client id is
+    // configured, and not being able to reach it doesn't stop the game
+    #[cfg(feature = "discord")]
+    let mut discord_problem = None;
+    #[cfg(feature = "discord")]
+    let mut presence = settings.discord.client_id.as_deref().and_then(|id| {
+        rake::discord::Presence::connect(id)
+            .and_then(|mut presence| presence.update(&game, &lang).map(|()| presence))
+            .map_err(|e| discord_problem = Some(e))
+            .ok()
+    });
+    // a resize can scramble or wipe the screen, so the border goes back up
+    // with the next frame
+    let mut redraw_border = false;
+
+    // MAIN GAME LOOP
+    'game: loop {
+        let nudge = idle
+            .as_mut()
+            .and_then(|idle| idle.check(time::Instant::now()));
+        let nudge = match std::mem::take(&mut pausing) {
+            true => Some(Nudge::Pause),
+            false => nudge,
+        };
+        if let Some(nudge) = nudge {
+            match nudge {
+                Nudge::Warn(seconds) => {
+                    let line = lang.format("idle-warning", &[("seconds", &seconds)]);
+                    if screen_reader {
+                        say(&line)?;
+                    } else {
+                        game.set_help(vec![line]);
+                    }
+                }
+                Nudge::Clear => game.set_help(keymap.help(&lang)),
+                Nudge::Pause => {
+                    let paused = time::Instant::now();
+                    let line = lang.text("idle-paused");
+                    if screen_reader {
+                        say(line)?;
+                    } else {
+                        // the short one for a board too narrow for the rest
+                        let short = lang.text("idle-paused-short");
+                        game.set_help(vec![line.to_string(), short.to_string()]);
+                        let mut frame = Frame::new(&mut renderer);
+                        game.draw(&mut frame)?;
+                        frame.finish()?;
+                    }
+                    // whatever key it is counts, and a turn turns
+                    loop {
+                        let event = match events.recv_timeout(reload::POLL) {
+                            Ok(event) => event,
+                            Err(RecvTimeoutError::Timeout) => {
+                                if let Some(watch) = &mut watch
+                                    && watch.changed()
+                                {
+                                    let reloaded = reload_settings(args, &mut game, &mut keymap);
+                                    #[cfg(feature = "online")]
+                                    if reloaded.as_ref().is_ok_and(|sped| *sped) {
+                                        slowed = true;
+                                    }
+                                    show_reload(
+                                        &mut renderer,
+                                        &game,
+                                        &lang,
+                                        reloaded,
+                                        screen_reader,
+                                    )?;
+                                }
+                                continue;
+                            }
+                            Err(RecvTimeoutError::Disconnected) => break 'game,
+                        };
+                        match keymap.action(&event) {
+                            Some(InputAction::Quit) => break 'game,
+                            Some(InputAction::Turn(direction)) => {
+                                game.turn(direction);
+                                break;
+                            }
+                            _ if matches!(event, Event::Key(key) if key.kind == KeyEventKind::Press) =>
+                            {
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                    recorder.pause(paused.elapsed());
+                    if let Some(idle) = &mut idle {
+                        idle.input(time::Instant::now());
+                    }
+                    game.set_help(keymap.help(&lang));
+                    if !pacer.is_on_demand() {
+                        pacer = Pacer::new(game.tick_rate());
+                    }
+                    // for a new theme, or to wipe a reload's complaint
+                    redraw_border = !screen_reader;
                 }
             }
         }
-        stdout.flush()?;
-
-        Ok(())
-    }
-
-    fn handle_input(&self, snake: &mut Snake) -> Result<(), std::io::Error> {
-        if poll(self.polling_rate)? {
-            let event = read()?;
-            if event == Event::Key(KeyCode::Char('w').into()) && snake.direction[1] != 1 {
-                snake.direction[0] = 0;
-                snake.direction[1] = -1;
-                Ok(())
-            } else if event == Event::Key(KeyCode::Char('d').into()) && snake.direction[0] != -1 {
-                snake.direction[0] = 1;
-                snake.direction[1] = 0;
-                Ok(())
-            } else if event == Event::Key(KeyCode::Char('s').into()) && snake.direction[1] != -1 {
-                snake.direction[0] = 0;
-                snake.direction[1] = 1;
-                Ok(())
-            } else if event == Event::Key(KeyCode::Char('a').into()) && snake.direction[0] != 1 {
-                snake.direction[0] = -1;
-                snake.direction[1] = 0;
-                Ok(())
-            } else if event == Event::Key(KeyCode::Esc.into()) {
-                snake.direction[0] = 69;
-                snake.direction[1] = 69;
-                Ok(())
-            } else {
-                Ok(())
+        // keep reading input until the next tick is due
+        while let Some(wait) = pacer.remaining() {
+            // waking up halfway there for smooth movement's frame
+            let wait = match smooth {
+                Some(_) if !halfway => wait.saturating_sub(game.tick_rate() / 2),
+                _ => wait,
+            };
+            let received = events.recv_timeout(wait);
+            if let (Some(idle), Ok(Event::Key(key))) = (&mut idle, &received)
+                && key.kind == KeyEventKind::Press
+            {
+                idle.input(time::Instant::now());
+            }
+            match received {
+                Ok(Event::Resize(..)) if !screen_reader => {
+                    if !wait_for_room(
+                        &mut renderer,
+                        &events,
+                        &keymap,
+                        &lang,
+                        game.width(),
+                        game.height() + overlay,
+                    )? {
+                        break 'game;
+                    }
+                    redraw_border = true;
+                }
+                Ok(event) if input::is_suspend(&event) => {
+                    let paused = time::Instant::now();
+                    rake::terminal::suspend()?;
+                    recorder.pause(paused.elapsed());
+                    redraw_border = !screen_reader;
+                }
+                Ok(event) if args.dev && !screen_reader && console::is_toggle(&event) => {
+                    let paused = time::Instant::now();
+                    run_console(&mut renderer, &events, &mut game)?;
+                    recorder.pause(paused.elapsed());
+                    // the game stood still meanwhile, so the next tick is a
+                    // whole one away again, at whatever speed it's at now
+                    if !pacer.is_on_demand() {
+                        pacer = Pacer::new(game.tick_rate());
+                    }
+                    redraw_border = true;
+                }
+                Ok(event) => match keymap.action(&event) {
+                    Some(InputAction::Turn(direction)) => {
+                        // backing up is no more a move than it is a turn,
+                        // unless it's the crash being debugged
+                        if turn_based && direction != game.snake().direction().opposite()
+                            || args.step_debug
+                        {
+                            pacer.step();
+                        }
+                        game.turn(direction);
+                    }
+                    Some(InputAction::Save) => {
+                        if let Some(path) = &save_path {
+                            save::store(path, &game.snapshot())?;
+                        }
+                    }
+                    Some(InputAction::Quit) => break 'game,
+                    Some(InputAction::Mute) => sound.toggle_mute(),
+                    Some(InputAction::Rewind) => {
+                        if let Some(snapshot) = history
+                            .as_mut()
+                            .and_then(|history| history.rewind(REWIND_TICKS))
+                        {
+                            game.rewind(snapshot)?;
+                            redraw_border = !screen_reader;
+                        }
+                    }
+                    // once the way's been shown, the score's not all the
+                    // player's own any more
+                    Some(InputAction::Hint) => {
+                        hint.toggle();
+                        scores_path = None;
+                    }
+                    Some(InputAction::Slow) => {
+                        game.set_slow(!game.is_slow());
+                        #[cfg(feature = "online")]
+                        {
+                            slowed = true;
+                        }
+                        // from now, not from when the last tick went
+                        if !pacer.is_on_demand() {
+                            pacer = Pacer::new(game.tick_rate());
+                        }
+                    }
+                    Some(InputAction::Help) => {
+                        let paused = time::Instant::now();
+                        let modifiers: Vec<&str> = [
+                            (args.practice, "help-practice"),
+                            (turn_based, "help-turn-based"),
+                            (speedrun, "help-speedrun"),
+                            (game.is_slow(), "help-slowed"),
+                        ]
+                        .into_iter()
+                        .filter_map(|(on, key)| on.then_some(key))
+                        .collect();
+                        let lines = help::lines(&keymap, game.config(), &modifiers, &lang);
+                        if screen_reader {
+                            for line in lines.iter().filter(|line| !line.is_empty()) {
+                                say(line.trim())?;
+                            }
+                        } else {
+                            let mut frame = Frame::new(&mut renderer);
+                            help::draw(&mut frame, &game, &lines)?;
+                            frame.finish()?;
+                        }
+                        wait_for_key(&events);
+                        recorder.pause(paused.elapsed());
+                        if let Some(idle) = &mut idle {
+                            idle.input(time::Instant::now());
+                        }
+                        if !pacer.is_on_demand() {
+                            pacer = Pacer::new(game.tick_rate());
+                        }
+                        redraw_border = !screen_reader;
+                    }
+                    // stopping where it is, not after another tick
+                    Some(InputAction::Pause) => {
+                        pausing = true;
+                        continue 'game;
+                    }
+                    // any other key steps straight on
+                    None => {
+                        if args.step_debug
+                            && let Event::Key(key) = event
+                            && key.kind == KeyEventKind::Press
+                        {
+                            pacer.step();
+                        }
+                    }
+                },
+                Err(RecvTimeoutError::Disconnected) => break 'game,
+                Err(RecvTimeoutError::Timeout) => match &mut smooth {
+                    Some(smooth) if !halfway && pacer.remaining().is_some() => {
+                        halfway = true;
+                        let mut frame = Frame::new(&mut renderer);
+                        smooth.draw_between(&mut frame, &game)?;
+                        frame.finish()?;
+                    }
+                    _ => break,
+                },
             }
+        }
+        pacer.advance(game.tick_rate());
+        halfway = false;
+
+        #[cfg(feature = "wasm")]
+        if let Some(plugin) = &mut plugin
+            && let Some(direction) = plugin.decide(&game)
+        {
+            game.turn(direction);
+        }
+        if let Some(chat) = &mut chat
+            && let Some(direction) = chat.decide(&game)
+        {
+            game.turn(direction);
+        }
+        if let Some(autopilot) = &mut autopilot
+            && let Some(direction) = autopilot.decide(&game)
+        {
+            game.turn(direction);
+        }
+
+        let tick = game.tick(None);
+        sound.react(game.events());
+        if let Some(ghost) = &mut ghost {
+            ghost.tick();
+        }
+
+        #[cfg(feature = "lua")]
+        if let Some(script) = &script {
+            script.after_tick(&mut game)?;
+        }
+
+        let now = time::Instant::now();
+        recorder.observe(&game, now);
+        game.set_elapsed(recorder.elapsed(now));
+        if let Some(history) = &mut history {
+            history.record(&game);
+        }
+        #[cfg(feature = "discord")]
+        if let Some(discord) = &mut presence
+            && let Err(e) = discord.update(&game, &lang)
+        {
+            presence = None;
+            discord_problem = Some(e);
+        }
+
+        if let Some(announcer) = &mut announcer {
+            sound.ring(&mut renderer)?;
+            renderer.present()?;
+            if let Some(line) = announcer.after_tick(&game) {
+                say(&line)?;
+            }
+            if tick.died || tick.won {
+                break;
+            }
+            continue;
+        }
+        // in practice a crash is just something else to take back
+        if tick.died
+            && let Some(history) = &mut history
+        {
+            let key = keymap
+                .keys_for(InputAction::Rewind)
+                .next()
+                .map_or_else(|| "?".to_string(), input::key_name);
+            game.set_help(vec![lang.format("practice-crashed", &[("key", &key)])]);
+            let mut frame = Frame::new(&mut renderer);
+            game.draw(&mut frame)?;
+            sound.ring(&mut frame)?;
+            frame.finish()?;
+            let paused = time::Instant::now();
+            let earlier = loop {
+                let Ok(event) = events.recv() else {
+                    break None;
+                };
+                match keymap.action(&event) {
+                    Some(InputAction::Rewind) => break history.rewind(REWIND_TICKS),
+                    Some(InputAction::Quit) => break None,
+                    _ => {}
+                }
+            };
+            let Some(snapshot) = earlier else {
+                break;
+            };
+            recorder.pause(paused.elapsed());
+            game.rewind(snapshot)?;
+            game.set_help(keymap.help(&lang));
+            redraw_border = true;
+            continue;
+        }
+        if tick.died {
+            break;
+        }
+        // a new stage of rooms, walls and all
+        if game
+            .events()
+            .iter()
+            .any(|event| matches!(event, GameEvent::Cleared { .. }))
+        {
+            redraw_border = true;
+        }
+
+        let mut frame = Frame::new(&mut renderer);
+        if redraw_border {
+            frame.clear()?;
+            game.draw_border(&mut frame)?;
+            redraw_border = false;
+        }
+        if let Some(smooth) = &mut smooth {
+            smooth.clear(&mut frame, &game)?;
+        }
+        game.draw(&mut frame)?;
+        if let Some(ghost) = &mut ghost {
+            ghost.draw(&mut frame, game.grid())?;
+        }
+        hint.draw(&mut frame, &game)?;
+        if args.step_debug {
+            draw_overlay(&mut frame, &game)?;
+        }
+        if speedrun {
+            let splits: Vec<u64> = recorder.splits().collect();
+            draw_splits(&mut frame, &game, &lang, &splits, debug_lines)?;
+        }
+        sound.ring(&mut frame)?;
+        frame.finish()?;
+        // unlike dying, the full board is worth a last look
+        if tick.won {
+            break;
+        }
+    }
+    // dying skips the last frame, but not its bell
+    sound.ring(&mut renderer)?;
+    renderer.present()?;
+
+    let summary = recorder.finish(&game, time::Instant::now(), &grades);
+    session.record(&summary);
+    if game.has_won() && !screen_reader {
+        show_win(&mut renderer, &events, &game, &lang, &summary, speedrun)?;
+    }
+
+    // quitting halfway keeps the game for next time, dying or winning ends
+    // it for good
+    if let Some(path) = &save_path {
+        if game.is_over() {
+            save::discard(path)?;
         } else {
-            Ok(())
+            save::store(path, &game.snapshot())?;
         }
     }
 
-    fn increase_score(&mut self) {
-        self.score += 1;
+    // a run that made the top ten gets a name, unless it was quit halfway
+    let mut place = None;
+    if scores_path.is_some()
+        && game.is_over()
+        && scores.qualifies(game.config(), game.score())
+        && let Some(initials) = enter_initials(
+            &mut renderer,
+            &events,
+            &game,
+            &lang,
+            screen_reader,
+            scores.last_initials(),
+        )?
+    {
+        place = scores.add_entry(
+            game.config(),
+            &initials,
+            game.score(),
+            summary.duration_ms,
+            grades.grade(game.score(), game.config()),
+        );
     }
 
-    fn display_score(&self, stdout: &mut Stdout) -> Result<(), std::io::Error> {
-        stdout.queue(cursor::MoveTo(0, self.height))?;
-        println!("Score: {}", self.score);
-        let msg = "WASD to move, ESC to exit";
-        stdout.queue(cursor::MoveTo(self.width - msg.len() as u16, self.height))?;
-        println!("{}", msg);
-        Ok(())
+    // and clean up, leaving the final board on screen
+    if !screen_reader {
+        io::stdout().execute(cursor::MoveTo(0, game.height() + 1 + overlay))?;
     }
-}
+    drop(guard);
 
-impl Default for Game {
-    fn default() -> Game {
-        let height = 15;
-        let width = 40;
-        let wall: Vec<[i16; 2]> = vec![];
-        let score = 0;
-        let polling_rate = time::Duration::from_millis(100);
-        Game::new(height, width, wall, score, polling_rate)
+    if let Some(path) = &args.json_results {
+        write_results(path, &summary)?;
+    }
+    if let Some(path) = &args.snapshot
+        && game.is_over()
+    {
+        export::write(path, &game)?;
     }
+    if game.is_over() {
+        if let Some(cause) = game.death() {
+            println!("{}", lang.text(&format!("death-{}", cause.name())));
+        }
+        let time = Clock::from_ms(summary.duration_ms).tenths(speedrun);
+        println!("{}", lang.format("run-time", &[("time", &time)]));
+        let rule = lang.text(scoring_key(game.config().scoring));
+        println!("{}", lang.format("run-scoring", &[("rule", &rule)]));
+        if let Some(grade) = summary.grade {
+            println!("{}", lang.format("run-grade", &[("grade", &grade)]));
+        }
+        if speedrun && summary.splits().next().is_some() {
+            let splits: Vec<String> = summary
+                .splits()
+                .enumerate()
+                .map(|(i, ms)| {
+                    let apples = (i + 1) * SPLIT_APPLES;
+                    let time = Clock::from_ms(ms).tenths(true);
+                    lang.format("split", &[("apples", &apples), ("time", &time)])
+                })
+                .collect();
+            println!(
+                "{}",
+                lang.format("run-splits", &[("splits", &splits.join(", "))])
+            );
+        }
+        let ascii = settings.ascii == Some(true) || screen_reader;
+        let card = card::card(&game, &summary, &lang, ascii);
+        println!("\n{card}\n");
+        if settings.copy_card == Some(true) {
+            print!("{}", card::osc52(&card));
+            println!("{}", lang.text("card-copied"));
+        }
+    }
+    if let Some(e) = save_problem {
+        eprintln!("rake: couldn't continue the saved game: {e}");
+    }
+    #[cfg(feature = "discord")]
+    if let Some(e) = discord_problem {
+        eprintln!("rake: couldn't show the game on Discord: {e}");
+    }
+    if scores_path.is_some()
+        && game.is_over()
+        && !game.has_won()
+        && let Some(path) = DeathLog::default_path()
+    {
+        let mut deaths = DeathLog::load(&path)?;
+        deaths.record(game.config(), game.snake().head(), game.death());
+        deaths.save(&path)?;
+    }
+    if scores_path.is_some()
+        && game.is_over()
+        && let Some(path) = Unlocks::default_path()
+    {
+        let mut unlocks = Unlocks::load(&path)?;
+        let earned = unlocks.record(&game);
+        unlocks.save(&path)?;
+        for skin in earned {
+            println!("{}", lang.format("skin-unlocked", &[("skin", &skin.name)]));
+        }
+    }
+    if let Some(path) = &scores_path {
+        let best = scores.record(game.config(), game.score());
+        if best || place.is_some() {
+            scores.save(path)?;
+        }
+        if best {
+            println!(
+                "{}",
+                lang.format("new-high-score", &[("score", &game.score())])
+            );
+        }
+        if place.is_some() {
+            println!();
+            print_table(
+                &board_key(game.config()),
+                scores.table(game.config()),
+                place,
+            );
+        }
+    }
+
+    // opt in only: nothing is sent unless a leaderboard url is configured
+    #[cfg(feature = "online")]
+    if let Some(url) = &settings.leaderboard.url
+        && scores_path.is_some()
+        && !resumed
+        && !slowed
+        && game.is_over()
+        && game.score() > 0
+    {
+        let leaderboard = rake::online::Leaderboard::new(url, settings.leaderboard.key.as_deref());
+        let name = match scores.last_initials() {
+            "" => "???",
+            name => name,
+        };
+        match leaderboard.submit(&board_key(game.config()), name, &summary) {
+            Ok(()) => println!("Sent to the leaderboard at {url}"),
+            Err(e) => eprintln!("rake: {e}"),
+        }
+    }
+
+    Ok(())
 }
 
-struct Snake {
-    body: Vec<[i16; 2]>,
-    head: [i16; 2],
-    tail: [i16; 2],
-    wake: [i16; 2],
-    length: usize,
-    direction: [i16; 2],
+// what a changed settings file can change mid-game: the theme, the keys
+// and the speed. Anything wrong with it and nothing changes at all. Says
+// whether the speed did.
+fn reload_settings(
+    args: &cli::GameArgs,
+    game: &mut Game,
+    keymap: &mut Keymap,
+) -> rake::Result<bool> {
+    let settings = args.settings()?;
+    let config = settings.game_config()?;
+    *keymap = settings.keymap()?;
+    game.set_theme(config.theme);
+    let sped = config.tick_rate != game.config().tick_rate;
+    game.set_tick_rate(config.tick_rate);
+    Ok(sped)
 }
 
-impl Snake {
-    fn new(
-        head: [i16; 2],
-        body: Vec<[i16; 2]>,
-        tail: [i16; 2],
-        wake: [i16; 2],
-        length: usize,
-        direction: [i16; 2],
-    ) -> Snake {
-        Snake {
-            head,
-            body,
-            tail,
-            wake,
-            length,
-            direction,
+// the whole board again, in case the theme changed, with what came of the
+// reload over it: just a word on the HUD if it worked, and what was wrong
+// across the board if it didn't
+fn show_reload(
+    renderer: &mut impl Renderer,
+    game: &Game,
+    lang: &Lang,
+    reloaded: rake::Result<bool>,
+    screen_reader: bool,
+) -> rake::Result<()> {
+    let problem = reloaded.err().map(|e| e.to_string());
+    if screen_reader {
+        return match problem {
+            Some(problem) => say(&format!("{} {problem}", lang.text("reload-failed"))),
+            None => say(lang.text("reload-done")),
         }
+        .map_err(Into::into);
     }
+    let mut frame = Frame::new(renderer);
+    frame.clear()?;
+    game.draw_border(&mut frame)?;
+    game.draw(&mut frame)?;
+    let color = game.config().theme.text;
+    let width = game.width() as usize - 2;
+    match problem {
+        Some(problem) => {
+            let mut lines = vec![lang.text("reload-failed").to_string()];
+            lines.extend(render::wrap(&problem, width));
+            for (y, line) in (1..game.height() - 1).zip(&lines) {
+                frame.draw_text(1, y, &format!("{line:<width$.width$}"), color)?;
+            }
+        }
+        None => {
+            let line = lang.text("reload-done");
+            frame.draw_text(1, 1, &format!("{line:<width$.width$}"), color)?;
+        }
+    }
+    frame.finish()?;
+    Ok(())
+}
+
+// one line, on stdout like the rest, even if the settings that went wrong
+// are the reason the program's stopping
+fn print_session(args: &cli::GameArgs, session: &Session) {
+    let lang = args
+        .settings()
+        .and_then(|settings| settings.lang())
+        .unwrap_or_else(|_| Lang::english());
+    let time = Clock::from_ms(session.duration_ms);
+    println!(
+        "{}",
+        lang.format(
+            "session",
+            &[
+                ("games", &session.games),
+                ("best", &session.best),
+                ("apples", &session.apples),
+                ("time", &time),
+            ],
+        )
+    );
+}
 
-    fn spawn(game: &Game) -> Snake {
-        let head: [i16; 2] = [game.width as i16 / 3, game.height as i16 / 2];
-        let tail: [i16; 2] = [head[0] - 2, head[1]];
-        let wake: [i16; 2] = [head[0] - 3, head[1]];
-        let body: Vec<[i16; 2]> = vec![head, [head[0] - 1, head[1]], tail];
-        let length: usize = 3;
-        let direction: [i16; 2] = [1, 0];
-        Snake::new(head, body, tail, wake, length, direction)
+// only what's changed gets sent to an old Windows console, where every
+// cell is a call into the system
+fn terminal_renderer(settings: &Settings) -> TerminalRenderer {
+    let renderer = TerminalRenderer::default();
+    if settings.legacy_console() {
+        renderer.only_changes()
+    } else {
+        renderer
     }
+}
 
-    fn ate(&self, apple: &mut Apple) -> bool {
-        self.head[0] == apple.position[0] && self.head[1] == apple.position[1]
+// what a scoring rule's called on the results screen
+fn scoring_key(scoring: Scoring) -> &'static str {
+    match scoring {
+        Scoring::Flat => "scoring-flat",
+        Scoring::Speed => "scoring-speed",
+        Scoring::Length => "scoring-length",
+        Scoring::Time => "scoring-time",
     }
+}
 
-    fn grow(&mut self) {
-        self.body
-            .insert(self.length - 1, [self.tail[0], self.tail[1]]);
-        self.length += 1;
+// `-` means stdout, for piping into something else
+fn write_results(path: &Path, summary: &RunSummary) -> rake::Result<()> {
+    let json = serde_json::to_string_pretty(summary).expect("summaries serialize");
+    if path == Path::new("-") {
+        println!("{json}");
+        return Ok(());
     }
+    rake::storage::write_atomic(path, (json + "\n").as_bytes()).map_err(|source| {
+        rake::RakeError::Write {
+            path: path.into(),
+            source,
+        }
+    })
+}
 
-    fn draw(&mut self, stdout: &mut Stdout) -> Result<(), std::io::Error> {
-        stdout
-            .queue(cursor::MoveTo(
-                self.head[0].try_into().unwrap(),
-                self.head[1].try_into().unwrap(),
-            ))?
-            .queue(style::PrintStyledContent("$".green()))?;
+/// Holds things up while the terminal's too small for a `width` by
+/// `height` board and the HUD under it, asking for it to be made bigger.
+/// `false` if the player quit instead of resizing.
+fn wait_for_room(
+    renderer: &mut impl Renderer,
+    events: &Receiver<Event>,
+    keymap: &Keymap,
+    lang: &Lang,
+    width: u16,
+    height: u16,
+) -> rake::Result<bool> {
+    let needed = (width, height + 1);
+    loop {
+        let (columns, rows) = terminal::size()?;
+        if columns >= needed.0 && rows >= needed.1 {
+            return Ok(true);
+        }
+        let message = lang.format(
+            "enlarge",
+            &[
+                ("width", &needed.0),
+                ("height", &needed.1),
+                ("columns", &columns),
+                ("rows", &rows),
+            ],
+        );
+        // broken into lines that fit, or the terminal would wrap it into
+        // a mess
+        renderer.clear()?;
+        let mut line = String::new();
+        let mut y = 0;
+        for word in message.split(' ') {
+            if !line.is_empty()
+                && line.chars().count() + 1 + word.chars().count() > columns as usize
+            {
+                renderer.draw_text(0, y, &line, Color::Reset)?;
+                line.clear();
+                y += 1;
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        renderer.draw_text(0, y, &line, Color::Reset)?;
+        renderer.present()?;
 
-        for i in 1..self.length - 1 {
-            let color;
-            if i % 2 == 0 {
-                color = "$".green();
-            } else {
-                color = "$".cyan();
-            }
-            stdout
-                .queue(cursor::MoveTo(
-                    self.body[i][0].try_into().unwrap(),
-                    self.body[i][1].try_into().unwrap(),
-                ))?
-                .queue(style::PrintStyledContent(color))?;
-        }
-        let color;
-        if self.length % 2 == 0 {
-            color = "$".cyan();
-        } else {
-            color = "$".green();
-        }
-        stdout
-            .queue(cursor::MoveTo(
-                self.tail[0].try_into().unwrap(),
-                self.tail[1].try_into().unwrap(),
-            ))?
-            .queue(style::PrintStyledContent(color))?;
-        stdout
-            .queue(cursor::MoveTo(
-                self.wake[0].try_into().unwrap(),
-                self.wake[1].try_into().unwrap(),
-            ))?
-            .queue(style::Print(" "))?;
-
-        Ok(())
-    }
-
-    fn slither(&mut self) -> Result<(), std::io::Error> {
-        self.wake = [self.tail[0], self.tail[1]];
-        self.tail = [self.body[self.length - 2][0], self.body[self.length - 2][1]];
-        let mut i = self.length - 2;
+        // anything but a resize or quitting is ignored
         loop {
-            self.body[i] = self.body[i - 1];
-            i -= 1;
-            if i < 1 {
-                break;
+            match events.recv() {
+                Ok(Event::Resize(..)) => break,
+                Ok(event) if keymap.action(&event) == Some(InputAction::Quit) => {
+                    return Ok(false);
+                }
+                Ok(_) => {}
+                Err(_) => return Ok(false),
             }
         }
-        self.body[1] = [self.head[0], self.head[1]];
-        self.head[0] += self.direction[0] as i16;
-        self.head[1] += self.direction[1] as i16;
-        self.body[self.length - 1] = self.tail;
-        self.body[0] = self.head;
-        Ok(())
+        // whatever was there is garbage now, and the caller redraws
+        renderer.clear()?;
     }
+}
 
-    fn collided_with_self(&self) -> bool {
-        self.body[1..self.length].contains(&self.head)
+/// Draws the `--step-debug` readout on the lines under the HUD.
+fn draw_overlay(renderer: &mut impl Renderer, game: &Game) -> rake::Result<()> {
+    let width = game.width() as usize;
+    for (i, line) in debug::overlay(game).iter().enumerate() {
+        renderer.draw_text(
+            0,
+            game.height() + 1 + i as u16,
+            &format!("{line:<width$.width$}"),
+            game.config().theme.text,
+        )?;
     }
+    Ok(())
+}
 
-    fn collided_with_wall(&self, game: &Game) -> bool {
-        if self.head[0] == game.width as i16 - 1 {
-            return true;
-        } else if self.head[0] == 0 {
-            return true;
-        } else if self.head[1] == game.height as i16 - 1 {
-            return true;
-        } else if self.head[1] == 0 {
-            return true;
+/// Draws the `--speedrun` splits on the line `below` lines under the HUD,
+/// the latest on the right and as many of the rest as fit.
+fn draw_splits(
+    renderer: &mut impl Renderer,
+    game: &Game,
+    lang: &Lang,
+    splits: &[u64],
+    below: u16,
+) -> rake::Result<()> {
+    let width = game.width() as usize;
+    let mut line = String::new();
+    for (i, ms) in splits.iter().enumerate().rev() {
+        let apples = (i + 1) * SPLIT_APPLES;
+        let time = Clock::from_ms(*ms).tenths(true);
+        let split = lang.format("split", &[("apples", &apples), ("time", &time)]);
+        if line.chars().count() + split.chars().count() + 2 > width {
+            break;
         }
-        false
+        line = format!("{split}  {line}");
     }
+    renderer.draw_text(
+        0,
+        game.height() + 1 + below,
+        &format!("{:<width$.width$}", line.trim_end()),
+        game.config().theme.text,
+    )?;
+    Ok(())
 }
 
-struct Apple {
-    position: [i16; 2],
-    exists: bool,
+/// Opens the dev console over the board until it's closed again, running
+/// whatever's typed into it on `game`.
+fn run_console(
+    renderer: &mut impl Renderer,
+    events: &Receiver<Event>,
+    game: &mut Game,
+) -> rake::Result<()> {
+    let mut console = Console::new();
+    loop {
+        let mut frame = Frame::new(&mut *renderer);
+        frame.clear()?;
+        game.draw_border(&mut frame)?;
+        game.draw(&mut frame)?;
+        console.draw(&mut frame, game)?;
+        frame.finish()?;
+        match events.recv() {
+            Ok(Event::Key(key)) => {
+                if !console.key(key, game) {
+                    return Ok(());
+                }
+            }
+            Ok(_) => {}
+            Err(_) => return Ok(()),
+        }
+    }
+}
+
+/// Prints `text` on a line of its own, for screen reader mode. Raw mode
+/// needs the carriage return spelled out.
+fn say(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "{text}\r\n")?;
+    stdout.flush()
 }
 
-impl Apple {
-    fn new(position: [i16; 2], exists: bool) -> Apple {
-        Apple { position, exists }
+/// Offers to pick up the saved game. Enter or Y says yes, N or Esc no.
+fn ask_continue(
+    renderer: &mut impl Renderer,
+    events: &Receiver<Event>,
+    lang: &Lang,
+) -> rake::Result<bool> {
+    renderer.clear()?;
+    renderer.draw_text(0, 0, lang.text("continue-saved"), Color::Reset)?;
+    renderer.present()?;
+    loop {
+        let Ok(event) = events.recv() else {
+            return Ok(false);
+        };
+        let Event::Key(key) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('y' | 'Y') => return Ok(true),
+            KeyCode::Esc | KeyCode::Char('n' | 'N') => return Ok(false),
+            _ => {}
+        }
     }
+}
 
-    fn spawn(
-        &mut self,
-        snake: &Snake,
-        game: &Game,
-        rng: &mut ThreadRng,
-        stdout: &mut Stdout,
-    ) -> Result<(), std::io::Error> {
-        self.position = [
-            rng.random_range(0..game.width as i16),
-            rng.random_range(0..game.height as i16),
-        ];
+/// Puts the stats for a board filled end to end over the middle of it,
+/// and waits for any key.
+fn show_win(
+    renderer: &mut impl Renderer,
+    events: &Receiver<Event>,
+    game: &Game,
+    lang: &Lang,
+    summary: &RunSummary,
+    speedrun: bool,
+) -> rake::Result<()> {
+    let time = Clock::from_ms(summary.duration_ms).tenths(speedrun);
+    let mut stats = vec![
+        ("win-score", format!("{:>6}", summary.score)),
+        ("win-length", format!("{:>6}", summary.length)),
+        ("win-moves", format!("{:>6}", summary.ticks)),
+        ("win-time", format!("{:>6}", time.to_string())),
+    ];
+    if let Some(grade) = summary.grade {
+        stats.push(("win-grade", format!("{grade:>6}")));
+    }
+    // labels padded to the longest, whatever language they're in
+    let label_width = stats
+        .iter()
+        .map(|(key, _)| lang.text(key).chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = vec![lang.text("win").to_string(), String::new()];
+    lines.extend(
+        stats
+            .iter()
+            .map(|(key, value)| format!("{:<label_width$}  {value}", lang.text(key))),
+    );
+    lines.extend([String::new(), lang.text("win-continue").to_string()]);
+    // a margin either side, unless the board's too narrow for one
+    let widest = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 4;
+    let width = widest.min(game.width() as usize);
+    let left = (game.width() - width as u16) / 2;
+    let top = game.height().saturating_sub(lines.len() as u16) / 2;
+    for (i, line) in lines.iter().enumerate() {
+        renderer.draw_text(
+            left,
+            top + i as u16,
+            &format!("{line:^width$.width$}"),
+            game.config().theme.text,
+        )?;
+    }
+    renderer.present()?;
 
-        if !game.wall.contains(&self.position) && !snake.body.contains(&self.position) {
-            stdout
-                .queue(cursor::MoveTo(
-                    self.position[0].try_into().unwrap(),
-                    self.position[1].try_into().unwrap(),
-                ))?
-                .queue(style::PrintStyledContent("@".red()))?;
-            self.exists = true;
+    // whatever was pressed on the way to the last apple isn't an answer
+    wait_for_key(events);
+    Ok(())
+}
+
+/// Waits for a key press, ignoring any from before it was called.
+fn wait_for_key(events: &Receiver<Event>) {
+    while events.try_recv().is_ok() {}
+    while let Ok(event) = events.recv() {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+        {
+            break;
         }
-        Ok(())
     }
 }
 
-impl Default for Apple {
-    fn default() -> Apple {
-        Apple::new([0, 0], false)
+/// Walks through the controls with `Tutorial` on a slowed down version of
+/// `config`, starting a fresh board whenever the snake crashes. `false` if
+/// the player quit.
+fn run_tutorial(
+    renderer: &mut impl Renderer,
+    events: &Receiver<Event>,
+    config: &GameConfig,
+    keymap: &Keymap,
+    lang: &Arc<Lang>,
+) -> rake::Result<bool> {
+    let mut config = config.clone();
+    config.tick_rate = config.tick_rate.max(TUTORIAL_TICK_RATE);
+    config.wall_mode = WallMode::Fatal;
+    config.apples = 1;
+    Level::default().apply(&mut config);
+    let mut tutorial = Tutorial::new();
+    while !tutorial.is_done() {
+        let mut game = Game::new(config.clone());
+        game.set_lang(lang.clone());
+        tutorial.retry();
+        if !wait_for_room(renderer, events, keymap, lang, game.width(), game.height())? {
+            return Ok(false);
+        }
+        let mut redraw_border = true;
+        let mut pacer = Pacer::new(game.tick_rate());
+        loop {
+            game.set_help(vec![tutorial.prompt(keymap, lang)]);
+            let mut frame = Frame::new(&mut *renderer);
+            if redraw_border {
+                frame.clear()?;
+                game.draw_border(&mut frame)?;
+                redraw_border = false;
+            }
+            game.draw(&mut frame)?;
+            frame.finish()?;
+            if tutorial.is_done() {
+                break;
+            }
+
+            while let Some(wait) = pacer.remaining() {
+                match events.recv_timeout(wait) {
+                    Ok(Event::Resize(..)) => {
+                        if !wait_for_room(
+                            renderer,
+                            events,
+                            keymap,
+                            lang,
+                            game.width(),
+                            game.height(),
+                        )? {
+                            return Ok(false);
+                        }
+                        redraw_border = true;
+                    }
+                    Ok(event) if input::is_suspend(&event) => {
+                        rake::terminal::suspend()?;
+                        redraw_border = true;
+                    }
+                    Ok(event) => match keymap.action(&event) {
+                        Some(InputAction::Turn(direction)) => game.turn(direction),
+                        Some(InputAction::Quit) => return Ok(false),
+                        _ => {}
+                    },
+                    Err(RecvTimeoutError::Disconnected) => return Ok(false),
+                    Err(RecvTimeoutError::Timeout) => break,
+                }
+            }
+            pacer.advance(game.tick_rate());
+            if game.tick(None).died {
+                game.set_help(vec![lang.text("tutorial-crashed").to_string()]);
+                game.draw(&mut *renderer)?;
+                renderer.present()?;
+                wait_for_key(events);
+                break;
+            }
+            tutorial.observe(&game);
+        }
     }
+    wait_for_key(events);
+    Ok(true)
 }
 
-const EXIT_SIGNAL: [i16; 2] = [69, 69];
+/// Asks for up to three initials on the HUD line. `None` if the player
+/// skipped it with Esc.
+fn enter_initials(
+    renderer: &mut impl Renderer,
+    events: &Receiver<Event>,
+    game: &Game,
+    lang: &Lang,
+    screen_reader: bool,
+    last: &str,
+) -> rake::Result<Option<String>> {
+    // whatever was pressed while the snake was dying isn't a name
+    while events.try_recv().is_ok() {}
 
-fn main() -> io::Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    let mut rng = rand::rng();
-    stdout.execute(cursor::Hide)?;
+    let mut initials = last.to_string();
+    loop {
+        let width = game.width() as usize;
+        let name = format!("{initials:_<3}");
+        let prompt = [
+            lang.format("initials", &[("places", &TABLE_SIZE), ("name", &name)]),
+            lang.format("initials-short", &[("name", &name)]),
+        ]
+        .into_iter()
+        .find(|prompt| screen_reader || prompt.chars().count() <= width)
+        .unwrap_or(name);
+        // read out afresh after every key, since there's no line to update
+        if screen_reader {
+            say(&prompt)?;
+        } else {
+            renderer.draw_text(
+                0,
+                game.height(),
+                &format!("{prompt:<width$.width$}"),
+                game.config().theme.text,
+            )?;
+            renderer.present()?;
+        }
 
-    let mut game = Game::default();
-    game.draw_border(&mut stdout)?;
-    let mut snake = Snake::spawn(&game);
-    let mut apple = Apple::default();
+        // the input thread only goes away if the terminal does
+        let Ok(event) = events.recv() else {
+            return Ok(None);
+        };
+        let Event::Key(key) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char(c) if c.is_ascii_alphanumeric() && initials.len() < 3 => {
+                initials.push(c.to_ascii_uppercase());
+            }
+            KeyCode::Backspace => {
+                initials.pop();
+            }
+            KeyCode::Enter if !initials.is_empty() => return Ok(Some(initials)),
+            KeyCode::Esc => return Ok(None),
+            _ => {}
+        }
+    }
+}
 
-    // MAIN GAME LOOP
-    loop {
-        game.handle_input(&mut snake)?;
-        if snake.direction == EXIT_SIGNAL {
-            // escape pressed
-            break;
+fn run_coop(args: &cli::GameArgs, either: bool, friendly_fire: bool) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let end = if either {
+        CoopEnd::Either
+    } else {
+        CoopEnd::Both
+    };
+    let mut coop = Coop::new(game_config(args, &settings)?, end, friendly_fire)?;
+    let keymap = settings.keymap()?;
+    let lang = settings.lang()?;
+    coop.set_lang(lang.clone());
+    // player two always has the arrows, even if they're bound for one too
+    let mut arrows = Keymap::empty();
+    for (key, direction) in [
+        (KeyCode::Up, Direction::Up),
+        (KeyCode::Down, Direction::Down),
+        (KeyCode::Left, Direction::Left),
+        (KeyCode::Right, Direction::Right),
+    ] {
+        arrows.bind(key, InputAction::Turn(direction));
+    }
+
+    let guard = TerminalGuard::enter(false)?;
+    let mut renderer = terminal_renderer(&settings);
+    let events = input::spawn();
+    let (width, height) = (coop.config().width, coop.config().height);
+    if !wait_for_room(&mut renderer, &events, &keymap, &lang, width, height)? {
+        return Ok(());
+    }
+    renderer.clear()?;
+    coop.draw(&mut renderer)?;
+    renderer.present()?;
+
+    let tick_rate = coop.config().tick_rate;
+    let mut pacer = Pacer::new(tick_rate);
+    'game: while !coop.is_over() {
+        while let Some(wait) = pacer.remaining() {
+            match events.recv_timeout(wait) {
+                Ok(Event::Resize(..)) => {
+                    if !wait_for_room(&mut renderer, &events, &keymap, &lang, width, height)? {
+                        break 'game;
+                    }
+                    renderer.clear()?;
+                }
+                Ok(event) if input::is_suspend(&event) => {
+                    rake::terminal::suspend()?;
+                    renderer.clear()?;
+                }
+                Ok(event) => match (arrows.action(&event), keymap.action(&event)) {
+                    (Some(InputAction::Turn(direction)), _) => coop.turn(1, direction),
+                    (_, Some(InputAction::Turn(direction))) => coop.turn(0, direction),
+                    (_, Some(InputAction::Quit)) => break 'game,
+                    _ => {}
+                },
+                Err(RecvTimeoutError::Disconnected) => break 'game,
+                Err(RecvTimeoutError::Timeout) => break,
+            }
         }
+        pacer.advance(tick_rate);
+        coop.tick();
+        let mut frame = Frame::new(&mut renderer);
+        coop.draw(&mut frame)?;
+        frame.finish()?;
+    }
 
-        while !apple.exists {
-            apple.spawn(&snake, &game, &mut rng, &mut stdout)?;
+    io::stdout().execute(cursor::MoveTo(0, coop.config().height + 1))?;
+    drop(guard);
+    println!(
+        "{}",
+        lang.format("score-together", &[("score", &coop.score())])
+    );
+    Ok(())
+}
+
+fn run_race(args: &cli::GameArgs, target: u16, against: Option<&str>) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let mut race = Race::new(game_config(args, &settings)?, target)?;
+    let keymap = settings.keymap()?;
+    let lang = settings.lang()?;
+    let mut opponent = against
+        .map(|name| bot::named(name).ok_or_else(|| no_such_bot(name)))
+        .transpose()?;
+    let names = match against {
+        Some(name) => [lang.text("hud-you").to_string(), name.to_string()],
+        None => [1, 2].map(|n| lang.format("race-player", &[("n", &n)])),
+    };
+    for (player, name) in names.iter().enumerate() {
+        let game = race.game_mut(player);
+        game.set_lang(lang.clone());
+        game.set_help(vec![name.clone()]);
+    }
+    // against a bot, both sets of keys are player one's; otherwise player
+    // two has the arrows, like in co-op
+    let mut arrows = Keymap::empty();
+    if opponent.is_none() {
+        for (key, direction) in [
+            (KeyCode::Up, Direction::Up),
+            (KeyCode::Down, Direction::Down),
+            (KeyCode::Left, Direction::Left),
+            (KeyCode::Right, Direction::Right),
+        ] {
+            arrows.bind(key, InputAction::Turn(direction));
         }
+    }
+
+    let guard = TerminalGuard::enter(false)?;
+    let mut renderer = terminal_renderer(&settings);
+    let events = input::spawn();
+    // `wait_for_room` leaves a row for the HUD already
+    let (width, height) = race.size();
+    let height = height - 1;
+    if !wait_for_room(&mut renderer, &events, &keymap, &lang, width, height)? {
+        return Ok(());
+    }
+    renderer.clear()?;
+    race.draw_border(&mut renderer)?;
+    race.draw(&mut renderer)?;
+    renderer.present()?;
 
-        if snake.ate(&mut apple) {
-            apple.exists = false;
-            snake.grow();
-            game.increase_score();
+    let tick_rate = race.game(0).config().tick_rate;
+    let mut pacer = Pacer::new(tick_rate);
+    'game: while !race.is_over() {
+        while let Some(wait) = pacer.remaining() {
+            match events.recv_timeout(wait) {
+                Ok(Event::Resize(..)) => {
+                    if !wait_for_room(&mut renderer, &events, &keymap, &lang, width, height)? {
+                        break 'game;
+                    }
+                    renderer.clear()?;
+                    race.draw_border(&mut renderer)?;
+                }
+                Ok(event) if input::is_suspend(&event) => {
+                    rake::terminal::suspend()?;
+                    renderer.clear()?;
+                    race.draw_border(&mut renderer)?;
+                }
+                Ok(event) => match (arrows.action(&event), keymap.action(&event)) {
+                    (Some(InputAction::Turn(direction)), _) => race.turn(1, direction),
+                    (_, Some(InputAction::Turn(direction))) => race.turn(0, direction),
+                    (_, Some(InputAction::Quit)) => break 'game,
+                    _ => {}
+                },
+                Err(RecvTimeoutError::Disconnected) => break 'game,
+                Err(RecvTimeoutError::Timeout) => break,
+            }
+        }
+        pacer.advance(tick_rate);
+        if let Some(opponent) = &mut opponent
+            && let Some(direction) = opponent.decide(race.game(1))
+        {
+            race.turn(1, direction);
         }
+        race.tick();
+        let mut frame = Frame::new(&mut renderer);
+        race.draw(&mut frame)?;
+        frame.finish()?;
+    }
+
+    io::stdout().execute(cursor::MoveTo(0, height + 1))?;
+    drop(guard);
+    if race.is_over() {
+        let scores = [0, 1].map(|player| race.game(player).score());
+        let line = match race.winner() {
+            Some(winner) => lang.format(
+                // "you wins" won't do
+                if against.is_some() && winner == 0 {
+                    "race-you-won"
+                } else {
+                    "race-won"
+                },
+                &[
+                    ("name", &names[winner]),
+                    ("score", &scores[winner]),
+                    ("other", &scores[1 - winner]),
+                ],
+            ),
+            None => lang.format("race-tie", &[("score", &scores[0])]),
+        };
+        println!("{line}");
+    }
+    Ok(())
+}
 
-        snake.slither()?;
+fn run_hotseat(args: &cli::GameArgs, rounds: u32, names: Option<Vec<String>>) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let keymap = settings.keymap()?;
+    let lang = settings.lang()?;
+    // clap makes sure there are two if there are any
+    let names = match names {
+        Some(names) => [names[0].clone(), names[1].clone()],
+        None => [1, 2].map(|n| lang.format("race-player", &[("n", &n)])),
+    };
+    let mut series = Series::new(game_config(args, &settings)?, names, rounds)?;
 
-        if snake.collided_with_self() {
+    let guard = TerminalGuard::enter(false)?;
+    let mut renderer = terminal_renderer(&settings);
+    let events = input::spawn();
+    'series: while let Some(player) = series.next() {
+        let mut lines = series.scoreboard(&lang);
+        let name = series.name(player).to_string();
+        lines.extend([
+            String::new(),
+            lang.format(
+                "hotseat-turn",
+                &[
+                    ("round", &series.round()),
+                    ("rounds", &series.rounds()),
+                    ("name", &name),
+                ],
+            ),
+        ]);
+        if let Some(score) = series.to_beat() {
+            lines.push(lang.format("hotseat-beat", &[("score", &score)]));
+        }
+        lines.extend([String::new(), lang.text("hotseat-start").to_string()]);
+        if !ask_ready(&mut renderer, &events, &lines)? {
             break;
         }
 
-        if snake.collided_with_wall(&game) {
+        let mut game = series.game();
+        game.set_lang(lang.clone());
+        game.set_help(vec![name]);
+        if !wait_for_room(
+            &mut renderer,
+            &events,
+            &keymap,
+            &lang,
+            game.width(),
+            game.height(),
+        )? {
             break;
         }
+        let mut redraw_border = true;
+        let mut pacer = Pacer::new(game.tick_rate());
+        while !game.is_over() {
+            let mut frame = Frame::new(&mut renderer);
+            if redraw_border {
+                frame.clear()?;
+                game.draw_border(&mut frame)?;
+                redraw_border = false;
+            }
+            game.draw(&mut frame)?;
+            frame.finish()?;
+
+            while let Some(wait) = pacer.remaining() {
+                match events.recv_timeout(wait) {
+                    Ok(Event::Resize(..)) => {
+                        if !wait_for_room(
+                            &mut renderer,
+                            &events,
+                            &keymap,
+                            &lang,
+                            game.width(),
+                            game.height(),
+                        )? {
+                            break 'series;
+                        }
+                        redraw_border = true;
+                    }
+                    Ok(event) if input::is_suspend(&event) => {
+                        rake::terminal::suspend()?;
+                        redraw_border = true;
+                    }
+                    Ok(event) => match keymap.action(&event) {
+                        Some(InputAction::Turn(direction)) => game.turn(direction),
+                        Some(InputAction::Quit) => break 'series,
+                        _ => {}
+                    },
+                    Err(RecvTimeoutError::Disconnected) => break 'series,
+                    Err(RecvTimeoutError::Timeout) => break,
+                }
+            }
+            pacer.advance(game.tick_rate());
+            game.tick(None);
+        }
+        series.record(game.score());
+        game.set_help(vec![lang.format("game-over", &[("score", &game.score())])]);
+        game.draw(&mut renderer)?;
+        renderer.present()?;
+        wait_for_key(&events);
+    }
 
-        snake.draw(&mut stdout)?;
+    renderer.clear()?;
+    renderer.present()?;
+    io::stdout().execute(cursor::MoveTo(0, 0))?;
+    drop(guard);
+    for line in series.scoreboard(&lang) {
+        println!("{line}");
+    }
+    if series.is_over() {
+        let wins = series.wins();
+        let line = match series.winner() {
+            Some(winner) => lang.format(
+                "hotseat-won",
+                &[
+                    ("name", &series.name(winner)),
+                    ("wins", &wins[winner]),
+                    ("other", &wins[1 - winner]),
+                ],
+            ),
+            None => lang.format("hotseat-tie", &[("wins", &wins[0])]),
+        };
+        println!("{line}");
+    }
+    Ok(())
+}
 
-        game.display_score(&mut stdout)?;
+/// Shows `lines` and waits to hear whether to go on: Enter or Space for
+/// yes, Esc or Q for no.
+fn ask_ready(
+    renderer: &mut impl Renderer,
+    events: &Receiver<Event>,
+    lines: &[String],
+) -> rake::Result<bool> {
+    renderer.clear()?;
+    for (i, line) in lines.iter().enumerate() {
+        renderer.draw_text(0, i as u16, line, Color::Reset)?;
+    }
+    renderer.present()?;
+    // whatever was pressed as the last run ended isn't an answer
+    while events.try_recv().is_ok() {}
+    loop {
+        let Ok(event) = events.recv() else {
+            return Ok(false);
+        };
+        let Event::Key(key) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter | KeyCode::Char(' ') => return Ok(true),
+            KeyCode::Esc | KeyCode::Char('q' | 'Q') => return Ok(false),
+            _ => {}
+        }
+    }
+}
 
-        // can't forget to flush after myself
-        stdout.flush()?;
+fn run_host(
+    args: &cli::GameArgs,
+    port: u16,
+    players: usize,
+    name: Option<String>,
+) -> rake::Result<()> {
+    if players == 0 {
+        return Err(rake::RakeError::Config(
+            "a game needs at least one player".into(),
+        ));
     }
+    let config = args.settings()?.game_config()?;
+    let listener = listen(port)?;
+    // the host's own snake joins over loopback like anyone else's
+    thread::spawn(move || net::host(listener, config, players));
+    run_join(args, &format!("127.0.0.1:{port}"), name)
+}
 
-    // and clean up
-    disable_raw_mode()?;
-    stdout.queue(cursor::MoveTo(0, game.height + 1))?;
-    stdout.execute(cursor::Show)?;
+fn run_serve(args: &cli::GameArgs, port: u16) -> rake::Result<()> {
+    let config = args.settings()?.game_config()?;
+    let listener = listen(port)?;
+    println!(
+        "Serving a {}x{} arena on port {port}, Ctrl-C to stop",
+        config.width, config.height
+    );
+    net::serve(listener, config)
+}
+
+#[cfg(feature = "websocket")]
+fn run_bridge(addr: &str, port: u16) -> rake::Result<()> {
+    let client = Client::spectate(addr)?;
+    let listener = listen(port)?;
+    println!("Passing {addr} on to browsers at ws://localhost:{port}, Ctrl-C to stop");
+    rake::websocket::bridge(client, listener)
+}
+
+#[cfg(feature = "ssh")]
+fn run_ssh_serve(args: &cli::GameArgs, bind: &str, port: u16) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let config = settings.game_config()?;
+    println!(
+        "Serving {}x{} games over SSH on {bind} port {port}, Ctrl-C to stop",
+        config.width, config.height
+    );
+    rake::ssh::serve(bind, port, config, settings.keymap()?, settings.lang()?)
+}
+
+fn listen(port: u16) -> rake::Result<TcpListener> {
+    TcpListener::bind(("0.0.0.0", port))
+        .map_err(|e| rake::RakeError::Connection(format!("couldn't listen on port {port}: {e}")))
+}
+
+/// Plays in someone else's game, or our own through `run_host`.
+fn run_join(args: &cli::GameArgs, addr: &str, name: Option<String>) -> rake::Result<()> {
+    let name = name.unwrap_or_else(default_name);
+    play_remote(args, Client::connect(addr, &name)?, false)
+}
+
+fn run_watch(args: &cli::GameArgs, addr: &str) -> rake::Result<()> {
+    play_remote(args, Client::spectate(addr)?, true)
+}
 
+// draws whatever the host sends until the game ends or we leave
+fn play_remote(args: &cli::GameArgs, mut client: Client, watching: bool) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let theme = settings.game_config()?.theme;
+    let keymap = settings.keymap()?;
+    let lang = settings.lang()?;
+
+    let guard = TerminalGuard::enter(false)?;
+    let mut renderer = terminal_renderer(&settings);
+    let events = input::spawn();
+    renderer.clear()?;
+    renderer.present()?;
+
+    let mut me = None;
+    let mut last: Option<ArenaView> = None;
+    let outcome = 'game: loop {
+        while let Ok(event) = events.try_recv() {
+            match keymap.action(&event) {
+                Some(InputAction::Turn(direction)) if !watching => {
+                    client.send(&ClientMessage::Turn { direction })?;
+                }
+                Some(InputAction::Quit) => {
+                    // leaving anyway, so it doesn't matter if this arrives
+                    let _ = client.send(&ClientMessage::Quit);
+                    break 'game lang
+                        .text(if watching {
+                            "stopped-watching"
+                        } else {
+                            "left-game"
+                        })
+                        .to_string();
+                }
+                _ => {}
+            }
+        }
+        match client
+            .messages()
+            .recv_timeout(time::Duration::from_millis(5))
+        {
+            Ok(ServerMessage::Welcome { player }) => me = Some(player),
+            Ok(ServerMessage::Lobby { players, needed }) if last.is_none() => {
+                let text = lang.format("waiting", &[("players", &players), ("needed", &needed)]);
+                renderer.draw_text(0, 0, &text, theme.text)?;
+                renderer.present()?;
+            }
+            Ok(ServerMessage::Lobby { .. }) => {}
+            Ok(ServerMessage::State { view }) => {
+                if last.is_none() {
+                    renderer.clear()?;
+                }
+                view.draw(&mut renderer, &theme, &lang, me)?;
+                renderer.present()?;
+                last = Some(view);
+            }
+            Ok(ServerMessage::Over { winner }) => {
+                let name = winner.and_then(|winner| {
+                    last.as_ref()
+                        .and_then(|view| view.players.get(winner))
+                        .map(|player| player.name.clone())
+                });
+                break match (winner, name) {
+                    (Some(winner), _) if Some(winner) == me => lang.text("you-won").to_string(),
+                    (Some(_), Some(name)) => lang.format("someone-won", &[("name", &name)]),
+                    _ => lang.text("draw").to_string(),
+                };
+            }
+            Ok(ServerMessage::Rejected { reason }) => {
+                drop(guard);
+                return Err(rake::RakeError::Connection(format!(
+                    "couldn't join: {reason}"
+                )));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break lang.text("lost-connection").to_string(),
+        }
+    };
+
+    let height = last.as_ref().map_or(1, |view| view.height + 1);
+    io::stdout().execute(cursor::MoveTo(0, height))?;
+    drop(guard);
+    println!("{outcome}");
     Ok(())
 }
+
+// what to call us if --name wasn't given
+fn default_name() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "player".into())
+}
+
+fn run_highscores(args: &cli::GameArgs) -> rake::Result<()> {
+    let lang = args.settings()?.lang()?;
+    let scores = match HighScores::default_path() {
+        Some(path) => HighScores::load(&path)?,
+        None => HighScores::default(),
+    };
+    let mut tables = scores.tables().peekable();
+    if tables.peek().is_none() {
+        println!("{}", lang.text("no-high-scores"));
+    }
+    while let Some((key, table)) = tables.next() {
+        print_table(key, table, None);
+        if tables.peek().is_some() {
+            println!();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "online")]
+fn run_global_highscores(args: &cli::GameArgs) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let Some(url) = &settings.leaderboard.url else {
+        return Err(rake::RakeError::Config(
+            "no leaderboard set up, see `rake config set leaderboard.url`".into(),
+        ));
+    };
+    let board = board_key(&settings.game_config()?);
+    let top = rake::online::Leaderboard::new(url, None).top(&board)?;
+    println!("{board} at {url}");
+    if top.is_empty() {
+        println!("  nobody yet");
+    }
+    for (i, entry) in top.iter().enumerate() {
+        println!("{:>3}. {:<3} {:>5}", i + 1, entry.name, entry.score);
+    }
+    Ok(())
+}
+
+fn run_heatmap(args: &cli::GameArgs) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let config = settings.game_config()?;
+    let deaths = match DeathLog::default_path() {
+        Some(path) => DeathLog::load(&path)?,
+        None => DeathLog::default(),
+    };
+    let board = board_key(&config);
+    let lang = settings.lang()?;
+    match deaths.heatmap(&config) {
+        Some(heatmap) if heatmap.total() > 0 => {
+            let color = settings.color.unwrap_or(true);
+            print!("{}", heatmap.render(color, settings.ascii == Some(true)));
+            println!(
+                "{}",
+                lang.format("deaths", &[("count", &heatmap.total()), ("board", &board)])
+            );
+            let causes: Vec<(String, u32)> = heatmap
+                .causes()
+                .into_iter()
+                .map(|(cause, count)| {
+                    let key = format!("death-{}", cause.name());
+                    (lang.text(&key).to_string(), count)
+                })
+                .collect();
+            let label_width = causes
+                .iter()
+                .map(|(label, _)| label.chars().count())
+                .max()
+                .unwrap_or(0);
+            for (label, count) in causes {
+                println!("  {label:<label_width$}  {count:>5}");
+            }
+        }
+        _ => println!("{}", lang.format("no-deaths", &[("board", &board)])),
+    }
+    Ok(())
+}
+
+fn run_replay(args: &cli::GameArgs, path: &Path, export: Option<&Path>) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let run = replay::load(path)?;
+    let config = replay::config_for(&run, settings.game_config()?)?;
+    let keymap = settings.keymap()?;
+    let lang = settings.lang()?;
+    let mut playback = Playback::new(&run, config);
+    playback.set_lang(lang.clone());
+    // nothing to steer, and the controls there are go under the board
+    playback.set_help(vec![]);
+    if let Some(export) = export {
+        return replay::export(export, &mut playback);
+    }
+    let quit = keymap.keys_for(InputAction::Quit).next().map_or_else(
+        || "?".to_string(),
+        |key| input::key_name(key).to_uppercase(),
+    );
+    let mut viewer = Viewer::new(&run, playback, lang.clone(), quit);
+    let (width, height) = (viewer.game().width(), viewer.game().height());
+    let rows = height + viewer::STATUS_LINES;
+
+    let guard = TerminalGuard::enter(false)?;
+    let mut renderer = terminal_renderer(&settings);
+    let events = input::spawn();
+    if !wait_for_room(&mut renderer, &events, &keymap, &lang, width, rows)? {
+        return Ok(());
+    }
+    renderer.clear()?;
+    viewer.game().draw_border(&mut renderer)?;
+    let pace = |viewer: &Viewer| match viewer.is_paused() {
+        true => Pacer::on_demand(),
+        false => Pacer::new(viewer.tick_rate()),
+    };
+    let mut pacer = pace(&viewer);
+    loop {
+        let columns = terminal::size()?.0;
+        let mut frame = Frame::new(&mut renderer);
+        viewer.draw(&mut frame, columns)?;
+        frame.finish()?;
+        let Some(wait) = pacer.remaining() else {
+            viewer.tick();
+            pacer = pace(&viewer);
+            continue;
+        };
+        let code = match events.recv_timeout(wait) {
+            Ok(Event::Resize(..)) => {
+                if !wait_for_room(&mut renderer, &events, &keymap, &lang, width, rows)? {
+                    break;
+                }
+                renderer.clear()?;
+                viewer.game().draw_border(&mut renderer)?;
+                continue;
+            }
+            Ok(event) if keymap.action(&event) == Some(InputAction::Quit) => break,
+            Ok(Event::Key(KeyEvent {
+                code,
+                kind: KeyEventKind::Press,
+                ..
+            })) => code,
+            Ok(_) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => continue,
+        };
+        match code {
+            KeyCode::Char(' ') => viewer.toggle_pause()?,
+            KeyCode::Right => viewer.step_forward(),
+            KeyCode::Left => viewer.step_back()?,
+            KeyCode::Up => viewer.faster(),
+            KeyCode::Down => viewer.slower(),
+            KeyCode::Char(']') => viewer.next_event()?,
+            KeyCode::Char('[') => viewer.previous_event()?,
+            KeyCode::Home => viewer.jump(0)?,
+            KeyCode::End => viewer.jump(10)?,
+            KeyCode::Char(digit @ '0'..='9') => viewer.jump(digit as u8 - b'0')?,
+            _ => continue,
+        }
+        // whatever changed, the next tick's a whole one from now
+        pacer = pace(&viewer);
+    }
+    io::stdout().execute(cursor::MoveTo(0, rows + 1))?;
+    drop(guard);
+    Ok(())
+}
+
+fn run_edit(args: &cli::GameArgs, path: &Path) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let mut config = settings.game_config()?;
+    // a new level starts out empty, on whatever board the settings give
+    if path.exists() {
+        Level::load(path)?.apply(&mut config);
+        config.validate()?;
+    }
+    let keymap = settings.keymap()?;
+    let lang = settings.lang()?;
+    let mut editor = Editor::new(config, lang.clone());
+    let (width, height) = (editor.config().width, editor.config().height);
+    // `wait_for_room` leaves a row for the HUD already
+    let rows = height + editor::STATUS_LINES - 1;
+
+    let guard = TerminalGuard::enter(false)?;
+    let mut renderer = terminal_renderer(&settings);
+    let events = input::spawn();
+    if !wait_for_room(&mut renderer, &events, &keymap, &lang, width, rows)? {
+        return Ok(());
+    }
+    renderer.clear()?;
+    // quitting with changes that aren't saved takes a second press
+    let mut warned = false;
+    loop {
+        let columns = terminal::size()?.0;
+        let mut frame = Frame::new(&mut renderer);
+        editor.draw(&mut frame, columns)?;
+        frame.finish()?;
+        let code = match events.recv() {
+            Ok(Event::Resize(..)) => {
+                if !wait_for_room(&mut renderer, &events, &keymap, &lang, width, rows)? {
+                    break;
+                }
+                renderer.clear()?;
+                continue;
+            }
+            Ok(Event::Key(KeyEvent {
+                code,
+                kind: KeyEventKind::Press,
+                ..
+            })) => code,
+            Ok(_) => continue,
+            Err(_) => break,
+        };
+        if code == KeyCode::Esc {
+            if !editor.is_changed() || warned {
+                break;
+            }
+            editor.say(lang.text("edit-unsaved").into());
+            warned = true;
+            continue;
+        }
+        warned = false;
+        match code {
+            KeyCode::Up => editor.move_cursor(Direction::Up),
+            KeyCode::Down => editor.move_cursor(Direction::Down),
+            KeyCode::Left => editor.move_cursor(Direction::Left),
+            KeyCode::Right => editor.move_cursor(Direction::Right),
+            KeyCode::Delete | KeyCode::Backspace => editor.erase(),
+            KeyCode::Char(c) => match c.to_ascii_lowercase() {
+                'w' => editor.toggle_wall(),
+                'o' => editor.place_spawn(),
+                'p' => editor.place_portal(),
+                'g' => editor.toggle_gate(),
+                'i' => editor.place_zone(Ground::Ice),
+                'm' => editor.place_zone(Ground::Mud),
+                'x' => editor.erase(),
+                's' => editor.save(path),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    io::stdout().execute(cursor::MoveTo(0, height + editor::STATUS_LINES))?;
+    drop(guard);
+    Ok(())
+}
+
+// exits 0 for a level that's fine, 1 for one with problems and 2 for a
+// file that isn't a level at all, so scripts can tell them apart
+fn run_check_level(args: &cli::GameArgs, path: &Path) -> ExitCode {
+    let loaded = args.settings().and_then(|settings| {
+        let mut config = settings.game_config()?;
+        Level::load(path)?.apply(&mut config);
+        Ok((config, settings.lang()?))
+    });
+    let (config, lang) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("rake: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    let problems = level::lint(&config);
+    for problem in &problems {
+        println!("{}: {problem}", path.display());
+    }
+    if !problems.is_empty() {
+        return ExitCode::FAILURE;
+    }
+    let board = board_key(&config);
+    println!(
+        "{}",
+        lang.format("level-ok", &[("path", &path.display()), ("board", &board)])
+    );
+    ExitCode::SUCCESS
+}
+
+fn run_skins(args: &cli::GameArgs) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let lang = settings.lang()?;
+    let unlocks = match Unlocks::default_path() {
+        Some(path) => Unlocks::load(&path)?,
+        None => Unlocks::default(),
+    };
+    let width = skins::SKINS
+        .iter()
+        .map(|skin| skin.name.len())
+        .max()
+        .unwrap_or(0);
+    for skin in &skins::SKINS {
+        let snake = settings.restyle(Theme::default(), Some(skin)).snake;
+        let mut preview = String::new();
+        for glyph in snake.iter().cycle().take(6) {
+            if glyph.color == Color::Reset {
+                preview.push(glyph.symbol);
+            } else {
+                let _ = write!(preview, "{}", glyph.symbol.with(glyph.color));
+            }
+        }
+        let status = if unlocks.has(skin) {
+            lang.text("skin-free").to_string()
+        } else {
+            skin.unlock.describe(&lang)
+        };
+        let marker = if settings.skin.as_deref() == Some(skin.name) {
+            " <"
+        } else {
+            ""
+        };
+        println!("{:<width$}  {preview}  {status}{marker}", skin.name);
+    }
+    Ok(())
+}
+
+fn print_table(key: &str, table: &[Entry], highlight: Option<usize>) {
+    println!("{key}");
+    for (i, entry) in table.iter().enumerate() {
+        let marker = if highlight == Some(i) { " <" } else { "" };
+        // scores from before runs were timed have no time to show, and
+        // from before they were graded no grade
+        let time = entry
+            .ms
+            .map(|ms| format!(" {:>8}", Clock::from_ms(ms).to_string()))
+            .unwrap_or_default();
+        let grade = entry
+            .grade
+            .map(|grade| format!(" {grade}"))
+            .unwrap_or_default();
+        println!(
+            "{:>3}. {:<3} {:>5}{time}{grade}{marker}",
+            i + 1,
+            entry.initials,
+            entry.score
+        );
+    }
+}
+
+fn run_bench(args: &cli::GameArgs, ticks: u64, json: bool) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let report = bench::run(&game_config(args, &settings)?, ticks);
+    if json {
+        let json = serde_json::to_string_pretty(&report.to_json()).expect("reports serialize");
+        println!("{json}");
+        return Ok(());
+    }
+    let [width, height] = report.board;
+    println!(
+        "{} ticks over {} games on a {width}x{height} board in {:.2?}",
+        report.ticks, report.games, report.elapsed
+    );
+    println!("{:.0} ticks/s", report.ticks_per_second());
+    println!(
+        "{} allocations ({:.3} per tick)",
+        report.allocations,
+        report.allocations_per_tick()
+    );
+    println!("{:.1?} per tick at the 99th percentile", report.p99);
+    println!(
+        "{} KiB of memory at most",
+        report.peak_memory.div_ceil(1024)
+    );
+    Ok(())
+}
+
+fn run_tournament(
+    args: &cli::GameArgs,
+    names: &[String],
+    games: u64,
+    max_ticks: u64,
+) -> rake::Result<()> {
+    let settings = args.settings()?;
+    let config = settings.game_config()?;
+    let names: Vec<String> = if names.is_empty() {
+        bot::NAMES.iter().map(|name| name.to_string()).collect()
+    } else {
+        names.to_vec()
+    };
+    let mut entrants = vec![];
+    for name in &names {
+        entrants.push(entrant(name)?);
+    }
+
+    let standings = tournament::run(
+        &mut entrants,
+        &config,
+        games,
+        settings.seed.unwrap_or(0),
+        max_ticks,
+    );
+    println!("{}, {games} games each", board_key(&config));
+    println!(
+        "     {:<16} {:>7} {:>6} {:>5} {:>5} {:>6}",
+        "entrant", "total", "avg", "best", "wins", "deaths"
+    );
+    for (i, standing) in standings.iter().enumerate() {
+        println!(
+            "{:>3}. {:<16} {:>7} {:>6.1} {:>5} {:>5} {:>6}",
+            i + 1,
+            standing.name,
+            standing.total,
+            standing.average(),
+            standing.best,
+            standing.wins,
+            standing.deaths
+        );
+    }
+    Ok(())
+}
+
+// a built-in AI, or a plugin file if it's not one of those
+fn entrant(name: &str) -> rake::Result<Entrant> {
+    if let Some(controller) = bot::named(name) {
+        return Ok(Entrant::new(name, controller));
+    }
+    #[cfg(feature = "wasm")]
+    if name.ends_with(".wasm") || name.ends_with(".wat") {
+        let path = Path::new(name);
+        let controller = rake::plugin::WasmController::load(path)?;
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(name);
+        return Ok(Entrant::new(stem, Box::new(controller)));
+    }
+    Err(no_such_bot(name))
+}
+
+fn no_such_bot(name: &str) -> rake::RakeError {
+    rake::RakeError::Config(format!(
+        "no bot called {name}, try one of: {}",
+        bot::NAMES.join(", ")
+    ))
+}
+
+fn run_config(args: &cli::GameArgs, action: ConfigAction) -> rake::Result<()> {
+    let path = args.config_path()?;
+    match action {
+        ConfigAction::Get { key } => match SettingsDocument::load(&path)?.get(&key)? {
+            Some(value) => println!("{value}"),
+            None => {
+                return Err(rake::RakeError::Config(format!(
+                    "{key} isn't set in {}",
+                    path.display()
+                )));
+            }
+        },
+        ConfigAction::Set { key, value } => {
+            let mut doc = SettingsDocument::load(&path)?;
+            doc.set(&key, &value)?;
+            doc.save(&path)?;
+        }
+        ConfigAction::Edit => {
+            if !path.exists() {
+                SettingsDocument::parse("")?.save(&path)?;
+            }
+            let editor = env::var("VISUAL")
+                .or_else(|_| env::var("EDITOR"))
+                .unwrap_or_else(|_| default_editor().into());
+            // editors are often set with flags, like `code --wait`
+            let mut words = editor.split_whitespace();
+            let program = words.next().unwrap_or(default_editor());
+            let status = process::Command::new(program)
+                .args(words)
+                .arg(&path)
+                .status()
+                .map_err(|e| rake::RakeError::Config(format!("couldn't run {program}: {e}")))?;
+            if !status.success() {
+                return Err(rake::RakeError::Config(format!(
+                    "{program} exited with {status}"
+                )));
+            }
+            // it's saved either way, but say so now rather than at the next game
+            let settings = Settings::load(&path)?;
+            settings.game_config()?;
+            settings.keymap()?;
+        }
+        ConfigAction::Path => println!("{}", path.display()),
+    }
+    Ok(())
+}
+
+fn default_editor() -> &'static str {
+    if cfg!(windows) { "notepad" } else { "vi" }
+}
@@ -0,0 +1,379 @@
+//! Multiplayer over TCP.
+//!
+//! The host runs the only real `Arena`; everyone else just sends turns and
+//! draws whatever the host says the board looks like. Messages are JSON,
+//! one per line, in both directions. After every tick the host sends the
+//! whole board, so a slow connection only ever shows a late frame, never
+//! one that disagrees with the host, and a turn counts on the first tick
+//! after it arrives.
+//!
+//! `host` plays a single round and stops. `serve` keeps one arena running
+//! indefinitely for people to drop in and out of.
+
+use crate::arena::{Arena, ArenaView};
+use crate::config::GameConfig;
+use crate::game::Direction;
+use crate::{RakeError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time;
+
+/// The port `rake host` listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 7878;
+
+// a client that can't keep up with this is dropped rather than allowed to
+// stall everyone else
+const WRITE_TIMEOUT: time::Duration = time::Duration::from_secs(2);
+
+/// What a client can say to the host.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// The first thing sent, asking for a snake.
+    Hello {
+        name: String,
+    },
+    /// Instead of `Hello`, to follow the game without playing.
+    Watch,
+    Turn {
+        direction: Direction,
+    },
+    Quit,
+}
+
+/// What the host tells its clients.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// You're in, as player number `player`.
+    Welcome {
+        player: usize,
+    },
+    /// Still waiting for everyone to turn up.
+    Lobby {
+        players: usize,
+        needed: usize,
+    },
+    State {
+        view: ArenaView,
+    },
+    /// The round's over, and player number `winner` won it unless it was
+    /// a draw.
+    Over {
+        winner: Option<usize>,
+    },
+    /// You can't join, and why.
+    Rejected {
+        reason: String,
+    },
+}
+
+fn connection_error(e: impl std::fmt::Display) -> RakeError {
+    RakeError::Connection(e.to_string())
+}
+
+fn send_line(stream: &mut TcpStream, message: &impl Serialize) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(message).expect("messages serialize");
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+// reads one message per line until the connection closes or sends
+// something that isn't one, then stops
+fn read_lines<T, F>(stream: TcpStream, mut each: F)
+where
+    T: for<'de> Deserialize<'de>,
+    F: FnMut(T) -> bool,
+{
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(message) = serde_json::from_str(&line) else {
+            break;
+        };
+        if !each(message) {
+            break;
+        }
+    }
+}
+
+enum Inbox {
+    Joined(usize, TcpStream),
+    Said(usize, ClientMessage),
+    Left(usize),
+}
+
+struct Seat {
+    stream: TcpStream,
+    player: Option<usize>,
+    watching: bool,
+}
+
+impl Seat {
+    // connections that haven't said hello yet don't get sent anything
+    fn listening(&self) -> bool {
+        self.player.is_some() || self.watching
+    }
+}
+
+/// How long a snake stays dead in `serve` before it's back.
+const RESPAWN_DELAY: time::Duration = time::Duration::from_secs(2);
+
+#[derive(Clone, Copy)]
+enum Mode {
+    // one round once enough players turn up, last snake standing wins
+    Round { needed: usize },
+    // never ends: come and go as you like, and dying only sets you back
+    Open,
+}
+
+/// Hosts one round for `needed` players on `listener`, then returns.
+pub fn host(listener: TcpListener, config: GameConfig, needed: usize) -> Result<()> {
+    run(listener, config, Mode::Round { needed })
+}
+
+/// Keeps an arena going on `listener` for as long as the process lives.
+/// Anyone can join at any time, and dead snakes respawn after a moment
+/// with their score reset.
+pub fn serve(listener: TcpListener, config: GameConfig) -> Result<()> {
+    run(listener, config, Mode::Open)
+}
+
+fn run(listener: TcpListener, config: GameConfig, mode: Mode) -> Result<()> {
+    let (inbox, messages) = mpsc::channel();
+    thread::spawn(move || accept(listener, inbox));
+
+    let mut arena = Arena::new(config);
+    let mut seats: BTreeMap<usize, Seat> = BTreeMap::new();
+    let mut started = matches!(mode, Mode::Open);
+    let tick_rate = arena.config().tick_rate;
+    let mut next_tick = time::Instant::now() + tick_rate;
+    let respawn_ticks = (RESPAWN_DELAY.as_millis() / tick_rate.as_millis().max(1)).max(1) as u64;
+    // when each dead player died, for respawning
+    let mut dead_since: BTreeMap<usize, u64> = BTreeMap::new();
+
+    loop {
+        let playing = seats.values().any(|seat| seat.player.is_some());
+        let wait = if started && playing {
+            next_tick.saturating_duration_since(time::Instant::now())
+        } else {
+            tick_rate
+        };
+        match messages.recv_timeout(wait) {
+            Ok(Inbox::Joined(id, stream)) => {
+                seats.insert(
+                    id,
+                    Seat {
+                        stream,
+                        player: None,
+                        watching: false,
+                    },
+                );
+            }
+            Ok(Inbox::Said(id, ClientMessage::Hello { name })) => {
+                let Some(seat) = seats.get_mut(&id) else {
+                    continue;
+                };
+                if seat.listening() {
+                    continue;
+                }
+                let late = started && matches!(mode, Mode::Round { .. });
+                let player = if late { None } else { arena.add_player(&name) };
+                let reply = match player {
+                    Some(player) => ServerMessage::Welcome { player },
+                    None if late => ServerMessage::Rejected {
+                        reason: "the game has already started".into(),
+                    },
+                    None => ServerMessage::Rejected {
+                        reason: "there's no room left on the board".into(),
+                    },
+                };
+                let _ = send_line(&mut seat.stream, &reply);
+                seat.player = player;
+                if player.is_none() {
+                    seats.remove(&id);
+                    continue;
+                }
+                if let Mode::Round { needed } = mode {
+                    let lobby = ServerMessage::Lobby {
+                        players: arena.alive(),
+                        needed,
+                    };
+                    broadcast(&mut seats, &mut arena, &lobby);
+                    if arena.alive() >= needed {
+                        started = true;
+                        next_tick = time::Instant::now() + tick_rate;
+                        let state = ServerMessage::State { view: arena.view() };
+                        broadcast(&mut seats, &mut arena, &state);
+                    }
+                } else if !playing {
+                    // nobody was here, so the clock wasn't running
+                    next_tick = time::Instant::now() + tick_rate;
+                }
+            }
+            Ok(Inbox::Said(id, ClientMessage::Watch)) => {
+                let Some(seat) = seats.get_mut(&id) else {
+                    continue;
+                };
+                if seat.listening() {
+                    continue;
+                }
+                seat.watching = true;
+                // something to look at straight away rather than next tick
+                let hello = match mode {
+                    Mode::Round { needed } if !started => ServerMessage::Lobby {
+                        players: arena.alive(),
+                        needed,
+                    },
+                    _ => ServerMessage::State { view: arena.view() },
+                };
+                if send_line(&mut seat.stream, &hello).is_err() {
+                    seats.remove(&id);
+                }
+            }
+            Ok(Inbox::Said(id, ClientMessage::Turn { direction })) => {
+                if let Some(Seat {
+                    player: Some(player),
+                    ..
+                }) = seats.get(&id)
+                {
+                    arena.turn(*player, direction);
+                }
+            }
+            Ok(Inbox::Said(id, ClientMessage::Quit) | Inbox::Left(id)) => {
+                if let Some(seat) = seats.remove(&id)
+                    && let Some(player) = seat.player
+                {
+                    arena.remove(player);
+                    dead_since.remove(&player);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(connection_error("stopped accepting connections"));
+            }
+        }
+
+        if !started {
+            continue;
+        }
+        if !seats.values().any(|seat| seat.player.is_some()) {
+            match mode {
+                // everyone left before it was decided
+                Mode::Round { .. } => return Ok(()),
+                Mode::Open => continue,
+            }
+        }
+        if time::Instant::now() < next_tick {
+            continue;
+        }
+        next_tick += tick_rate;
+        arena.tick();
+
+        if let Mode::Open = mode {
+            for player in seats.values().filter_map(|seat| seat.player) {
+                if arena.is_alive(player) {
+                    continue;
+                }
+                let since = *dead_since.entry(player).or_insert(arena.ticks());
+                if arena.ticks() - since >= respawn_ticks && arena.respawn(player) {
+                    dead_since.remove(&player);
+                }
+            }
+        }
+
+        let state = ServerMessage::State { view: arena.view() };
+        broadcast(&mut seats, &mut arena, &state);
+        if let Mode::Round { .. } = mode
+            && arena.is_over()
+        {
+            let over = ServerMessage::Over {
+                winner: arena.winner(),
+            };
+            broadcast(&mut seats, &mut arena, &over);
+            return Ok(());
+        }
+    }
+}
+
+// sends to everyone playing or watching, dropping anyone whose connection
+// has gone
+fn broadcast(seats: &mut BTreeMap<usize, Seat>, arena: &mut Arena, message: &ServerMessage) {
+    seats.retain(|_, seat| {
+        if !seat.listening() {
+            return true;
+        }
+        let sent = send_line(&mut seat.stream, message).is_ok();
+        if !sent && let Some(player) = seat.player {
+            arena.remove(player);
+        }
+        sent
+    });
+}
+
+fn accept(listener: TcpListener, inbox: Sender<Inbox>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let _ = stream.set_nodelay(true);
+        let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+        let Ok(reader) = stream.try_clone() else {
+            continue;
+        };
+        if inbox.send(Inbox::Joined(id, stream)).is_err() {
+            return;
+        }
+        let inbox = inbox.clone();
+        thread::spawn(move || {
+            read_lines(reader, |message| {
+                inbox.send(Inbox::Said(id, message)).is_ok()
+            });
+            let _ = inbox.send(Inbox::Left(id));
+        });
+    }
+}
+
+/// A connection to a host.
+pub struct Client {
+    stream: TcpStream,
+    messages: Receiver<ServerMessage>,
+}
+
+impl Client {
+    /// Connects and asks for a snake under `name`. Whether we got one comes
+    /// back as the first message.
+    pub fn connect(addr: impl ToSocketAddrs, name: &str) -> Result<Client> {
+        Client::open(addr, &ClientMessage::Hello { name: name.into() })
+    }
+
+    /// Connects to watch. Turns sent from here are ignored.
+    pub fn spectate(addr: impl ToSocketAddrs) -> Result<Client> {
+        Client::open(addr, &ClientMessage::Watch)
+    }
+
+    fn open(addr: impl ToSocketAddrs, hello: &ClientMessage) -> Result<Client> {
+        let mut stream = TcpStream::connect(addr).map_err(connection_error)?;
+        stream.set_nodelay(true).map_err(connection_error)?;
+        let reader = stream.try_clone().map_err(connection_error)?;
+        let (sender, messages) = mpsc::channel();
+        // the channel disconnects when the host goes away
+        thread::spawn(move || read_lines(reader, |message| sender.send(message).is_ok()));
+        send_line(&mut stream, hello).map_err(connection_error)?;
+        Ok(Client { stream, messages })
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> Result<()> {
+        send_line(&mut self.stream, message).map_err(connection_error)
+    }
+
+    /// Everything the host says, in order.
+    pub fn messages(&self) -> &Receiver<ServerMessage> {
+        &self.messages
+    }
+}
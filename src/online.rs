@@ -0,0 +1,115 @@
+//! A global leaderboard over HTTP, behind the `online` feature.
+//!
+//! Runs go to `POST {url}/runs` as JSON:
+//!
+//! ```json
+//! { "name": "ABC", "board": "fatal 40x15", "input_hash": "…",
+//!   "signature": "…", "summary": { … } }
+//! ```
+//!
+//! The summary carries the seed and every turn, so the server can replay
+//! the run and check the score and `input_hash` for itself. If a key is
+//! configured, `signature` is a hex HMAC-SHA256 of
+//! `board\nname\nscore\nseed\ninput_hash` with it. The top list comes from
+//! `GET {url}/top?board=…` as `[{ "name": "ABC", "score": 42 }, …]`.
+
+use crate::results::RunSummary;
+use crate::{RakeError, Result};
+use hmac::{Hmac, KeyInit, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time;
+use ureq::Agent;
+
+// nobody wants to sit at a dead terminal waiting on a slow server
+const TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+/// One line of the global top list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteEntry {
+    pub name: String,
+    pub score: u16,
+}
+
+#[derive(Serialize)]
+struct Submission<'a> {
+    name: &'a str,
+    board: &'a str,
+    input_hash: String,
+    signature: Option<String>,
+    summary: &'a RunSummary,
+}
+
+pub struct Leaderboard {
+    agent: Agent,
+    url: String,
+    key: Option<String>,
+}
+
+impl Leaderboard {
+    /// `key`, if given, signs every submission.
+    pub fn new(url: &str, key: Option<&str>) -> Leaderboard {
+        let agent = Agent::config_builder()
+            .timeout_global(Some(TIMEOUT))
+            .build()
+            .into();
+        Leaderboard {
+            agent,
+            url: url.trim_end_matches('/').into(),
+            key: key.map(String::from),
+        }
+    }
+
+    /// Sends a finished run in under `name` on leaderboard `board` (see
+    /// `scores::board_key`).
+    pub fn submit(&self, board: &str, name: &str, summary: &RunSummary) -> Result<()> {
+        let input_hash = format!("{:016x}", summary.input_hash());
+        let signature = self.key.as_deref().map(|key| {
+            let message = format!(
+                "{board}\n{name}\n{}\n{}\n{input_hash}",
+                summary.score,
+                summary.seed.unwrap_or(0)
+            );
+            sign(key, &message)
+        });
+        let submission = Submission {
+            name,
+            board,
+            input_hash,
+            signature,
+            summary,
+        };
+        self.agent
+            .post(format!("{}/runs", self.url))
+            .send_json(&submission)
+            .map_err(network_error)?;
+        Ok(())
+    }
+
+    /// The global top list for `board`, best first.
+    pub fn top(&self, board: &str) -> Result<Vec<RemoteEntry>> {
+        self.agent
+            .get(format!("{}/top", self.url))
+            .query("board", board)
+            .call()
+            .map_err(network_error)?
+            .body_mut()
+            .read_json()
+            .map_err(network_error)
+    }
+}
+
+fn sign(key: &str, message: &str) -> String {
+    // HMAC takes keys of any length, so this can't fail
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("any key length works");
+    mac.update(message.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn network_error(e: ureq::Error) -> RakeError {
+    RakeError::Network(e.to_string())
+}
@@ -0,0 +1,113 @@
+//! Keeping ticks on schedule.
+
+use std::time;
+
+/// Tick deadlines a fixed rate apart. Deadlines are absolute, so the time
+/// spent ticking and drawing a frame comes out of the wait for the next
+/// one instead of being added to it, and the snake moves at the same speed
+/// however slow the terminal or the machine is.
+///
+/// An `on_demand` pacer has no schedule at all: each tick waits for `step`.
+#[derive(Clone, Copy, Debug)]
+pub struct Pacer {
+    // `None` while waiting on `step`
+    next_tick: Option<time::Instant>,
+    on_demand: bool,
+}
+
+impl Pacer {
+    /// The first tick is due `tick_rate` from now.
+    pub fn new(tick_rate: time::Duration) -> Pacer {
+        Pacer {
+            next_tick: Some(time::Instant::now() + tick_rate),
+            on_demand: false,
+        }
+    }
+
+    /// A pacer for turn-based play, where nothing moves until `step`.
+    pub fn on_demand() -> Pacer {
+        Pacer {
+            next_tick: None,
+            on_demand: true,
+        }
+    }
+
+    pub fn is_on_demand(&self) -> bool {
+        self.on_demand
+    }
+
+    /// How long is left before the next tick is due, or `None` once it is.
+    /// Waiting on `step` is as good as forever, which `recv_timeout`
+    /// takes as blocking until something arrives.
+    pub fn remaining(&self) -> Option<time::Duration> {
+        let Some(next_tick) = self.next_tick else {
+            return Some(time::Duration::MAX);
+        };
+        next_tick
+            .checked_duration_since(time::Instant::now())
+            .filter(|left| !left.is_zero())
+    }
+
+    /// Makes the next tick due right away.
+    pub fn step(&mut self) {
+        self.next_tick = Some(time::Instant::now());
+    }
+
+    /// Schedules the tick after the one that's due, `tick_rate` after it.
+    pub fn advance(&mut self, tick_rate: time::Duration) {
+        if self.on_demand {
+            self.next_tick = None;
+            return;
+        }
+        let now = time::Instant::now();
+        let next_tick = self.next_tick.unwrap_or(now) + tick_rate;
+        // if we fell more than a whole tick behind (suspended, slow
+        // terminal) don't try to catch up with a burst of moves
+        self.next_tick = Some(if next_tick < now {
+            now + tick_rate
+        } else {
+            next_tick
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: time::Duration = time::Duration::from_secs(3600);
+
+    #[test]
+    fn the_next_tick_is_a_tick_after_the_last_deadline() {
+        let mut pacer = Pacer::new(HOUR);
+        assert!(pacer.remaining().is_some_and(|left| left <= HOUR));
+        // from when the last one was due, not from now
+        pacer.advance(HOUR);
+        assert!(pacer.remaining().is_some_and(|left| left > HOUR));
+        assert_eq!(Pacer::new(time::Duration::ZERO).remaining(), None);
+    }
+
+    #[test]
+    fn falling_behind_doesnt_bring_a_burst_of_ticks() {
+        let tick_rate = time::Duration::from_millis(40);
+        let mut pacer = Pacer::new(time::Duration::ZERO);
+        std::thread::sleep(time::Duration::from_millis(50));
+        pacer.advance(tick_rate);
+        assert!(pacer.remaining().is_some_and(|left| left <= tick_rate));
+    }
+
+    #[test]
+    fn on_demand_waits_for_each_step() {
+        let mut pacer = Pacer::on_demand();
+        assert!(pacer.is_on_demand());
+        assert_eq!(pacer.remaining(), Some(time::Duration::MAX));
+        pacer.step();
+        assert_eq!(pacer.remaining(), None);
+        pacer.advance(HOUR);
+        assert_eq!(pacer.remaining(), Some(time::Duration::MAX));
+        // a step on a schedule just brings the next tick forward
+        let mut pacer = Pacer::new(HOUR);
+        pacer.step();
+        assert_eq!(pacer.remaining(), None);
+    }
+}
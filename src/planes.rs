@@ -0,0 +1,148 @@
+//! The board as numbers, for bots, agents and web pages to read without
+//! going anywhere near what's drawn on screen.
+//!
+//! There's a plane for each `Channel`, one after the other, and each plane
+//! is a byte per cell row by row from the top left: 1 where there's one of
+//! those, 0 where there isn't. The border counts as walls even in wrap
+//! mode, where it's only for show. The head is only in the head plane,
+//! not the body one.
+
+use crate::arena::ArenaView;
+use crate::game::Game;
+use crate::grid::{Coord, Tile};
+use rand::Rng;
+use serde::Serialize;
+
+/// What a plane shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Wall,
+    Body,
+    Head,
+    Food,
+}
+
+impl Channel {
+    /// Every channel, in the order the planes come in.
+    pub const ALL: [Channel; 4] = [Channel::Wall, Channel::Body, Channel::Head, Channel::Food];
+}
+
+/// A board split into planes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Planes {
+    width: u16,
+    height: u16,
+    data: Vec<u8>,
+}
+
+impl Planes {
+    /// Empty planes for a `width` by `height` board.
+    pub fn new(width: u16, height: u16) -> Planes {
+        let size = Channel::ALL.len() * width as usize * height as usize;
+        Planes {
+            width,
+            height,
+            data: vec![0; size],
+        }
+    }
+
+    /// `game` as it stands.
+    pub fn of<R: Rng>(game: &Game<R>) -> Planes {
+        let mut planes = Planes::new(game.width(), game.height());
+        planes.encode(game);
+        planes
+    }
+
+    /// A multiplayer board, every snake that hasn't left on it.
+    pub fn of_view(view: &ArenaView) -> Planes {
+        let mut planes = Planes::new(view.width, view.height);
+        for y in 0..view.height as i16 {
+            for x in 0..view.width as i16 {
+                if x == 0 || y == 0 || x == view.width as i16 - 1 || y == view.height as i16 - 1 {
+                    planes.set(Channel::Wall, [x, y]);
+                }
+            }
+        }
+        for player in view.players.iter().filter(|player| !player.left) {
+            if let Some((head, body)) = player.body.split_first() {
+                planes.set(Channel::Head, *head);
+                for cell in body {
+                    planes.set(Channel::Body, *cell);
+                }
+            }
+        }
+        for apple in &view.apples {
+            planes.set(Channel::Food, *apple);
+        }
+        planes
+    }
+
+    /// Redoes the planes for `game`, which has to be on a board the same
+    /// size. Nothing's allocated, so it's fine to do every tick.
+    pub fn encode<R: Rng>(&mut self, game: &Game<R>) {
+        self.data.fill(0);
+        let grid = game.grid();
+        for y in 0..self.height as i16 {
+            for x in 0..self.width as i16 {
+                let channel = match grid.get([x, y]) {
+                    Tile::Wall => Channel::Wall,
+                    Tile::Snake => Channel::Body,
+                    Tile::Apple => Channel::Food,
+                    Tile::Empty => continue,
+                };
+                self.set(channel, [x, y]);
+            }
+        }
+        // the grid doesn't tell the head from the rest, and it isn't on
+        // the grid at all once it's run into a wall
+        let head = game.snake().head();
+        self.clear(Channel::Body, head);
+        self.set(Channel::Head, head);
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Whether `cell` has a `channel` in it. Off the board, nothing does.
+    pub fn get(&self, channel: Channel, cell: Coord) -> bool {
+        self.index(channel, cell)
+            .is_some_and(|index| self.data[index] == 1)
+    }
+
+    /// The plane for `channel` on its own.
+    pub fn plane(&self, channel: Channel) -> &[u8] {
+        let size = self.width as usize * self.height as usize;
+        let start = channel as usize * size;
+        &self.data[start..start + size]
+    }
+
+    /// Every plane, one after the other.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn set(&mut self, channel: Channel, cell: Coord) {
+        if let Some(index) = self.index(channel, cell) {
+            self.data[index] = 1;
+        }
+    }
+
+    fn clear(&mut self, channel: Channel, cell: Coord) {
+        if let Some(index) = self.index(channel, cell) {
+            self.data[index] = 0;
+        }
+    }
+
+    fn index(&self, channel: Channel, cell: Coord) -> Option<usize> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let x = usize::try_from(cell[0]).ok().filter(|x| *x < width)?;
+        let y = usize::try_from(cell[1]).ok().filter(|y| *y < height)?;
+        Some(channel as usize * width * height + y * width + x)
+    }
+}
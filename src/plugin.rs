@@ -0,0 +1,149 @@
+//! WebAssembly bot plugins, behind the `wasm` feature.
+//!
+//! A plugin is a module with no imports that exports:
+//!
+//! - `memory`
+//! - `rake_buffer(len: i32) -> i32`, returning the address of at least
+//!   `len` writable bytes. Rake copies the board there before each move.
+//!   Returning the same static buffer every time is fine.
+//! - `rake_decide(width: i32, height: i32, head_x: i32, head_y: i32,
+//!   direction: i32) -> i32`, returning the next direction.
+//!
+//! The board is one byte per cell, row by row from the top left: 0 empty,
+//! 1 wall, 2 snake, 3 apple. Directions are 0 up, 1 down, 2 left, 3 right;
+//! anything else from `rake_decide` means carry on straight.
+//!
+//! Plugins run in an interpreter with no access to the host, and each
+//! call gets a fixed fuel budget so a stuck plugin can't hang the game.
+
+use crate::bot::Controller;
+use crate::game::{Direction, Game};
+use crate::{RakeError, Result};
+use std::fs;
+use std::path::Path;
+use wasmi::{Config, Engine, Linker, Memory, Module, Store, TypedFunc};
+
+// plenty for a BFS over a large board, nowhere near enough to spin forever
+const FUEL_PER_MOVE: u64 = 10_000_000;
+
+pub struct WasmController {
+    store: Store<()>,
+    memory: Memory,
+    buffer: TypedFunc<i32, i32>,
+    decide: TypedFunc<(i32, i32, i32, i32, i32), i32>,
+    board: Vec<u8>,
+    failure: Option<RakeError>,
+}
+
+impl WasmController {
+    pub fn load(path: &Path) -> Result<WasmController> {
+        let bytes = fs::read(path)
+            .map_err(|e| RakeError::Plugin(format!("couldn't read {}: {e}", path.display())))?;
+        WasmController::from_bytes(&bytes)
+    }
+
+    /// Accepts binary modules or the text format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<WasmController> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes).map_err(plugin_error)?;
+        let mut store = Store::new(&engine, ());
+        store.set_fuel(FUEL_PER_MOVE).map_err(plugin_error)?;
+        // nothing is linked in, so a plugin that imports anything won't load
+        let linker = Linker::<()>::new(&engine);
+        let instance = linker
+            .instantiate_and_start(&mut store, &module)
+            .map_err(plugin_error)?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| RakeError::Plugin("plugin doesn't export `memory`".into()))?;
+        let buffer = instance
+            .get_typed_func::<i32, i32>(&store, "rake_buffer")
+            .map_err(plugin_error)?;
+        let decide = instance
+            .get_typed_func::<(i32, i32, i32, i32, i32), i32>(&store, "rake_decide")
+            .map_err(plugin_error)?;
+
+        Ok(WasmController {
+            store,
+            memory,
+            buffer,
+            decide,
+            board: vec![],
+            failure: None,
+        })
+    }
+
+    /// Why the plugin stopped playing, if it trapped or ran out of fuel.
+    /// After a failure it never makes another move.
+    pub fn failure(&self) -> Option<&RakeError> {
+        self.failure.as_ref()
+    }
+
+    fn try_decide(&mut self, game: &Game) -> Result<Option<Direction>> {
+        self.store.set_fuel(FUEL_PER_MOVE).map_err(plugin_error)?;
+
+        self.board.clear();
+        self.board
+            .extend(game.grid().tiles().iter().map(|tile| *tile as u8));
+        let address = self
+            .buffer
+            .call(&mut self.store, self.board.len() as i32)
+            .map_err(plugin_error)?;
+        self.memory
+            .write(&mut self.store, address as usize, &self.board)
+            .map_err(plugin_error)?;
+
+        let head = game.snake().head();
+        let choice = self
+            .decide
+            .call(
+                &mut self.store,
+                (
+                    game.width() as i32,
+                    game.height() as i32,
+                    head[0] as i32,
+                    head[1] as i32,
+                    direction_code(game.snake().direction()),
+                ),
+            )
+            .map_err(plugin_error)?;
+        Ok(match choice {
+            0 => Some(Direction::Up),
+            1 => Some(Direction::Down),
+            2 => Some(Direction::Left),
+            3 => Some(Direction::Right),
+            _ => None,
+        })
+    }
+}
+
+impl Controller for WasmController {
+    fn decide(&mut self, game: &Game) -> Option<Direction> {
+        if self.failure.is_some() {
+            return None;
+        }
+        match self.try_decide(game) {
+            Ok(direction) => direction,
+            Err(e) => {
+                self.failure = Some(e);
+                None
+            }
+        }
+    }
+}
+
+fn direction_code(direction: Direction) -> i32 {
+    match direction {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    }
+}
+
+fn plugin_error(e: impl std::fmt::Display) -> RakeError {
+    RakeError::Plugin(e.to_string())
+}
@@ -0,0 +1,191 @@
+//! Python bindings, behind the `python` feature: the game without the
+//! terminal, for training agents against the same rules people play by.
+//!
+//! ```python
+//! import rake
+//!
+//! env = rake.Env(width=20, height=10, seed=1, max_steps=1000, closer=0.1)
+//! board = env.reset()
+//! done = False
+//! while not done:
+//!     board, reward, done = env.step(agent(board, env.head, env.direction))
+//! ```
+//!
+//! It's `rl::RlEnv` underneath, and the rewards are `rl::Rewards`, each
+//! one a keyword argument. The board comes as `bytes`, one per cell, row
+//! by row from the top left: 0 empty, 1 wall, 2 snake, 3 apple, like wasm
+//! plugins see it. Actions are 0 up, 1 down, 2 left and 3 right; anything
+//! else, `None` included, carries on straight. `planes` has the board
+//! split up the way `planes::Planes` does it.
+
+use crate::config::GameConfig;
+use crate::game::Direction;
+use crate::planes::Planes;
+use crate::rl::{Observation, Rewards, RlEnv};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+/// A game to play a move at a time.
+#[pyclass(module = "rake")]
+pub struct Env {
+    env: RlEnv,
+}
+
+#[pymethods]
+impl Env {
+    /// A board `width` by `height`, border included. With a `seed` every
+    /// game plays out the same given the same moves; without one each
+    /// `reset` picks a new one. Episodes still going after `max_steps`
+    /// end there, and the rest are what each thing is worth.
+    #[new]
+    #[pyo3(signature = (
+        width = 40,
+        height = 15,
+        seed = None,
+        max_steps = None,
+        point = 1.0,
+        death = -1.0,
+        win = 1.0,
+        step = 0.0,
+        closer = 0.0,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        width: u16,
+        height: u16,
+        seed: Option<u64>,
+        max_steps: Option<u64>,
+        point: f32,
+        death: f32,
+        win: f32,
+        step: f32,
+        closer: f32,
+    ) -> PyResult<Env> {
+        let mut builder = GameConfig::builder().size(width, height);
+        if let Some(seed) = seed {
+            builder = builder.seed(seed);
+        }
+        let config = builder
+            .build()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let rewards = Rewards {
+            point,
+            death,
+            win,
+            step,
+            closer,
+        };
+        let mut env = RlEnv::new(config, rewards);
+        if let Some(max_steps) = max_steps {
+            env = env.with_max_steps(max_steps);
+        }
+        Ok(Env { env })
+    }
+
+    /// Starts a new episode, on `seed` if given, and returns its board.
+    #[pyo3(signature = (seed = None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> Bound<'py, PyBytes> {
+        let observation = match seed {
+            Some(seed) => self.env.reset_with_seed(seed),
+            None => self.env.reset(),
+        };
+        board(py, &observation)
+    }
+
+    /// Plays one move and returns the board after it, the reward for it,
+    /// and whether the episode's over.
+    #[pyo3(signature = (action = None))]
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        action: Option<i64>,
+    ) -> (Bound<'py, PyBytes>, f32, bool) {
+        let direction = match action {
+            Some(0) => Some(Direction::Up),
+            Some(1) => Some(Direction::Down),
+            Some(2) => Some(Direction::Left),
+            Some(3) => Some(Direction::Right),
+            _ => None,
+        };
+        let (observation, reward, done) = self.env.step(direction);
+        (board(py, &observation), reward, done)
+    }
+
+    /// The board as it stands.
+    fn observe<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        board(py, &self.env.observe())
+    }
+
+    /// The board as it stands as planes for walls, body, head and food,
+    /// one after the other.
+    fn planes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, Planes::of(self.env.game()).as_bytes())
+    }
+
+    #[getter]
+    fn width(&self) -> u16 {
+        self.env.game().width()
+    }
+
+    #[getter]
+    fn height(&self) -> u16 {
+        self.env.game().height()
+    }
+
+    /// Where the head is, as `(x, y)`.
+    #[getter]
+    fn head(&self) -> (i16, i16) {
+        let [x, y] = self.env.game().snake().head();
+        (x, y)
+    }
+
+    /// The way the snake goes next, as an action.
+    #[getter]
+    fn direction(&self) -> u8 {
+        match self.env.game().snake().direction() {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        }
+    }
+
+    #[getter]
+    fn length(&self) -> usize {
+        self.env.game().snake().len()
+    }
+
+    #[getter]
+    fn score(&self) -> u16 {
+        self.env.game().score()
+    }
+
+    /// How many times the snake has moved this game.
+    #[getter]
+    fn ticks(&self) -> u64 {
+        self.env.game().ticks()
+    }
+
+    /// The seed this game is playing on.
+    #[getter]
+    fn seed(&self) -> Option<u64> {
+        self.env.game().config().seed
+    }
+
+    /// Whether the episode's over: the snake dead, the board full or the
+    /// steps run out.
+    #[getter]
+    fn done(&self) -> bool {
+        self.env.is_done()
+    }
+}
+
+fn board<'py>(py: Python<'py>, observation: &Observation) -> Bound<'py, PyBytes> {
+    PyBytes::new(py, &observation.board)
+}
+
+#[pymodule]
+fn rake(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Env>()
+}
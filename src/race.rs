@@ -0,0 +1,112 @@
+//! Split screen: two boards side by side on the same seed, racing to a
+//! score. Each snake has a board of its own, so nothing one does gets in
+//! the other's way; the apples just turn up in the same places for both
+//! for as long as they eat the same way.
+//!
+//! The first to the target wins. A snake that dies is out, and if both
+//! are, the higher score wins.
+
+use crate::config::GameConfig;
+use crate::game::{Direction, Game};
+use crate::render::{Renderer, Viewport};
+use crate::{RakeError, Result};
+use rand::Rng;
+
+/// Columns between the two boards.
+pub const GAP: u16 = 2;
+
+/// Two games being raced.
+pub struct Race {
+    boards: [Game; 2],
+    target: u16,
+}
+
+impl Race {
+    /// Both boards are `config`, on its seed or, without one, the same
+    /// one picked at random.
+    pub fn new(mut config: GameConfig, target: u16) -> Result<Race> {
+        if target == 0 {
+            return Err(RakeError::Config(
+                "the target has to be at least one point".into(),
+            ));
+        }
+        config.seed.get_or_insert_with(|| rand::rng().random());
+        Ok(Race {
+            boards: [Game::new(config.clone()), Game::new(config)],
+            target,
+        })
+    }
+
+    /// `player` is 0, on the left, or 1.
+    pub fn game(&self, player: usize) -> &Game {
+        &self.boards[player]
+    }
+
+    /// For setting up each board's HUD.
+    pub fn game_mut(&mut self, player: usize) -> &mut Game {
+        &mut self.boards[player]
+    }
+
+    pub fn target(&self) -> u16 {
+        self.target
+    }
+
+    /// How much room both boards take, HUDs and all.
+    pub fn size(&self) -> (u16, u16) {
+        let game = &self.boards[0];
+        (game.width() * 2 + GAP, game.height() + 1)
+    }
+
+    pub fn turn(&mut self, player: usize, direction: Direction) {
+        self.boards[player].turn(direction);
+    }
+
+    /// Moves both snakes on, or whichever is still going.
+    pub fn tick(&mut self) {
+        for game in &mut self.boards {
+            game.tick(None);
+        }
+    }
+
+    /// Once someone's got to the target, or both snakes are out.
+    pub fn is_over(&self) -> bool {
+        self.boards.iter().any(|game| game.score() >= self.target)
+            || self.boards.iter().all(Game::is_over)
+    }
+
+    /// Who won, once it's over: whoever has more points, which is the
+    /// first to the target if anyone got there. `None` for a tie.
+    pub fn winner(&self) -> Option<usize> {
+        if !self.is_over() {
+            return None;
+        }
+        let [one, two] = self.boards.each_ref().map(Game::score);
+        match one.cmp(&two) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Draws both borders, like `Game::draw_border`.
+    pub fn draw_border(&self, renderer: &mut impl Renderer) -> Result<()> {
+        for (n, game) in self.boards.iter().enumerate() {
+            game.draw_border(&mut self.viewport(renderer, n))?;
+        }
+        Ok(())
+    }
+
+    /// Draws both boards, each with its own HUD under it.
+    pub fn draw(&self, renderer: &mut impl Renderer) -> Result<()> {
+        for (n, game) in self.boards.iter().enumerate() {
+            game.draw(&mut self.viewport(renderer, n))?;
+        }
+        Ok(())
+    }
+
+    fn viewport<'a, R: Renderer>(&self, renderer: &'a mut R, player: usize) -> Viewport<'a, R> {
+        let game = &self.boards[player];
+        let x = player as u16 * (game.width() + GAP);
+        Viewport::new(renderer, x, 0, game.width(), game.height() + 1)
+    }
+}
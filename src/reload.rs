@@ -0,0 +1,41 @@
+//! Noticing the settings file change, so edits to it can be picked up
+//! while a game's paused instead of after starting over. There's no
+//! watching as such, just a look at when the file was last written each
+//! time it's asked.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time;
+
+/// How often to look while waiting.
+pub const POLL: time::Duration = time::Duration::from_millis(500);
+
+/// The settings file, and when it had last been written.
+#[derive(Clone, Debug)]
+pub struct Watch {
+    path: PathBuf,
+    // `None` while there's no file there
+    modified: Option<time::SystemTime>,
+}
+
+impl Watch {
+    /// Starts from however `path` is now.
+    pub fn new(path: PathBuf) -> Watch {
+        let modified = modified(&path);
+        Watch { path, modified }
+    }
+
+    /// Whether the file's been written, made or removed since last time.
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<time::SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
@@ -0,0 +1,445 @@
+use crossterm::{
+    QueueableCommand, cursor,
+    style::{self, Color, Stylize},
+    terminal,
+};
+use std::collections::HashMap;
+use std::io::{self, Stdout, Write};
+
+/// Something the game can draw cells onto.
+///
+/// Drawing calls may be buffered; nothing is guaranteed to be visible until
+/// `present` is called.
+pub trait Renderer {
+    /// Draws `glyph` in `color` at column `x`, row `y`.
+    fn draw_cell(&mut self, x: u16, y: u16, glyph: char, color: Color) -> io::Result<()>;
+
+    /// Draws `glyph` in `color` on a `background` color. Renderers
+    /// without backgrounds draw it as `draw_cell` would.
+    fn draw_cell_on(
+        &mut self,
+        x: u16,
+        y: u16,
+        glyph: char,
+        color: Color,
+        background: Color,
+    ) -> io::Result<()> {
+        let _ = background;
+        self.draw_cell(x, y, glyph, color)
+    }
+
+    /// Blanks out the cell at column `x`, row `y`.
+    fn clear_cell(&mut self, x: u16, y: u16) -> io::Result<()>;
+
+    /// Blanks out every cell.
+    fn clear(&mut self) -> io::Result<()>;
+
+    /// Makes everything drawn since the last call visible.
+    fn present(&mut self) -> io::Result<()>;
+
+    /// Rings the bell along with the next `present`. Does nothing where
+    /// there's no bell to ring.
+    fn bell(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Draws `text` left to right starting at column `x`, row `y`.
+    fn draw_text(&mut self, x: u16, y: u16, text: &str, color: Color) -> io::Result<()> {
+        for (i, glyph) in text.chars().enumerate() {
+            self.draw_cell(x + i as u16, y, glyph, color)?;
+        }
+        Ok(())
+    }
+}
+
+/// A rectangle of some other renderer, for drawing a board somewhere other
+/// than the top left corner. Coordinates are relative to the rectangle and
+/// anything outside it is clipped.
+///
+/// `present` and `clear` only ever touch the rectangle; presenting the
+/// whole screen is left to whoever owns the underlying renderer.
+pub struct Viewport<'a, R: Renderer + ?Sized> {
+    inner: &'a mut R,
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+impl<'a, R: Renderer + ?Sized> Viewport<'a, R> {
+    pub fn new(inner: &'a mut R, x: u16, y: u16, width: u16, height: u16) -> Viewport<'a, R> {
+        Viewport {
+            inner,
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn contains(&self, x: u16, y: u16) -> bool {
+        x < self.width && y < self.height
+    }
+}
+
+impl<R: Renderer + ?Sized> Renderer for Viewport<'_, R> {
+    fn draw_cell(&mut self, x: u16, y: u16, glyph: char, color: Color) -> io::Result<()> {
+        if self.contains(x, y) {
+            self.inner.draw_cell(self.x + x, self.y + y, glyph, color)?;
+        }
+        Ok(())
+    }
+
+    fn draw_cell_on(
+        &mut self,
+        x: u16,
+        y: u16,
+        glyph: char,
+        color: Color,
+        background: Color,
+    ) -> io::Result<()> {
+        if self.contains(x, y) {
+            self.inner
+                .draw_cell_on(self.x + x, self.y + y, glyph, color, background)?;
+        }
+        Ok(())
+    }
+
+    fn clear_cell(&mut self, x: u16, y: u16) -> io::Result<()> {
+        if self.contains(x, y) {
+            self.inner.clear_cell(self.x + x, self.y + y)?;
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.inner.clear_cell(self.x + x, self.y + y)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn present(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn bell(&mut self) -> io::Result<()> {
+        self.inner.bell()
+    }
+}
+
+/// One frame's worth of drawing, made visible all at once by `finish`.
+///
+/// `present` does nothing on a frame, so whatever gets handed it halfway
+/// through can't flush early and show the screen half drawn.
+pub struct Frame<'a, R: Renderer + ?Sized> {
+    inner: &'a mut R,
+}
+
+impl<'a, R: Renderer + ?Sized> Frame<'a, R> {
+    pub fn new(inner: &'a mut R) -> Frame<'a, R> {
+        Frame { inner }
+    }
+
+    /// Presents everything drawn on the frame.
+    pub fn finish(self) -> io::Result<()> {
+        self.inner.present()
+    }
+}
+
+impl<R: Renderer + ?Sized> Renderer for Frame<'_, R> {
+    fn draw_cell(&mut self, x: u16, y: u16, glyph: char, color: Color) -> io::Result<()> {
+        self.inner.draw_cell(x, y, glyph, color)
+    }
+
+    fn draw_cell_on(
+        &mut self,
+        x: u16,
+        y: u16,
+        glyph: char,
+        color: Color,
+        background: Color,
+    ) -> io::Result<()> {
+        self.inner.draw_cell_on(x, y, glyph, color, background)
+    }
+
+    fn clear_cell(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.inner.clear_cell(x, y)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn present(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn bell(&mut self) -> io::Result<()> {
+        self.inner.bell()
+    }
+
+    fn draw_text(&mut self, x: u16, y: u16, text: &str, color: Color) -> io::Result<()> {
+        self.inner.draw_text(x, y, text, color)
+    }
+}
+
+/// Renders to the real terminal through crossterm, or to anything else
+/// that takes terminal escape codes, like an SSH channel.
+pub struct TerminalRenderer<W: Write = Stdout> {
+    stdout: W,
+    // with `only_changes`, what's on screen and what's been drawn since
+    screen: Option<Screen>,
+}
+
+// a glyph, its color and its background
+type Cell = (char, Color, Color);
+
+#[derive(Default)]
+struct Screen {
+    shown: HashMap<(u16, u16), Cell>,
+    // `None` for a cell cleared
+    drawn: Vec<((u16, u16), Option<Cell>)>,
+}
+
+impl<W: Write> TerminalRenderer<W> {
+    pub fn new(stdout: W) -> TerminalRenderer<W> {
+        TerminalRenderer {
+            stdout,
+            screen: None,
+        }
+    }
+
+    /// Holds everything back until `present`, then sends only the cells
+    /// that aren't on screen already. For consoles where every cell drawn
+    /// costs a call into the system, like the old Windows one; anywhere
+    /// else the terminal's quicker at sorting it out.
+    pub fn only_changes(mut self) -> TerminalRenderer<W> {
+        self.screen = Some(Screen::default());
+        self
+    }
+
+    fn queue_cell(&mut self, x: u16, y: u16, cell: Option<Cell>) -> io::Result<()> {
+        self.stdout.queue(cursor::MoveTo(x, y))?;
+        match cell {
+            None => self.stdout.queue(style::Print(' '))?,
+            // no escape codes at all for the default color, so NO_COLOR
+            // means exactly that
+            Some((glyph, Color::Reset, Color::Reset)) => self.stdout.queue(style::Print(glyph))?,
+            Some((glyph, color, Color::Reset)) => self
+                .stdout
+                .queue(style::PrintStyledContent(glyph.with(color)))?,
+            Some((glyph, color, background)) => self
+                .stdout
+                .queue(style::PrintStyledContent(glyph.with(color).on(background)))?,
+        };
+        Ok(())
+    }
+
+    fn put(&mut self, x: u16, y: u16, cell: Option<Cell>) -> io::Result<()> {
+        match &mut self.screen {
+            Some(screen) => {
+                screen.drawn.push(((x, y), cell));
+                Ok(())
+            }
+            None => self.queue_cell(x, y, cell),
+        }
+    }
+}
+
+impl TerminalRenderer<Vec<u8>> {
+    /// Everything written since the last call, for recording rather than
+    /// showing.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.stdout)
+    }
+}
+
+impl Default for TerminalRenderer {
+    fn default() -> TerminalRenderer {
+        TerminalRenderer::new(io::stdout())
+    }
+}
+
+impl<W: Write> Renderer for TerminalRenderer<W> {
+    fn draw_cell(&mut self, x: u16, y: u16, glyph: char, color: Color) -> io::Result<()> {
+        self.put(x, y, Some((glyph, color, Color::Reset)))
+    }
+
+    fn draw_cell_on(
+        &mut self,
+        x: u16,
+        y: u16,
+        glyph: char,
+        color: Color,
+        background: Color,
+    ) -> io::Result<()> {
+        self.put(x, y, Some((glyph, color, background)))
+    }
+
+    fn clear_cell(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.put(x, y, None)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        if let Some(screen) = &mut self.screen {
+            screen.shown.clear();
+            screen.drawn.clear();
+        }
+        self.stdout
+            .queue(terminal::Clear(terminal::ClearType::All))?;
+        Ok(())
+    }
+
+    fn present(&mut self) -> io::Result<()> {
+        if let Some(mut screen) = self.screen.take() {
+            for ((x, y), cell) in screen.drawn.drain(..) {
+                let shown = screen.shown.get(&(x, y)).copied();
+                if shown == cell {
+                    continue;
+                }
+                match cell {
+                    Some(cell) => screen.shown.insert((x, y), cell),
+                    None => screen.shown.remove(&(x, y)),
+                };
+                self.queue_cell(x, y, cell)?;
+            }
+            self.screen = Some(screen);
+        }
+        self.stdout.flush()
+    }
+
+    fn bell(&mut self) -> io::Result<()> {
+        self.stdout.queue(style::Print('\x07'))?;
+        Ok(())
+    }
+
+    fn draw_text(&mut self, x: u16, y: u16, text: &str, color: Color) -> io::Result<()> {
+        if self.screen.is_some() {
+            for (i, glyph) in text.chars().enumerate() {
+                self.draw_cell(x + i as u16, y, glyph, color)?;
+            }
+            return Ok(());
+        }
+        self.stdout.queue(cursor::MoveTo(x, y))?;
+        if color == Color::Reset {
+            self.stdout.queue(style::Print(text))?;
+        } else {
+            self.stdout
+                .queue(style::PrintStyledContent(text.with(color)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders into an in-memory grid, for snapshot tests and anything else
+/// that wants to look at a frame without a terminal.
+///
+/// Writes outside the grid are silently dropped.
+pub struct HeadlessRenderer {
+    width: u16,
+    height: u16,
+    back: Vec<Option<(char, Color)>>,
+    front: Vec<Option<(char, Color)>>,
+    frames: usize,
+}
+
+impl HeadlessRenderer {
+    pub fn new(width: u16, height: u16) -> HeadlessRenderer {
+        let size = width as usize * height as usize;
+        HeadlessRenderer {
+            width,
+            height,
+            back: vec![None; size],
+            front: vec![None; size],
+            frames: 0,
+        }
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The presented glyph and color at column `x`, row `y`, if any.
+    pub fn cell(&self, x: u16, y: u16) -> Option<(char, Color)> {
+        self.index(x, y).and_then(|i| self.front[i])
+    }
+
+    /// How many times `present` has been called.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// The presented frame as plain text, one line per row, colors dropped.
+    pub fn snapshot(&self) -> String {
+        let mut out = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.push(self.cell(x, y).map_or(' ', |(glyph, _)| glyph));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Renderer for HeadlessRenderer {
+    fn draw_cell(&mut self, x: u16, y: u16, glyph: char, color: Color) -> io::Result<()> {
+        if let Some(i) = self.index(x, y) {
+            self.back[i] = Some((glyph, color));
+        }
+        Ok(())
+    }
+
+    fn clear_cell(&mut self, x: u16, y: u16) -> io::Result<()> {
+        if let Some(i) = self.index(x, y) {
+            self.back[i] = None;
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.back.fill(None);
+        Ok(())
+    }
+
+    fn present(&mut self) -> io::Result<()> {
+        self.front.clone_from(&self.back);
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// `text` broken between words into lines no longer than `width`, or
+/// cut where there's no space to break at.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
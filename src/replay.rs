@@ -0,0 +1,215 @@
+//! Runs saved with `--json-results`, played back turn for turn on their
+//! seed: as a ghost to race, with `rake replay` on their own, or with
+//! `rake replay --export` into a file to share.
+//!
+//! An export is an asciinema (v2) recording for a `.cast` file and an
+//! animated GIF for a `.gif` one. Either way every tick is a frame lasting
+//! as long as the tick would have, at the speed in the settings since runs
+//! don't record theirs.
+
+use crate::config::GameConfig;
+use crate::export::Gif;
+use crate::game::{Game, Snapshot};
+use crate::lang::Lang;
+use crate::render::{HeadlessRenderer, Renderer, TerminalRenderer};
+use crate::results::{RunSummary, Turn};
+use crate::{RakeError, Result};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time;
+
+// how long a GIF holds on the last frame before it starts over
+const GIF_END: time::Duration = time::Duration::from_secs(2);
+
+/// Reads a run saved by `--json-results`.
+pub fn load(path: &Path) -> Result<RunSummary> {
+    let error = |message: String| RakeError::Replay {
+        path: path.into(),
+        message,
+    };
+    let json = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    serde_json::from_str(&json).map_err(|e| error(e.to_string()))
+}
+
+/// `config` changed to the board and seed `run` was played on, for
+/// playing it back by itself.
+pub fn config_for(run: &RunSummary, config: GameConfig) -> Result<GameConfig> {
+    let Some(seed) = run.seed else {
+        return Err(RakeError::Config(
+            "the run has no seed, so it can't be played back".into(),
+        ));
+    };
+    Ok(GameConfig {
+        width: run.width,
+        height: run.height,
+        wall_mode: run.mode,
+        scoring: run.scoring,
+        seed: Some(seed),
+        ..config
+    })
+}
+
+/// A run being played back.
+pub struct Playback {
+    game: Game,
+    // the game before the first tick, for seeking back
+    start: Snapshot,
+    turns: Vec<Turn>,
+    // where the next turn to make is in `turns`
+    next: usize,
+    // the run stops here even if the snake didn't die, when it was quit
+    ticks: u64,
+}
+
+impl Playback {
+    /// Plays `run` back on `config`, which has to be set up like the board
+    /// it was played on, seed and all (see `config_for`).
+    pub fn new(run: &RunSummary, config: GameConfig) -> Playback {
+        let game = Game::new(config);
+        Playback {
+            start: game.snapshot(),
+            game,
+            turns: run.turns.clone(),
+            next: 0,
+            ticks: run.ticks,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// What the HUD's written in.
+    pub fn set_lang(&mut self, lang: Arc<Lang>) {
+        self.game.set_lang(lang);
+    }
+
+    /// What the HUD says about the controls, like `Game::set_help`.
+    pub fn set_help(&mut self, help: Vec<String>) {
+        self.game.set_help(help);
+    }
+
+    /// The tick the run ends on.
+    pub fn end(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Whether the run has ended, one way or the other.
+    pub fn is_done(&self) -> bool {
+        self.game.is_over() || self.game.ticks() >= self.ticks
+    }
+
+    /// Moves on one step, making whatever turn the run made then. The
+    /// HUD's clock goes on by as long as the step would have taken.
+    pub fn tick(&mut self) {
+        if self.is_done() {
+            return;
+        }
+        // turns are stamped with the move they were made on
+        let mut input = None;
+        while let Some(turn) = self.turns.get(self.next)
+            && turn.tick <= self.game.ticks() + 1
+        {
+            input = Some(turn.direction);
+            self.next += 1;
+        }
+        let elapsed = self.game.elapsed() + self.game.tick_rate();
+        self.game.tick(input);
+        self.game.set_elapsed(elapsed);
+    }
+
+    /// Steps without drawing until the run is at tick `ticks`, for
+    /// picking up a saved game partway.
+    pub fn advance_to(&mut self, ticks: u64) {
+        while self.game.ticks() < ticks && !self.is_done() {
+            self.tick();
+        }
+    }
+
+    /// Goes to tick `ticks`, or as near as the run gets. Going back plays
+    /// it again from the start, which is quick enough for any run anyone
+    /// could sit through.
+    pub fn seek(&mut self, ticks: u64) -> Result<()> {
+        if ticks < self.game.ticks() {
+            self.game.rewind(self.start.clone())?;
+            self.next = 0;
+        }
+        self.advance_to(ticks);
+        Ok(())
+    }
+}
+
+/// Plays the rest of `playback` into `path`, as whatever its extension
+/// asks for.
+pub fn export(path: &Path, playback: &mut Playback) -> Result<()> {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    let bytes = match extension.map(str::to_ascii_lowercase).as_deref() {
+        Some("cast") => cast(playback)?.into_bytes(),
+        Some("gif") => gif(playback)?,
+        _ => {
+            return Err(RakeError::Config(format!(
+                "can only export to a .cast or .gif file, not {}",
+                path.display()
+            )));
+        }
+    };
+    crate::storage::write_atomic(path, &bytes).map_err(|source| RakeError::Write {
+        path: path.into(),
+        source,
+    })
+}
+
+/// The rest of `playback` as an asciinema (v2) recording: exactly what
+/// playing it back in a terminal would have written out.
+pub fn cast(playback: &mut Playback) -> Result<String> {
+    let game = &playback.game;
+    let header = serde_json::json!({
+        "version": 2,
+        "width": game.width(),
+        "height": game.height() + 1,
+    });
+    let mut out = header.to_string();
+    let mut renderer = TerminalRenderer::new(Vec::new());
+    // and no cursor blinking away wherever the last cell was drawn
+    let mut screen = b"\x1b[?25l".to_vec();
+    renderer.clear()?;
+    game.draw_border(&mut renderer)?;
+    let mut at = time::Duration::ZERO;
+    loop {
+        playback.game.draw(&mut renderer)?;
+        renderer.present()?;
+        screen.extend(renderer.take_output());
+        let event = (at.as_secs_f64(), "o", String::from_utf8_lossy(&screen));
+        out.push('\n');
+        out.push_str(&serde_json::to_string(&event).expect("events serialize"));
+        screen.clear();
+        if playback.is_done() {
+            break;
+        }
+        at += playback.game.tick_rate();
+        playback.tick();
+    }
+    out.push('\n');
+    Ok(out)
+}
+
+/// The rest of `playback` as an animated GIF, each frame drawn like
+/// `--snapshot` draws a PNG.
+pub fn gif(playback: &mut Playback) -> Result<Vec<u8>> {
+    let game = &playback.game;
+    let mut board = HeadlessRenderer::new(game.width(), game.height() + 1);
+    let mut gif = Gif::new(game.width(), game.height());
+    game.draw_border(&mut board)?;
+    loop {
+        playback.game.draw(&mut board)?;
+        board.present()?;
+        if playback.is_done() {
+            gif.frame(&board, GIF_END);
+            break;
+        }
+        gif.frame(&board, playback.game.tick_rate());
+        playback.tick();
+    }
+    Ok(gif.finish())
+}
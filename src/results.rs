@@ -0,0 +1,210 @@
+//! Machine-readable summaries of finished runs, for wrappers, stats and
+//! tournaments.
+
+use crate::clock::SPLIT_APPLES;
+use crate::config::{Scoring, WallMode};
+use crate::game::{DeathCause, Direction, Game, GameEvent};
+use crate::grade::{Grade, Grades};
+use crate::grid::Coord;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time;
+
+/// How a run went, written out by `--json-results`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub score: u16,
+    pub length: usize,
+    pub ticks: u64,
+    /// Wall clock time from the first tick to the end, leaving out pauses
+    /// and counting the time before a saved game was carried on.
+    pub duration_ms: u64,
+    pub seed: Option<u64>,
+    pub mode: WallMode,
+    /// Older runs were all scored flat.
+    #[serde(default)]
+    pub scoring: Scoring,
+    pub width: u16,
+    pub height: u16,
+    /// `None` if the run was quit or won rather than lost.
+    pub death: Option<DeathCause>,
+    /// Whether the snake filled the board.
+    #[serde(default)]
+    pub won: bool,
+    /// Left out of runs from before they were graded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grade: Option<Grade>,
+    pub apples: Vec<AppleEaten>,
+    /// Every change of direction, which together with the seed is enough
+    /// to play the run again.
+    pub turns: Vec<Turn>,
+}
+
+impl RunSummary {
+    /// A fingerprint of the board and every move made on it. Anyone with
+    /// the seed and the turns can replay the run and check they get the
+    /// same score and the same hash.
+    pub fn input_hash(&self) -> u64 {
+        // FNV-1a: tiny, stable across platforms and versions, which is all
+        // that's needed here
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+        feed(&self.seed.unwrap_or(0).to_le_bytes());
+        feed(&self.width.to_le_bytes());
+        feed(&self.height.to_le_bytes());
+        feed(&[self.mode as u8]);
+        for turn in &self.turns {
+            feed(&turn.tick.to_le_bytes());
+            feed(&[turn.direction as u8]);
+        }
+        hash
+    }
+
+    /// The time at every tenth apple, in milliseconds, for `--speedrun`.
+    pub fn splits(&self) -> impl Iterator<Item = u64> + '_ {
+        splits(&self.apples)
+    }
+}
+
+/// Totals over every solo run played before the program exits, for the
+/// summary it prints on the way out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Session {
+    pub games: u32,
+    pub best: u16,
+    pub apples: usize,
+    pub duration_ms: u64,
+}
+
+impl Session {
+    /// Adds a finished run, unless it was quit before it got going.
+    pub fn record(&mut self, summary: &RunSummary) {
+        if summary.ticks == 0 {
+            return;
+        }
+        self.games += 1;
+        self.best = self.best.max(summary.score);
+        self.apples += summary.apples.len();
+        self.duration_ms += summary.duration_ms;
+    }
+}
+
+/// When and where one apple went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppleEaten {
+    pub tick: u64,
+    /// Since the start of the run.
+    pub ms: u64,
+    pub at: Coord,
+}
+
+/// The snake set off in `direction` on move number `tick`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Turn {
+    pub tick: u64,
+    pub direction: Direction,
+}
+
+/// Watches a game tick by tick to build its `RunSummary`.
+#[derive(Clone, Debug)]
+pub struct RunRecorder {
+    start: time::Instant,
+    // played before `start`, when it's a saved game carried on
+    before: time::Duration,
+    apples: Vec<AppleEaten>,
+    turns: Vec<Turn>,
+    death: Option<DeathCause>,
+    won: bool,
+}
+
+impl RunRecorder {
+    pub fn new(start: time::Instant) -> RunRecorder {
+        RunRecorder::resume(start, time::Duration::ZERO)
+    }
+
+    /// For a saved game that had already been going for `before`.
+    pub fn resume(start: time::Instant, before: time::Duration) -> RunRecorder {
+        RunRecorder {
+            start,
+            before,
+            apples: vec![],
+            turns: vec![],
+            death: None,
+            won: false,
+        }
+    }
+
+    /// How long the run has been going at `now`, not counting pauses.
+    pub fn elapsed(&self, now: time::Instant) -> time::Duration {
+        now.saturating_duration_since(self.start) + self.before
+    }
+
+    /// Stops the clock for `paused`, say while the terminal was suspended.
+    pub fn pause(&mut self, paused: time::Duration) {
+        self.start += paused;
+    }
+
+    /// The time at every tenth apple so far, in milliseconds.
+    pub fn splits(&self) -> impl Iterator<Item = u64> + '_ {
+        splits(&self.apples)
+    }
+
+    /// Notes down whatever happened in the tick `game` just ran.
+    pub fn observe<R: Rng>(&mut self, game: &Game<R>, now: time::Instant) {
+        let ms = millis(self.elapsed(now));
+        for event in game.events() {
+            match *event {
+                GameEvent::AppleEaten { at } => self.apples.push(AppleEaten {
+                    tick: game.ticks(),
+                    ms,
+                    at,
+                }),
+                GameEvent::Turned { direction } => self.turns.push(Turn {
+                    tick: game.ticks(),
+                    direction,
+                }),
+                GameEvent::Died { cause } => self.death = Some(cause),
+                GameEvent::Won => self.won = true,
+                _ => {}
+            }
+        }
+    }
+
+    /// The summary, with the run graded by `grades`.
+    pub fn finish<R: Rng>(self, game: &Game<R>, now: time::Instant, grades: &Grades) -> RunSummary {
+        let config = game.config();
+        RunSummary {
+            score: game.score(),
+            length: game.snake().len(),
+            ticks: game.ticks(),
+            duration_ms: millis(self.elapsed(now)),
+            seed: config.seed,
+            mode: config.wall_mode,
+            scoring: config.scoring,
+            width: config.width,
+            height: config.height,
+            death: self.death,
+            won: self.won,
+            grade: Some(grades.grade(game.score(), config)),
+            apples: self.apples,
+            turns: self.turns,
+        }
+    }
+}
+
+fn splits(apples: &[AppleEaten]) -> impl Iterator<Item = u64> + '_ {
+    apples
+        .iter()
+        .skip(SPLIT_APPLES - 1)
+        .step_by(SPLIT_APPLES)
+        .map(|apple| apple.ms)
+}
+
+fn millis(duration: time::Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
@@ -0,0 +1,41 @@
+//! Taking moves back, for practice.
+
+use crate::game::{Game, Snapshot};
+use std::collections::VecDeque;
+
+/// The last few ticks of a game, oldest first, to step back through.
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    capacity: usize,
+}
+
+impl History {
+    /// Keeps up to `capacity` ticks, forgetting the oldest past that.
+    pub fn new(capacity: usize) -> History {
+        History {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Remembers `game` as it is now. Call it once at the start and after
+    /// every tick.
+    pub fn record(&mut self, game: &Game) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(game.snapshot());
+    }
+
+    /// The game `ticks` ticks back from the latest `record`, or as far back
+    /// as there is. Everything after it is forgotten, so rewinding again
+    /// goes further. `None` if there's nothing before the latest.
+    pub fn rewind(&mut self, ticks: usize) -> Option<Snapshot> {
+        if self.snapshots.len() < 2 {
+            return None;
+        }
+        let keep = self.snapshots.len().saturating_sub(ticks).max(1);
+        self.snapshots.truncate(keep);
+        self.snapshots.back().cloned()
+    }
+}
@@ -0,0 +1,216 @@
+//! The game as a reinforcement learning environment, gym style: `reset`
+//! for a new episode and `step` to make a move, getting back what the
+//! board looks like, a reward and whether the episode's over.
+//!
+//! The rules are the game's own. What earns a reward, and how much, is
+//! up to `Rewards`; by default it's a point per point scored and a point
+//! off for dying, and nothing else.
+
+use crate::config::GameConfig;
+use crate::game::{Direction, Game};
+use crate::grid::Coord;
+use crate::planes::Planes;
+
+/// What an agent gets to see after every step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Observation {
+    /// One byte per cell, row by row from the top left: 0 empty, 1 wall,
+    /// 2 snake, 3 apple, like wasm plugins see it.
+    pub board: Vec<u8>,
+    /// The same board split into walls, body, head and food, which is
+    /// usually handier for a network to take in.
+    pub planes: Planes,
+    pub width: u16,
+    pub height: u16,
+    pub head: Coord,
+    pub direction: Direction,
+    pub score: u16,
+    pub length: usize,
+}
+
+/// How much each thing that happens is worth, for shaping what an agent
+/// learns to go after.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rewards {
+    /// For each point scored, which is one an apple unless the foods or
+    /// scoring rule say otherwise, and taken off for each point lost.
+    pub point: f32,
+    /// For dying, or for running out of steps.
+    pub death: f32,
+    /// For filling the board.
+    pub win: f32,
+    /// For every step, say a little below zero to hurry it along.
+    pub step: f32,
+    /// For each cell the head got closer to the nearest apple, and taken
+    /// off for each cell further away.
+    pub closer: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Rewards {
+        Rewards {
+            point: 1.0,
+            death: -1.0,
+            win: 1.0,
+            step: 0.0,
+            closer: 0.0,
+        }
+    }
+}
+
+/// A game played a step at a time, for an agent to learn on.
+pub struct RlEnv {
+    config: GameConfig,
+    rewards: Rewards,
+    // episodes that are still going after this many steps end there
+    max_steps: Option<u64>,
+    game: Game,
+    steps: u64,
+}
+
+impl RlEnv {
+    /// Episodes on `config`'s board. With a seed in `config` every episode
+    /// starts the same, which `reset_with_seed` can change; without one
+    /// each picks its own.
+    pub fn new(config: GameConfig, rewards: Rewards) -> RlEnv {
+        RlEnv {
+            game: Game::new(config.clone()),
+            config,
+            rewards,
+            max_steps: None,
+            steps: 0,
+        }
+    }
+
+    /// Ends episodes after `steps` steps, as deaths by timing out, so an
+    /// agent that's learned to go round in circles doesn't go on forever.
+    pub fn with_max_steps(mut self, steps: u64) -> RlEnv {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// The game the current episode's being played on.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn rewards(&self) -> Rewards {
+        self.rewards
+    }
+
+    /// Starts a new episode.
+    pub fn reset(&mut self) -> Observation {
+        self.start(self.config.clone())
+    }
+
+    /// Starts a new episode on `seed`, whatever the config says.
+    pub fn reset_with_seed(&mut self, seed: u64) -> Observation {
+        self.start(GameConfig {
+            seed: Some(seed),
+            ..self.config.clone()
+        })
+    }
+
+    /// Moves the snake a step, turning it to `action` first if that's
+    /// `Some`. Once the episode's over, steps do nothing and are worth
+    /// nothing.
+    pub fn step(&mut self, action: Option<Direction>) -> (Observation, f32, bool) {
+        if self.is_done() {
+            return (self.observe(), 0.0, true);
+        }
+        let before = (self.game.score(), self.apple_distance());
+        let tick = self.game.tick(action);
+        self.steps += 1;
+        if self.out_of_steps() {
+            self.game.time_out();
+        }
+
+        let rewards = &self.rewards;
+        let mut reward = rewards.step;
+        // an apple eaten out of order takes points off
+        let scored = i32::from(self.game.score()) - i32::from(before.0);
+        reward += rewards.point * scored as f32;
+        if self.game.death().is_some() {
+            reward += rewards.death;
+        } else if tick.won {
+            reward += rewards.win;
+        } else if let (Some(was), Some(is)) = (before.1, self.apple_distance())
+            && !tick.ate
+        {
+            reward += rewards.closer * (was as f32 - is as f32);
+        }
+        (self.observe(), reward, self.is_done())
+    }
+
+    /// What the board looks like now.
+    pub fn observe(&self) -> Observation {
+        let game = &self.game;
+        Observation {
+            board: game.grid().tiles().iter().map(|tile| *tile as u8).collect(),
+            planes: Planes::of(game),
+            width: game.width(),
+            height: game.height(),
+            head: game.snake().head(),
+            direction: game.snake().direction(),
+            score: game.score(),
+            length: game.snake().len(),
+        }
+    }
+
+    /// Whether the episode's over, one way or another.
+    pub fn is_done(&self) -> bool {
+        self.game.is_over()
+    }
+
+    fn start(&mut self, config: GameConfig) -> Observation {
+        self.game = Game::new(config);
+        self.steps = 0;
+        self.observe()
+    }
+
+    fn out_of_steps(&self) -> bool {
+        self.max_steps.is_some_and(|max| self.steps >= max)
+    }
+
+    // how many cells it is from the head to the nearest apple, straight
+    // across the board
+    fn apple_distance(&self) -> Option<u16> {
+        let head = self.game.snake().head();
+        self.game
+            .targets()
+            .map(|apple| {
+                let [x, y] = apple.position();
+                head[0].abs_diff(x) + head[1].abs_diff(y)
+            })
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SpawnPattern;
+
+    #[test]
+    fn an_apple_out_of_order_is_a_penalty() {
+        // the first apple right in front of the snake, and the third just
+        // after it, with the second out of the way
+        let mut config = GameConfig::builder()
+            .size(12, 8)
+            .ordered(true)
+            .seed(1)
+            .build()
+            .unwrap();
+        config.spawn_pattern = SpawnPattern::Sequence;
+        config.apple_cells = vec![[5, 4], [8, 1], [6, 4], [8, 2], [8, 3]];
+        let mut env = RlEnv::new(config, Rewards::default());
+        env.reset();
+
+        let (_, reward, _) = env.step(None);
+        assert!(reward > 0.0);
+        let (observation, reward, done) = env.step(None);
+        assert!(reward < 0.0, "got {reward}");
+        assert_eq!(observation.score, 0);
+        assert!(!done);
+    }
+}
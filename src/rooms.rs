@@ -0,0 +1,208 @@
+//! Rooms and corridors, Nibbles style: stages of rooms walled off from
+//! each other and joined up by corridors a cell wide, laid out from the
+//! game's own random numbers so a seed always plays the same stages.
+//!
+//! Apples only turn up inside rooms that still have some of their quota
+//! left to eat. Once every room's quota is eaten the next stage is laid
+//! out, the snake starts over in its first room, and each room wants one
+//! apple more than last time.
+
+use crate::game::Direction;
+use crate::grid::{Coord, Grid};
+use crate::level::{SPAWN_LENGTH, Spawn};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+// roughly how much of the board each room gets, the wall round it included
+const SECTOR: [u16; 2] = [12, 6];
+// no room is smaller, so the snake starts out in the first with a few
+// cells to go before it has to turn
+const MIN_ROOM: [u16; 2] = [SPAWN_LENGTH as u16 + 3, 3];
+// apples per room on the first stage, and the most a room ever wants
+const FIRST_QUOTA: u16 = 2;
+const MAX_QUOTA: u16 = 6;
+
+/// A room, and how it's getting on with its quota.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Room {
+    /// The top left corner.
+    pub at: Coord,
+    /// `[width, height]` in cells.
+    pub size: [u16; 2],
+    /// How many apples have to be eaten in here.
+    pub quota: u16,
+    pub eaten: u16,
+}
+
+impl Room {
+    pub fn contains(&self, [x, y]: Coord) -> bool {
+        let [width, height] = self.size.map(|side| side as i16);
+        x >= self.at[0] && y >= self.at[1] && x < self.at[0] + width && y < self.at[1] + height
+    }
+
+    /// Every cell in the room, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = Coord> {
+        let [x, y] = self.at;
+        let [width, height] = self.size.map(|side| side as i16);
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| [x, y]))
+    }
+
+    /// Whether its quota's been eaten.
+    pub fn is_done(&self) -> bool {
+        self.eaten >= self.quota
+    }
+
+    fn center(&self) -> Coord {
+        let [width, height] = self.size.map(|side| side as i16);
+        [self.at[0] + width / 2, self.at[1] + height / 2]
+    }
+}
+
+/// One stage's layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stage {
+    /// Every cell inside the border that isn't a room or a corridor.
+    pub walls: Vec<Coord>,
+    pub rooms: Vec<Room>,
+    /// In the first room, heading right.
+    pub spawn: Spawn,
+}
+
+/// Lays out stage `stage`, counting from one, on a `width` by `height`
+/// board. The board is split into a grid of sectors with a room in each,
+/// and the rooms are joined up like a maze, with the odd extra corridor
+/// making a loop, so every room can be got to from every other.
+pub fn generate(width: u16, height: u16, stage: u32, rng: &mut impl Rng) -> Stage {
+    let inside = [width - 2, height - 2];
+    let [columns, rows] = [0, 1].map(|axis| (inside[axis] / SECTOR[axis]).max(1));
+    let quota = (FIRST_QUOTA as u32 + stage.saturating_sub(1)).min(MAX_QUOTA as u32) as u16;
+
+    let mut rooms = vec![];
+    for row in 0..rows {
+        for column in 0..columns {
+            // where the sector starts and how big it is, along each axis
+            let sector = [(column, columns), (row, rows)]
+                .into_iter()
+                .zip(inside)
+                .map(|((n, of), inside)| {
+                    let start = n * inside / of;
+                    (start, (n + 1) * inside / of - start)
+                });
+            let mut at = [0; 2];
+            let mut size = [0; 2];
+            for (axis, (start, span)) in sector.enumerate() {
+                // a cell of wall after it, unless that'd leave no room
+                let most = if span > MIN_ROOM[axis] {
+                    span - 1
+                } else {
+                    span
+                };
+                let side = rng.random_range(MIN_ROOM[axis].min(most)..=most);
+                at[axis] = 1 + (start + rng.random_range(0..=most - side)) as i16;
+                size[axis] = side;
+            }
+            rooms.push(Room {
+                at,
+                size,
+                quota,
+                eaten: 0,
+            });
+        }
+    }
+
+    let (across, down) = (width as usize, height as usize);
+    let mut open = vec![false; across * down];
+    let mut carve = |[x, y]: Coord| open[y as usize * across + x as usize] = true;
+    for room in &rooms {
+        room.cells().for_each(&mut carve);
+    }
+    for (from, to) in links(columns as usize, rows as usize, rng) {
+        let ([x1, y1], [x2, y2]) = (rooms[from].center(), rooms[to].center());
+        // along, then up or down
+        for x in x1.min(x2)..=x1.max(x2) {
+            carve([x, y1]);
+        }
+        for y in y1.min(y2)..=y1.max(y2) {
+            carve([x2, y]);
+        }
+    }
+    let walls = (1..height as i16 - 1)
+        .flat_map(|y| (1..width as i16 - 1).map(move |x| [x, y]))
+        .filter(|[x, y]| !open[*y as usize * across + *x as usize])
+        .collect();
+
+    let first = rooms[0];
+    let spawn = Spawn {
+        at: [
+            first.at[0] + SPAWN_LENGTH as i16 - 1,
+            first.at[1] + first.size[1] as i16 / 2,
+        ],
+        direction: Direction::Right,
+    };
+    Stage {
+        walls,
+        rooms,
+        spawn,
+    }
+}
+
+/// An empty cell picked at random from the rooms that still want apples,
+/// or `None` if there's nowhere like that.
+pub fn random_free(rooms: &[Room], grid: &Grid, rng: &mut impl Rng) -> Option<Coord> {
+    let free = || {
+        rooms
+            .iter()
+            .filter(|room| !room.is_done())
+            .flat_map(Room::cells)
+            .filter(|cell| grid.is_empty(*cell))
+    };
+    let count = free().count();
+    if count == 0 {
+        return None;
+    }
+    free().nth(rng.random_range(0..count))
+}
+
+// which sectors get a corridor between them: a random spanning tree of
+// the grid of them, so everything's joined up, and a few more besides
+fn links(columns: usize, rows: usize, rng: &mut impl Rng) -> Vec<(usize, usize)> {
+    let neighbours = |n: usize| {
+        let (column, row) = (n % columns, n / columns);
+        [
+            (column > 0).then(|| n - 1),
+            (column + 1 < columns).then(|| n + 1),
+            (row > 0).then(|| n - columns),
+            (row + 1 < rows).then(|| n + columns),
+        ]
+        .into_iter()
+        .flatten()
+    };
+    let mut joined = vec![false; columns * rows];
+    let mut links = vec![];
+    let mut todo = vec![0];
+    joined[0] = true;
+    while let Some(&n) = todo.last() {
+        let mut next: Vec<usize> = neighbours(n).filter(|m| !joined[*m]).collect();
+        next.shuffle(rng);
+        match next.first() {
+            Some(&m) => {
+                joined[m] = true;
+                links.push((n, m));
+                todo.push(m);
+            }
+            None => {
+                todo.pop();
+            }
+        }
+    }
+    // a loop or two, so a room isn't always a dead end
+    for n in 0..columns * rows {
+        for m in neighbours(n).filter(|m| *m > n) {
+            if !links.contains(&(n, m)) && !links.contains(&(m, n)) && rng.random_ratio(1, 4) {
+                links.push((n, m));
+            }
+        }
+    }
+    links
+}
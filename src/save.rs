@@ -0,0 +1,44 @@
+//! A game in progress, kept on disk so it can be picked up on the next
+//! launch.
+
+use crate::game::Snapshot;
+use crate::storage;
+use crate::{RakeError, Result};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `save.json` in `storage::data_dir()`.
+pub fn default_path() -> Option<PathBuf> {
+    storage::data_dir().map(|dir| dir.join("save.json"))
+}
+
+/// The saved game at `path`, if there is one.
+pub fn load(path: &Path) -> Result<Option<Snapshot>> {
+    // snapshots carry their own version, so no envelope
+    match storage::read_optional(path) {
+        Ok(json) => json.as_deref().map(Snapshot::from_json).transpose(),
+        Err(source) => Err(RakeError::Save {
+            path: path.into(),
+            source,
+        }),
+    }
+}
+
+pub fn store(path: &Path, snapshot: &Snapshot) -> Result<()> {
+    storage::write_atomic(path, snapshot.to_json().as_bytes()).map_err(|source| RakeError::Save {
+        path: path.into(),
+        source,
+    })
+}
+
+/// Throws the saved game away. Fine if there wasn't one.
+pub fn discard(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(RakeError::Save {
+            path: path.into(),
+            source: e,
+        }),
+        _ => Ok(()),
+    }
+}
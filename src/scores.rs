@@ -0,0 +1,239 @@
+//! Scores that outlast a single run, kept in a JSON file in the platform
+//! data directory.
+
+use crate::Result;
+use crate::config::{GameConfig, Scoring, SpawnPattern, WallMode};
+use crate::grade::Grade;
+use crate::storage::{self, Versioned};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How many runs each leaderboard keeps.
+pub const TABLE_SIZE: usize = 10;
+
+/// One line on a leaderboard.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub initials: String,
+    pub score: u16,
+    /// How long the run took, left out of scores from before runs were
+    /// timed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ms: Option<u64>,
+    /// Left out of scores from before runs were graded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grade: Option<Grade>,
+}
+
+/// The best score and a top ten for every mode and board size that's been
+/// played.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    best: BTreeMap<String, u16>,
+    tables: BTreeMap<String, Vec<Entry>>,
+    // offered again next time to save typing
+    last_initials: Option<String>,
+}
+
+impl HighScores {
+    /// `scores.json` in `storage::data_dir()`.
+    pub fn default_path() -> Option<PathBuf> {
+        storage::data_dir().map(|dir| dir.join("scores.json"))
+    }
+
+    /// Reads `path`. A file that doesn't exist yet has no scores in it.
+    pub fn load(path: &Path) -> Result<HighScores> {
+        storage::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        storage::store(path, self)
+    }
+
+    /// The best score on boards like `config`'s, or 0 if there isn't one.
+    pub fn best(&self, config: &GameConfig) -> u16 {
+        self.best.get(&board_key(config)).copied().unwrap_or(0)
+    }
+
+    /// Keeps `score` if it beats the best for its board. Returns whether it
+    /// did.
+    pub fn record(&mut self, config: &GameConfig, score: u16) -> bool {
+        let best = self.best.entry(board_key(config)).or_insert(0);
+        if score > *best {
+            *best = score;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The leaderboard for boards like `config`'s, best first.
+    pub fn table(&self, config: &GameConfig) -> &[Entry] {
+        self.tables
+            .get(&board_key(config))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Every leaderboard with anything on it, keyed by `board_key`.
+    pub fn tables(&self) -> impl Iterator<Item = (&str, &[Entry])> {
+        self.tables
+            .iter()
+            .filter(|(_, table)| !table.is_empty())
+            .map(|(key, table)| (key.as_str(), table.as_slice()))
+    }
+
+    /// Whether `score` would make it onto the leaderboard for `config`.
+    pub fn qualifies(&self, config: &GameConfig, score: u16) -> bool {
+        let table = self.table(config);
+        score > 0 && (table.len() < TABLE_SIZE || table.iter().any(|entry| score > entry.score))
+    }
+
+    /// Puts a run that took `ms` and earned `grade` on the leaderboard for
+    /// `config`, returning its place counting from 0, or `None` if it
+    /// didn't make the cut. Ties go to whoever got there first.
+    pub fn add_entry(
+        &mut self,
+        config: &GameConfig,
+        initials: &str,
+        score: u16,
+        ms: u64,
+        grade: Grade,
+    ) -> Option<usize> {
+        if !self.qualifies(config, score) {
+            return None;
+        }
+        let table = self.tables.entry(board_key(config)).or_default();
+        let place = table
+            .iter()
+            .position(|entry| score > entry.score)
+            .unwrap_or(table.len());
+        table.insert(
+            place,
+            Entry {
+                initials: initials.into(),
+                score,
+                ms: Some(ms),
+                grade: Some(grade),
+            },
+        );
+        table.truncate(TABLE_SIZE);
+        self.last_initials = Some(initials.into());
+        Some(place)
+    }
+
+    /// Whatever was entered last time, to start the next entry with.
+    pub fn last_initials(&self) -> &str {
+        self.last_initials.as_deref().unwrap_or("")
+    }
+}
+
+impl Versioned for HighScores {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, data: Value) -> std::result::Result<Value, String> {
+        match version {
+            // the same shape, from before the file had a version
+            0 | 1 => Ok(data),
+            _ => Err(format!("don't know how to read version {version}")),
+        }
+    }
+}
+
+/// Which scores a game competes with, like `fatal 40x15`, or `wrap 60x20
+/// speed` for anything not scored flat, with `casual`, `rooms`, `bugs`,
+/// `ordered`, `decay` and `obstacles` on the end for those, then the spawn
+/// pattern if apples don't come out at random. Themes and seeds don't
+/// change how hard a board is, so they're left out.
+pub fn board_key(config: &GameConfig) -> String {
+    let mode = match config.wall_mode {
+        WallMode::Fatal => "fatal",
+        WallMode::Wrap => "wrap",
+        WallMode::Bounce => "bounce",
+    };
+    let mut key = format!("{mode} {}x{}", config.width, config.height);
+    if config.scoring != Scoring::Flat {
+        key.push(' ');
+        key.push_str(config.scoring.name());
+    }
+    if config.casual {
+        key.push_str(" casual");
+    }
+    if config.rooms {
+        key.push_str(" rooms");
+    }
+    if config.bugs {
+        key.push_str(" bugs");
+    }
+    if config.ordered {
+        key.push_str(" ordered");
+    }
+    if config.decay {
+        key.push_str(" decay");
+    }
+    if config.obstacles {
+        key.push_str(" obstacles");
+    }
+    if config.spawn_pattern != SpawnPattern::Random {
+        key.push(' ');
+        key.push_str(config.spawn_pattern.name());
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(scores: &mut HighScores, initials: &str, score: u16) -> Option<usize> {
+        let config = GameConfig::default();
+        scores.add_entry(&config, initials, score, 1000, Grade::C)
+    }
+
+    fn initials(scores: &HighScores) -> Vec<&str> {
+        let table = scores.table(&GameConfig::default());
+        table.iter().map(|entry| entry.initials.as_str()).collect()
+    }
+
+    #[test]
+    fn ties_go_to_whoever_got_there_first() {
+        let mut scores = HighScores::default();
+        assert_eq!(add(&mut scores, "AAA", 10), Some(0));
+        assert_eq!(add(&mut scores, "BBB", 10), Some(1));
+        assert_eq!(add(&mut scores, "CCC", 12), Some(0));
+        assert_eq!(add(&mut scores, "DDD", 10), Some(3));
+        assert_eq!(initials(&scores), ["CCC", "AAA", "BBB", "DDD"]);
+        assert_eq!(scores.last_initials(), "DDD");
+    }
+
+    #[test]
+    fn a_full_table_only_takes_a_better_score() {
+        let mut scores = HighScores::default();
+        assert_eq!(add(&mut scores, "NIL", 0), None);
+        for score in 1..=TABLE_SIZE as u16 {
+            add(&mut scores, "OLD", score);
+        }
+        // equal to the last isn't enough once it's full
+        assert_eq!(add(&mut scores, "TIE", 1), None);
+        assert_eq!(add(&mut scores, "NEW", 2), Some(TABLE_SIZE - 1));
+        let table = scores.table(&GameConfig::default());
+        assert_eq!(table.len(), TABLE_SIZE);
+        assert_eq!(table.last().map(|entry| entry.score), Some(2));
+        assert_eq!(initials(&scores).last(), Some(&"NEW"));
+    }
+
+    #[test]
+    fn each_board_keeps_its_own_scores() {
+        let mut scores = HighScores::default();
+        let wrap = GameConfig::builder()
+            .wall_mode(WallMode::Wrap)
+            .build()
+            .unwrap();
+        assert!(scores.record(&GameConfig::default(), 5));
+        assert!(!scores.record(&GameConfig::default(), 5));
+        assert_eq!(scores.best(&wrap), 0);
+        assert_eq!(board_key(&wrap), "wrap 40x15");
+    }
+}
@@ -0,0 +1,146 @@
+//! Lua hooks for custom rules, behind the `lua` feature.
+//!
+//! A script defines any of these globals and rake calls them after every
+//! tick:
+//!
+//! ```lua
+//! function on_tick(state) end          -- state.score, state.length, state.x, state.y, state.tick
+//! function on_eat(x, y) end            -- an apple at x, y was just eaten
+//! function on_spawn_food(x, y) end     -- an apple just appeared at x, y
+//! ```
+//!
+//! and can change the game through the `rake` table:
+//!
+//! ```lua
+//! rake.add_score(5)          -- negative takes points away
+//! rake.set_speed(80)         -- milliseconds per tick
+//! rake.spawn_apple(10, 4)    -- ignored if the cell isn't free
+//! ```
+
+use crate::config::Food;
+use crate::game::{Game, GameEvent};
+use crate::{RakeError, Result};
+use mlua::{Function, Lua, Table};
+use rand::Rng;
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::time;
+
+// what a script asked for during a hook. applied once the hook returns so
+// scripts never see the game half-changed
+enum Command {
+    AddScore(i32),
+    SetSpeed(u64),
+    SpawnApple(i16, i16),
+}
+
+pub struct Script {
+    lua: Lua,
+    commands: Rc<RefCell<Vec<Command>>>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Script> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| RakeError::Script(format!("couldn't read {}: {e}", path.display())))?;
+        Script::from_source(&source, &path.display().to_string())
+    }
+
+    /// `name` is only used in error messages.
+    pub fn from_source(source: &str, name: &str) -> Result<Script> {
+        let lua = Lua::new();
+        let commands = Rc::new(RefCell::new(vec![]));
+        register_api(&lua, &commands).map_err(script_error)?;
+        lua.load(source)
+            .set_name(name)
+            .exec()
+            .map_err(script_error)?;
+        Ok(Script { lua, commands })
+    }
+
+    /// Runs the hooks for whatever happened in the last tick and applies
+    /// anything they asked for.
+    pub fn after_tick<R: Rng>(&self, game: &mut Game<R>) -> Result<()> {
+        let globals = self.lua.globals();
+        for event in game.events() {
+            match *event {
+                GameEvent::AppleEaten { at } => call(&globals, "on_eat", (at[0], at[1]))?,
+                GameEvent::AppleSpawned { at } => call(&globals, "on_spawn_food", (at[0], at[1]))?,
+                _ => {}
+            }
+        }
+
+        let state = self.lua.create_table().map_err(script_error)?;
+        let head = game.snake().head();
+        state.set("score", game.score()).map_err(script_error)?;
+        state
+            .set("length", game.snake().len())
+            .map_err(script_error)?;
+        state.set("x", head[0]).map_err(script_error)?;
+        state.set("y", head[1]).map_err(script_error)?;
+        state.set("tick", game.ticks()).map_err(script_error)?;
+        call(&globals, "on_tick", state)?;
+
+        for command in self.commands.borrow_mut().drain(..) {
+            match command {
+                Command::AddScore(points) => game.add_score(points),
+                Command::SetSpeed(ms) => game.set_tick_rate(time::Duration::from_millis(ms)),
+                Command::SpawnApple(x, y) => {
+                    game.spawn_apple([x, y], Food::Cherry);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn register_api(lua: &Lua, commands: &Rc<RefCell<Vec<Command>>>) -> mlua::Result<()> {
+    let api = lua.create_table()?;
+
+    let queue = Rc::clone(commands);
+    api.set(
+        "add_score",
+        lua.create_function(move |_, points: i32| {
+            queue.borrow_mut().push(Command::AddScore(points));
+            Ok(())
+        })?,
+    )?;
+
+    let queue = Rc::clone(commands);
+    api.set(
+        "set_speed",
+        lua.create_function(move |_, ms: u64| {
+            if ms == 0 {
+                return Err(mlua::Error::runtime("speed must be above zero"));
+            }
+            queue.borrow_mut().push(Command::SetSpeed(ms));
+            Ok(())
+        })?,
+    )?;
+
+    let queue = Rc::clone(commands);
+    api.set(
+        "spawn_apple",
+        lua.create_function(move |_, (x, y): (i16, i16)| {
+            queue.borrow_mut().push(Command::SpawnApple(x, y));
+            Ok(())
+        })?,
+    )?;
+
+    lua.globals().set("rake", api)
+}
+
+// hooks are all optional, so a missing global is fine
+fn call(globals: &Table, name: &str, args: impl mlua::IntoLuaMulti) -> Result<()> {
+    let hook: Option<Function> = globals.get(name).map_err(script_error)?;
+    if let Some(hook) = hook {
+        hook.call::<()>(args).map_err(script_error)?;
+    }
+    Ok(())
+}
+
+fn script_error(e: mlua::Error) -> RakeError {
+    RakeError::Script(e.to_string())
+}
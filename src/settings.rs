@@ -0,0 +1,835 @@
+//! Player settings, read from a TOML file and layered under the command
+//! line.
+//!
+//! ```toml
+//! width = 60
+//! height = 20
+//! speed = 7
+//! theme = "ocean"
+//! mode = "wrap"
+//! scoring = "speed"
+//! color = false
+//! lang = "de"
+//! layout = "azerty"
+//!
+//! [keys]
+//! up = ["w", "Up"]
+//! down = ["s", "Down"]
+//!
+//! [food]
+//! melon = 0
+//!
+//! [profiles.hardcore]
+//! speed = 10
+//! mode = "fatal"
+//! ```
+//!
+//! Everything is optional. Anything left out falls back to the defaults,
+//! and a profile only changes what it sets.
+
+use crate::config::{self, Food, Foods, GameConfig, Scoring, SpawnPattern, WallMode};
+use crate::game::Direction;
+use crate::grade::Grades;
+use crate::input::{self, InputAction, Keymap, Layout};
+use crate::lang::Lang;
+use crate::skins::{self, Skin, Unlocks};
+use crate::sound::{self, Music, SoundConfig};
+use crate::storage;
+use crate::terminal;
+use crate::theme::Theme;
+use crate::{RakeError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time;
+use toml_edit::{Array, DocumentMut, Item, Table, Value};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+    /// 1 (slowest) to 10.
+    pub speed: Option<u8>,
+    /// Milliseconds per move, for going slower than speed 1. Wins over
+    /// `speed`.
+    pub tick_ms: Option<u64>,
+    /// Only move when a direction key is pressed.
+    pub turn_based: Option<bool>,
+    pub theme: Option<String>,
+    /// A snake skin from `skins::SKINS`, once it's been unlocked.
+    pub skin: Option<String>,
+    pub mode: Option<WallMode>,
+    /// `"flat"`, `"speed"`, `"length"` or `"time"`, see `Scoring`.
+    pub scoring: Option<Scoring>,
+    /// Where apples come out, see `SpawnPattern`.
+    pub spawn_pattern: Option<SpawnPattern>,
+    pub apples: Option<usize>,
+    /// Up and down at half speed, to make up for tall terminal cells.
+    pub slow_vertical: Option<bool>,
+    pub ascii: Option<bool>,
+    /// `false` draws everything in the terminal's default colors.
+    pub color: Option<bool>,
+    /// No crawling stripes, trail, banners or bell, for anyone bothered by
+    /// motion or flashing.
+    pub reduced_motion: Option<bool>,
+    /// Read the game out a line at a time instead of drawing the board.
+    pub screen_reader: Option<bool>,
+    /// The snake passes through itself, and only walls are fatal.
+    pub casual: Option<bool>,
+    /// Rooms joined up by corridors, a new stage of them each time every
+    /// room's had its apples.
+    pub rooms: Option<bool>,
+    /// A bonus bug out along the wall every few apples.
+    pub bugs: Option<bool>,
+    /// Numbered apples, five at a time, to eat in order.
+    pub ordered: Option<bool>,
+    /// Apples worth more the sooner they're eaten.
+    pub decay: Option<bool>,
+    /// A block that stays put every ten seconds.
+    pub obstacles: Option<bool>,
+    /// A timer to the tenth of a second, with a split every ten apples.
+    pub speedrun: Option<bool>,
+    /// The speed level and milliseconds a move on the HUD.
+    pub show_speed: Option<bool>,
+    /// The head half a cell on between ticks, for smoother movement.
+    pub smooth: Option<bool>,
+    /// Seconds without a key pressed before the game pauses itself. 0, or
+    /// leaving it out, never does.
+    pub idle_pause: Option<u64>,
+    /// ASCII, steady and only redrawing what's changed, for old Windows
+    /// consoles. Left out, it's on wherever one's detected.
+    pub legacy_console: Option<bool>,
+    /// Put the score card on the clipboard after every game.
+    pub copy_card: Option<bool>,
+    pub seed: Option<u64>,
+    /// `"en"`, the code of a translation in `Lang::dir`, or a path to one.
+    pub lang: Option<String>,
+    /// `"qwerty"`, `"azerty"`, `"dvorak"` or `"colemak"`, for which
+    /// letters the default controls turn with. `keys` still wins.
+    pub layout: Option<Layout>,
+    pub keys: KeySettings,
+    pub food: FoodSettings,
+    pub grades: GradeSettings,
+    pub leaderboard: LeaderboardSettings,
+    pub discord: DiscordSettings,
+    pub sound: SoundSettings,
+    /// Named sets of settings to pick from with `with_profile`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Settings>,
+}
+
+/// Keys for each action. Setting one replaces its default keys entirely.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeySettings {
+    pub up: Option<Vec<String>>,
+    pub down: Option<Vec<String>>,
+    pub left: Option<Vec<String>>,
+    pub right: Option<Vec<String>>,
+    pub save: Option<Vec<String>>,
+    pub quit: Option<Vec<String>>,
+    pub mute: Option<Vec<String>>,
+    pub rewind: Option<Vec<String>>,
+    pub hint: Option<Vec<String>>,
+    pub slow: Option<Vec<String>>,
+    pub help: Option<Vec<String>>,
+    pub pause: Option<Vec<String>>,
+}
+
+/// How often each food turns up, against the others. 0 for never.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FoodSettings {
+    pub cherry: Option<u32>,
+    pub banana: Option<u32>,
+    pub melon: Option<u32>,
+}
+
+/// The rating each grade starts at, see `grade`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GradeSettings {
+    pub s: Option<u32>,
+    pub a: Option<u32>,
+    pub b: Option<u32>,
+    pub c: Option<u32>,
+}
+
+/// Where to send finished runs, if anywhere. Nothing leaves the machine
+/// unless `url` is set (and rake was built with the `online` feature).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LeaderboardSettings {
+    pub url: Option<String>,
+    /// Shared with the server, to sign submissions with.
+    pub key: Option<String>,
+}
+
+/// Showing what's being played on Discord. Off unless `client_id` is set
+/// (and rake was built with the `discord` feature).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscordSettings {
+    /// The id of the Discord application to show up as.
+    pub client_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SoundSettings {
+    /// How loud effects are, 0 (silent) to 10.
+    pub volume: Option<u8>,
+    /// Whether the terminal bell stands in when there's no audio output.
+    pub bell: Option<bool>,
+    /// `"chiptune"`, or a file to loop in the background.
+    pub music: Option<String>,
+    /// 0 (silent) to 10.
+    pub music_volume: Option<u8>,
+}
+
+impl Settings {
+    /// Where the settings file lives: `$XDG_CONFIG_HOME/rake/config.toml`
+    /// if that's set, otherwise the platform's config directory
+    /// (`~/.config` on Linux, `~/Library/Application Support` on macOS,
+    /// `%APPDATA%` on Windows).
+    pub fn default_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(dirs::config_dir)?;
+        Some(base.join("rake").join("config.toml"))
+    }
+
+    /// Reads `path`. A file that doesn't exist is the same as an empty one.
+    pub fn load(path: &Path) -> Result<Settings> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Settings::default()),
+            Err(e) => {
+                return Err(RakeError::Config(format!(
+                    "couldn't read {}: {e}",
+                    path.display()
+                )));
+            }
+        };
+        Settings::from_toml(&text)
+            .map_err(|e| RakeError::Config(format!("{}: {e}", path.display())))
+    }
+
+    pub fn from_toml(text: &str) -> std::result::Result<Settings, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// Settings from `RAKE_WIDTH`, `RAKE_SPEED`, `RAKE_THEME` and so on, one
+    /// variable per top level setting. Empty variables are ignored.
+    pub fn from_env() -> Result<Settings> {
+        Settings::from_vars(|name| env::var(name).ok())
+    }
+
+    /// `from_env` reading from `var` instead of the real environment.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Settings> {
+        let get = |name: &str| var(name).filter(|value| !value.is_empty());
+        fn parse<T: FromStr>(name: &str, value: Option<String>) -> Result<Option<T>> {
+            value
+                .map(|value| {
+                    value.trim().parse().map_err(|_| {
+                        RakeError::Config(format!("{name} has a bad value: {value:?}"))
+                    })
+                })
+                .transpose()
+        }
+        let mode = match get("RAKE_MODE").as_deref() {
+            Some("fatal") => Some(WallMode::Fatal),
+            Some("wrap") => Some(WallMode::Wrap),
+            Some("bounce") => Some(WallMode::Bounce),
+            Some(other) => {
+                return Err(RakeError::Config(format!(
+                    "RAKE_MODE has a bad value: {other:?}, pick fatal, wrap or bounce"
+                )));
+            }
+            None => None,
+        };
+        Ok(Settings {
+            width: parse("RAKE_WIDTH", get("RAKE_WIDTH"))?,
+            height: parse("RAKE_HEIGHT", get("RAKE_HEIGHT"))?,
+            speed: parse("RAKE_SPEED", get("RAKE_SPEED"))?,
+            tick_ms: parse("RAKE_TICK_MS", get("RAKE_TICK_MS"))?,
+            turn_based: parse("RAKE_TURN_BASED", get("RAKE_TURN_BASED"))?,
+            theme: get("RAKE_THEME"),
+            skin: get("RAKE_SKIN"),
+            mode,
+            scoring: get("RAKE_SCORING")
+                .map(|name| name.trim().parse())
+                .transpose()?,
+            spawn_pattern: get("RAKE_SPAWN_PATTERN")
+                .map(|name| name.trim().parse())
+                .transpose()?,
+            apples: parse("RAKE_APPLES", get("RAKE_APPLES"))?,
+            slow_vertical: parse("RAKE_SLOW_VERTICAL", get("RAKE_SLOW_VERTICAL"))?,
+            ascii: parse("RAKE_ASCII", get("RAKE_ASCII"))?,
+            color: parse("RAKE_COLOR", get("RAKE_COLOR"))?,
+            reduced_motion: parse("RAKE_REDUCED_MOTION", get("RAKE_REDUCED_MOTION"))?,
+            screen_reader: parse("RAKE_SCREEN_READER", get("RAKE_SCREEN_READER"))?,
+            casual: parse("RAKE_CASUAL", get("RAKE_CASUAL"))?,
+            rooms: parse("RAKE_ROOMS", get("RAKE_ROOMS"))?,
+            bugs: parse("RAKE_BUGS", get("RAKE_BUGS"))?,
+            ordered: parse("RAKE_ORDERED", get("RAKE_ORDERED"))?,
+            decay: parse("RAKE_DECAY", get("RAKE_DECAY"))?,
+            obstacles: parse("RAKE_OBSTACLES", get("RAKE_OBSTACLES"))?,
+            speedrun: parse("RAKE_SPEEDRUN", get("RAKE_SPEEDRUN"))?,
+            show_speed: parse("RAKE_SHOW_SPEED", get("RAKE_SHOW_SPEED"))?,
+            smooth: parse("RAKE_SMOOTH", get("RAKE_SMOOTH"))?,
+            idle_pause: parse("RAKE_IDLE_PAUSE", get("RAKE_IDLE_PAUSE"))?,
+            legacy_console: parse("RAKE_LEGACY_CONSOLE", get("RAKE_LEGACY_CONSOLE"))?,
+            copy_card: parse("RAKE_COPY_CARD", get("RAKE_COPY_CARD"))?,
+            seed: parse("RAKE_SEED", get("RAKE_SEED"))?,
+            lang: get("RAKE_LANG"),
+            layout: get("RAKE_LAYOUT")
+                .map(|name| name.trim().parse())
+                .transpose()?,
+            ..Settings::default()
+        })
+    }
+
+    /// `self` with anything set in `over` taking its place.
+    pub fn merge(self, over: Settings) -> Settings {
+        Settings {
+            width: over.width.or(self.width),
+            height: over.height.or(self.height),
+            speed: over.speed.or(self.speed),
+            tick_ms: over.tick_ms.or(self.tick_ms),
+            turn_based: over.turn_based.or(self.turn_based),
+            theme: over.theme.or(self.theme),
+            skin: over.skin.or(self.skin),
+            mode: over.mode.or(self.mode),
+            scoring: over.scoring.or(self.scoring),
+            spawn_pattern: over.spawn_pattern.or(self.spawn_pattern),
+            apples: over.apples.or(self.apples),
+            slow_vertical: over.slow_vertical.or(self.slow_vertical),
+            ascii: over.ascii.or(self.ascii),
+            color: over.color.or(self.color),
+            reduced_motion: over.reduced_motion.or(self.reduced_motion),
+            screen_reader: over.screen_reader.or(self.screen_reader),
+            casual: over.casual.or(self.casual),
+            rooms: over.rooms.or(self.rooms),
+            bugs: over.bugs.or(self.bugs),
+            ordered: over.ordered.or(self.ordered),
+            decay: over.decay.or(self.decay),
+            obstacles: over.obstacles.or(self.obstacles),
+            speedrun: over.speedrun.or(self.speedrun),
+            show_speed: over.show_speed.or(self.show_speed),
+            smooth: over.smooth.or(self.smooth),
+            idle_pause: over.idle_pause.or(self.idle_pause),
+            legacy_console: over.legacy_console.or(self.legacy_console),
+            copy_card: over.copy_card.or(self.copy_card),
+            seed: over.seed.or(self.seed),
+            lang: over.lang.or(self.lang),
+            layout: over.layout.or(self.layout),
+            keys: KeySettings {
+                up: over.keys.up.or(self.keys.up),
+                down: over.keys.down.or(self.keys.down),
+                left: over.keys.left.or(self.keys.left),
+                right: over.keys.right.or(self.keys.right),
+                save: over.keys.save.or(self.keys.save),
+                quit: over.keys.quit.or(self.keys.quit),
+                mute: over.keys.mute.or(self.keys.mute),
+                rewind: over.keys.rewind.or(self.keys.rewind),
+                hint: over.keys.hint.or(self.keys.hint),
+                slow: over.keys.slow.or(self.keys.slow),
+                help: over.keys.help.or(self.keys.help),
+                pause: over.keys.pause.or(self.keys.pause),
+            },
+            food: FoodSettings {
+                cherry: over.food.cherry.or(self.food.cherry),
+                banana: over.food.banana.or(self.food.banana),
+                melon: over.food.melon.or(self.food.melon),
+            },
+            grades: GradeSettings {
+                s: over.grades.s.or(self.grades.s),
+                a: over.grades.a.or(self.grades.a),
+                b: over.grades.b.or(self.grades.b),
+                c: over.grades.c.or(self.grades.c),
+            },
+            leaderboard: LeaderboardSettings {
+                url: over.leaderboard.url.or(self.leaderboard.url),
+                key: over.leaderboard.key.or(self.leaderboard.key),
+            },
+            discord: DiscordSettings {
+                client_id: over.discord.client_id.or(self.discord.client_id),
+            },
+            sound: SoundSettings {
+                volume: over.sound.volume.or(self.sound.volume),
+                bell: over.sound.bell.or(self.sound.bell),
+                music: over.sound.music.or(self.sound.music),
+                music_volume: over.sound.music_volume.or(self.sound.music_volume),
+            },
+            profiles: {
+                let mut profiles = self.profiles;
+                profiles.extend(over.profiles);
+                profiles
+            },
+        }
+    }
+
+    /// These settings with profile `name` on top.
+    pub fn with_profile(mut self, name: &str) -> Result<Settings> {
+        let Some(profile) = self.profiles.remove(name) else {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            return Err(RakeError::Config(if known.is_empty() {
+                format!("no profile called {name:?}, none are set up")
+            } else {
+                format!(
+                    "no profile called {name:?}, pick one of {}",
+                    known.join(", ")
+                )
+            }));
+        };
+        if !profile.profiles.is_empty() {
+            return Err(RakeError::Config(format!(
+                "profile {name:?} can't have profiles of its own"
+            )));
+        }
+        Ok(self.merge(profile))
+    }
+
+    pub fn game_config(&self) -> Result<GameConfig> {
+        let mut builder = GameConfig::builder();
+        if let Some(width) = self.width {
+            builder = builder.width(width);
+        }
+        if let Some(height) = self.height {
+            builder = builder.height(height);
+        }
+        if let Some(speed) = self.speed {
+            builder = builder.tick_rate(config::speed_level(speed)?);
+        }
+        if let Some(ms) = self.tick_ms {
+            builder = builder.tick_rate(time::Duration::from_millis(ms));
+        }
+        if let Some(mode) = self.mode {
+            builder = builder.wall_mode(mode);
+        }
+        if let Some(scoring) = self.scoring {
+            builder = builder.scoring(scoring);
+        }
+        if let Some(pattern) = self.spawn_pattern {
+            builder = builder.spawn_pattern(pattern);
+        }
+        if let Some(apples) = self.apples {
+            builder = builder.apples(apples);
+        }
+        if let Some(casual) = self.casual {
+            builder = builder.casual(casual);
+        }
+        if let Some(rooms) = self.rooms {
+            builder = builder.rooms(rooms);
+        }
+        if let Some(bugs) = self.bugs {
+            builder = builder.bugs(bugs);
+        }
+        if let Some(ordered) = self.ordered {
+            builder = builder.ordered(ordered);
+        }
+        if let Some(decay) = self.decay {
+            builder = builder.decay(decay);
+        }
+        if let Some(obstacles) = self.obstacles {
+            builder = builder.obstacles(obstacles);
+        }
+        if let Some(slow_vertical) = self.slow_vertical {
+            builder = builder.slow_vertical(slow_vertical);
+        }
+        builder = builder.theme(self.theme()?);
+        let mut foods = Foods::default();
+        let weights = [
+            (Food::Cherry, self.food.cherry),
+            (Food::Banana, self.food.banana),
+            (Food::Melon, self.food.melon),
+        ];
+        for (food, weight) in weights {
+            if let Some(weight) = weight {
+                foods.rule_mut(food).weight = weight;
+            }
+        }
+        builder = builder.foods(foods);
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        }
+        builder.build()
+    }
+
+    /// What runs are graded against, the defaults for anything unset.
+    pub fn grades(&self) -> Result<Grades> {
+        let defaults = Grades::default();
+        let grades = Grades {
+            s: self.grades.s.unwrap_or(defaults.s),
+            a: self.grades.a.unwrap_or(defaults.a),
+            b: self.grades.b.unwrap_or(defaults.b),
+            c: self.grades.c.unwrap_or(defaults.c),
+        };
+        grades.validate()?;
+        Ok(grades)
+    }
+
+    /// The theme to draw with, wearing the skin if there is one.
+    pub fn theme(&self) -> Result<Theme> {
+        let theme = match &self.theme {
+            Some(name) => Theme::named(name).ok_or_else(|| {
+                RakeError::Config(format!(
+                    "unknown theme {name:?}, pick one of {}",
+                    Theme::NAMES.join(", ")
+                ))
+            })?,
+            None => Theme::default(),
+        };
+        Ok(self.restyle(theme, self.skin()?))
+    }
+
+    /// Whether to play the way an old Windows console needs, as set or as
+    /// detected.
+    pub fn legacy_console(&self) -> bool {
+        self.legacy_console
+            .unwrap_or_else(terminal::is_legacy_console)
+    }
+
+    /// `theme` wearing `skin`, then turned ASCII, monochrome or steady if
+    /// these settings ask for it.
+    pub fn restyle(&self, mut theme: Theme, skin: Option<&Skin>) -> Theme {
+        if let Some(skin) = skin {
+            theme = theme.skinned(skin);
+        }
+        if self.ascii == Some(true) {
+            theme = theme.ascii();
+        }
+        if self.color == Some(false) {
+            theme = theme.monochrome();
+        }
+        if self.reduced_motion == Some(true) {
+            theme = theme.steady();
+        }
+        theme
+    }
+
+    /// The skin to wear, if any. It has to have been unlocked.
+    pub fn skin(&self) -> Result<Option<&'static Skin>> {
+        let Some(name) = &self.skin else {
+            return Ok(None);
+        };
+        let skin = skins::named(name).ok_or_else(|| {
+            let names: Vec<&str> = skins::SKINS.iter().map(|skin| skin.name).collect();
+            RakeError::Config(format!(
+                "unknown skin {name:?}, pick one of {}",
+                names.join(", ")
+            ))
+        })?;
+        let unlocks = match Unlocks::default_path() {
+            Some(path) => Unlocks::load(&path)?,
+            None => Unlocks::default(),
+        };
+        if !unlocks.has(skin) {
+            return Err(RakeError::Config(format!(
+                "the {name} skin is still locked, {} to unlock it",
+                skin.unlock.describe(&Lang::english())
+            )));
+        }
+        Ok(Some(skin))
+    }
+
+    pub fn sound_config(&self) -> Result<SoundConfig> {
+        let volume = |name: &str, setting: Option<u8>, default: u8| match setting {
+            Some(volume) if volume > sound::MAX_VOLUME => Err(RakeError::Config(format!(
+                "{name} must be between 0 and {}, got {volume}",
+                sound::MAX_VOLUME
+            ))),
+            Some(volume) => Ok(volume),
+            None => Ok(default),
+        };
+        let defaults = SoundConfig::default();
+        Ok(SoundConfig {
+            volume: volume("volume", self.sound.volume, defaults.volume)?,
+            // some terminals flash the screen for a bell
+            bell: self
+                .sound
+                .bell
+                .unwrap_or(defaults.bell && self.reduced_motion != Some(true)),
+            music: self.sound.music.as_deref().map(Music::named),
+            music_volume: volume(
+                "music volume",
+                self.sound.music_volume,
+                defaults.music_volume,
+            )?,
+        })
+    }
+
+    /// The language to play in, English unless `lang` says otherwise.
+    pub fn lang(&self) -> Result<Arc<Lang>> {
+        match &self.lang {
+            Some(code) => Lang::load(code),
+            None => Ok(Lang::english()),
+        }
+    }
+
+    pub fn keymap(&self) -> Result<Keymap> {
+        let mut keymap = Keymap::for_layout(self.layout.unwrap_or_default());
+        let actions = [
+            (&self.keys.up, InputAction::Turn(Direction::Up)),
+            (&self.keys.down, InputAction::Turn(Direction::Down)),
+            (&self.keys.left, InputAction::Turn(Direction::Left)),
+            (&self.keys.right, InputAction::Turn(Direction::Right)),
+            (&self.keys.save, InputAction::Save),
+            (&self.keys.quit, InputAction::Quit),
+            (&self.keys.mute, InputAction::Mute),
+            (&self.keys.rewind, InputAction::Rewind),
+            (&self.keys.hint, InputAction::Hint),
+            (&self.keys.slow, InputAction::Slow),
+            (&self.keys.help, InputAction::Help),
+            (&self.keys.pause, InputAction::Pause),
+        ];
+        for (keys, action) in actions {
+            let Some(keys) = keys else {
+                continue;
+            };
+            keymap.clear(action);
+            for name in keys {
+                let key = input::parse_key(name)
+                    .ok_or_else(|| RakeError::Config(format!("unknown key {name:?}")))?;
+                keymap.bind(key, action);
+            }
+        }
+        // rebinding something else onto the only quit key would leave no
+        // way out
+        if keymap.keys_for(InputAction::Quit).next().is_none() {
+            return Err(RakeError::Config("no key is bound to quit".into()));
+        }
+        Ok(keymap)
+    }
+}
+
+/// Every setting `SettingsDocument` knows how to get and set. Any of them
+/// can also be prefixed with `profiles.<name>.` to change a profile.
+pub const KEYS: [&str; 57] = [
+    "width",
+    "height",
+    "speed",
+    "tick_ms",
+    "turn_based",
+    "theme",
+    "skin",
+    "mode",
+    "scoring",
+    "spawn_pattern",
+    "apples",
+    "slow_vertical",
+    "ascii",
+    "color",
+    "reduced_motion",
+    "screen_reader",
+    "casual",
+    "rooms",
+    "bugs",
+    "ordered",
+    "decay",
+    "obstacles",
+    "speedrun",
+    "show_speed",
+    "smooth",
+    "idle_pause",
+    "legacy_console",
+    "copy_card",
+    "seed",
+    "lang",
+    "layout",
+    "keys.up",
+    "keys.down",
+    "keys.left",
+    "keys.right",
+    "keys.save",
+    "keys.quit",
+    "keys.mute",
+    "keys.rewind",
+    "keys.hint",
+    "keys.slow",
+    "keys.help",
+    "keys.pause",
+    "food.cherry",
+    "food.banana",
+    "food.melon",
+    "grades.s",
+    "grades.a",
+    "grades.b",
+    "grades.c",
+    "leaderboard.url",
+    "leaderboard.key",
+    "discord.client_id",
+    "sound.volume",
+    "sound.bell",
+    "sound.music",
+    "sound.music_volume",
+];
+
+// always text, so an id or a secret that's all digits isn't taken for a
+// number
+const TEXT_KEYS: [&str; 2] = ["leaderboard.key", "discord.client_id"];
+
+/// A settings file opened for editing one key at a time. Comments and
+/// layout survive, unlike a round trip through `Settings`.
+pub struct SettingsDocument {
+    doc: DocumentMut,
+}
+
+impl SettingsDocument {
+    /// Opens `path`, or starts an empty document if it doesn't exist yet.
+    pub fn load(path: &Path) -> Result<SettingsDocument> {
+        match fs::read_to_string(path) {
+            Ok(text) => SettingsDocument::parse(&text)
+                .map_err(|e| RakeError::Config(format!("{}: {e}", path.display()))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => SettingsDocument::parse(""),
+            Err(e) => Err(RakeError::Config(format!(
+                "couldn't read {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<SettingsDocument> {
+        let doc = text
+            .parse::<DocumentMut>()
+            .map_err(|e| RakeError::Config(e.to_string()))?;
+        Ok(SettingsDocument { doc })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        storage::write_atomic(path, self.doc.to_string().as_bytes())
+            .map_err(|e| RakeError::Config(format!("couldn't write {}: {e}", path.display())))
+    }
+
+    /// The value of `key` as written in the file, or `None` if it isn't set.
+    /// Strings come back without their quotes.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        let path = key_path(key)?;
+        let mut item = self.doc.as_item();
+        for segment in &path {
+            match item.get(segment) {
+                Some(next) => item = next,
+                None => return Ok(None),
+            }
+        }
+        Ok(item.as_value().map(|value| match value.as_str() {
+            Some(text) => text.to_string(),
+            None => value.clone().decorated("", "").to_string(),
+        }))
+    }
+
+    /// Sets `key` to `value` and checks the whole file still makes sense,
+    /// leaving the document as it was if it doesn't.
+    ///
+    /// `value` is read as TOML if it can be (`7`, `true`, `["w", "Up"]`)
+    /// and as a plain string otherwise. Keys take a comma separated list
+    /// as well, so `w,Up` works without any quoting.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let path = key_path(key)?;
+        let parsed = value.trim().parse::<Value>().ok();
+        let value = if path
+            .iter()
+            .rev()
+            .nth(1)
+            .is_some_and(|table| table == "keys")
+        {
+            match parsed {
+                Some(array @ Value::Array(_)) => array,
+                _ => value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|key| !key.is_empty())
+                    .collect::<Array>()
+                    .into(),
+            }
+        } else if path.len() >= 2 && TEXT_KEYS.contains(&path[path.len() - 2..].join(".").as_str())
+        {
+            value.into()
+        } else {
+            parsed.unwrap_or_else(|| value.into())
+        };
+
+        let before = self.doc.clone();
+        let result = self.insert(&path, value).and_then(|()| self.check(&path));
+        if let Err(e) = result {
+            self.doc = before;
+            let reason = match e {
+                RakeError::Config(reason) => reason,
+                e => e.to_string(),
+            };
+            return Err(RakeError::Config(format!("can't set {key}: {reason}")));
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, path: &[String], value: Value) -> Result<()> {
+        let (last, tables) = path.split_last().expect("keys are never empty");
+        let mut table = self.doc.as_table_mut();
+        for segment in tables {
+            let item = table.entry(segment).or_insert_with(|| {
+                let mut table = Table::new();
+                // no empty [profiles] header when only [profiles.x] is needed
+                table.set_implicit(true);
+                Item::Table(table)
+            });
+            table = item
+                .as_table_mut()
+                .ok_or_else(|| RakeError::Config(format!("{segment} isn't a table")))?;
+        }
+        table.insert(last, Item::Value(value));
+        Ok(())
+    }
+
+    // everything a game would do with the settings, short of starting one
+    fn check(&self, path: &[String]) -> Result<()> {
+        let settings = self.settings()?;
+        let settings = match path {
+            [profiles, name, ..] if profiles == "profiles" => settings.with_profile(name)?,
+            _ => settings,
+        };
+        settings.game_config()?;
+        settings.keymap()?;
+        settings.sound_config()?;
+        settings.grades()?;
+        settings.lang()?;
+        Ok(())
+    }
+
+    pub fn settings(&self) -> Result<Settings> {
+        toml::from_str(&self.doc.to_string()).map_err(|e| RakeError::Config(e.message().into()))
+    }
+}
+
+// `speed` -> [speed], `profiles.kids.keys.up` -> [profiles, kids, keys, up]
+fn key_path(key: &str) -> Result<Vec<String>> {
+    let (prefix, setting) = match key.strip_prefix("profiles.") {
+        Some(rest) => match rest.split_once('.') {
+            Some((name, setting)) if !name.is_empty() => (vec!["profiles", name], setting),
+            _ => {
+                return Err(RakeError::Config(format!(
+                    "{key:?} needs a setting after the profile name, like profiles.{rest}.speed"
+                )));
+            }
+        },
+        None => (vec![], key),
+    };
+    if !KEYS.contains(&setting) {
+        return Err(RakeError::Config(format!(
+            "unknown setting {setting:?}, pick one of {}",
+            KEYS.join(", ")
+        )));
+    }
+    Ok(prefix
+        .into_iter()
+        .chain(setting.split('.'))
+        .map(String::from)
+        .collect())
+}
@@ -0,0 +1,138 @@
+//! Other looks for the snake, earned by playing.
+//!
+//! A skin swaps the theme's snake glyphs for its own and leaves everything
+//! else about the theme alone. Every skin but the theme's own snake starts
+//! out locked; which ones have been earned is kept in `unlocks.json` next
+//! to the high scores.
+
+use crate::Result;
+use crate::game::Game;
+use crate::lang::Lang;
+use crate::storage::{self, Versioned};
+use crate::theme::Glyph;
+use crossterm::style::Color;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// What it takes to earn a skin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unlock {
+    /// At least this many points in one game.
+    Score(u16),
+    /// Fill the board.
+    Win,
+    /// Finish this many games, however they end.
+    Games(u32),
+}
+
+impl Unlock {
+    /// What to do to earn it, in the player's language.
+    pub fn describe(self, lang: &Lang) -> String {
+        match self {
+            Unlock::Score(points) => lang.format("unlock-score", &[("points", &points)]),
+            Unlock::Win => lang.text("unlock-win").to_string(),
+            Unlock::Games(games) => lang.format("unlock-games", &[("games", &games)]),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Skin {
+    pub name: &'static str,
+    /// Drawn alternately from the head, like `Theme::snake`.
+    pub snake: [Glyph; 2],
+    pub unlock: Unlock,
+}
+
+/// Every skin there is, easiest to earn first.
+pub const SKINS: [Skin; 5] = [
+    Skin {
+        name: "mint",
+        snake: [Glyph::new('o', Color::Green), Glyph::new('o', Color::White)],
+        unlock: Unlock::Score(10),
+    },
+    Skin {
+        name: "veteran",
+        snake: [Glyph::new('=', Color::Blue), Glyph::new('=', Color::White)],
+        unlock: Unlock::Games(25),
+    },
+    Skin {
+        name: "gold",
+        snake: [
+            Glyph::new('$', Color::Yellow),
+            Glyph::new('$', Color::DarkYellow),
+        ],
+        unlock: Unlock::Score(25),
+    },
+    Skin {
+        name: "neon",
+        snake: [
+            Glyph::new('#', Color::Magenta),
+            Glyph::new('#', Color::Cyan),
+        ],
+        unlock: Unlock::Score(50),
+    },
+    Skin {
+        name: "ghost",
+        snake: [Glyph::new('░', Color::White), Glyph::new('░', Color::Grey)],
+        unlock: Unlock::Win,
+    },
+];
+
+/// The skin called `name`, if there is one.
+pub fn named(name: &str) -> Option<&'static Skin> {
+    SKINS.iter().find(|skin| skin.name == name)
+}
+
+/// The skins earned so far, and what's needed to work out the rest.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Unlocks {
+    skins: BTreeSet<String>,
+    // finished games, for `Unlock::Games`
+    games: u32,
+}
+
+impl Unlocks {
+    /// `unlocks.json` in `storage::data_dir()`.
+    pub fn default_path() -> Option<PathBuf> {
+        storage::data_dir().map(|dir| dir.join("unlocks.json"))
+    }
+
+    /// Reads `path`. A file that doesn't exist yet has nothing unlocked.
+    pub fn load(path: &Path) -> Result<Unlocks> {
+        storage::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        storage::store(path, self)
+    }
+
+    pub fn has(&self, skin: &Skin) -> bool {
+        self.skins.contains(skin.name)
+    }
+
+    /// Counts a finished game, unlocking whatever it earned. Returns the
+    /// skins that are new.
+    pub fn record<R: Rng>(&mut self, game: &Game<R>) -> Vec<&'static Skin> {
+        self.games = self.games.saturating_add(1);
+        let mut earned = vec![];
+        for skin in &SKINS {
+            let done = match skin.unlock {
+                Unlock::Score(points) => game.score() >= points,
+                Unlock::Win => game.has_won(),
+                Unlock::Games(games) => self.games >= games,
+            };
+            if done && self.skins.insert(skin.name.to_string()) {
+                earned.push(skin);
+            }
+        }
+        earned
+    }
+}
+
+impl Versioned for Unlocks {
+    const VERSION: u32 = 1;
+}
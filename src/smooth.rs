@@ -0,0 +1,62 @@
+//! Smooth movement: halfway between ticks the head pokes half a cell into
+//! where it's going next, drawn with a half block, so the snake seems to
+//! slide along instead of jumping a whole cell at a time. It wants a fast
+//! terminal and a font with block characters, so it's off unless asked
+//! for.
+
+use crate::Result;
+use crate::game::{self, Direction, Game};
+use crate::grid::Coord;
+use crate::render::Renderer;
+use crate::theme::Glyph;
+
+/// The half a head, if there is one.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Smooth {
+    // the cell it went in, to wipe if the head went elsewhere after all
+    drawn: Option<Coord>,
+}
+
+impl Smooth {
+    pub fn new() -> Smooth {
+        Smooth::default()
+    }
+
+    /// Draws the head half into the next cell it's heading for, if that's
+    /// empty, for the frame halfway to the next tick.
+    pub fn draw_between(&mut self, renderer: &mut impl Renderer, game: &Game) -> Result<()> {
+        self.clear(renderer, game)?;
+        if game.is_over() {
+            return Ok(());
+        }
+        let snake = game.snake();
+        let next = game.neighbour(snake.head(), snake.direction());
+        if !game.grid().is_empty(next) {
+            return Ok(());
+        }
+        // whichever half is nearer the head
+        let symbol = match snake.direction() {
+            Direction::Up => '▄',
+            Direction::Down => '▀',
+            Direction::Left => '▐',
+            Direction::Right => '▌',
+        };
+        let theme = &game.config().theme;
+        let glyph = Glyph::new(symbol, theme.snake[0].color);
+        game::draw_cell(renderer, theme, game.grid(), next, glyph)?;
+        self.drawn = Some(next);
+        Ok(())
+    }
+
+    /// Wipes the half a head, unless the snake's moved into its cell since,
+    /// for every frame on a tick.
+    pub fn clear(&mut self, renderer: &mut impl Renderer, game: &Game) -> Result<()> {
+        if let Some(cell) = self.drawn.take()
+            && game.grid().is_empty(cell)
+        {
+            let theme = &game.config().theme;
+            game::clear_cell(renderer, theme, game.grid(), cell)?;
+        }
+        Ok(())
+    }
+}
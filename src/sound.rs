@@ -0,0 +1,348 @@
+//! Sound effects and music.
+//!
+//! With the `sound` feature, effects are short synthesized tones played
+//! through the default output device, and there can be a track looping
+//! underneath: a little chiptune that's built in, or a file of your own.
+//! Without the feature, or with no device to play on, the terminal bell
+//! stands in for the effects worth hearing, and there's no music.
+
+use crate::Result;
+use crate::game::GameEvent;
+use crate::render::Renderer;
+use std::path::PathBuf;
+#[cfg(feature = "sound")]
+use std::time;
+
+/// The loudest `SoundConfig::volume` and `music_volume` go.
+pub const MAX_VOLUME: u8 = 10;
+
+/// What plays in the background.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Music {
+    /// The tune that comes with rake.
+    Chiptune,
+    /// Any WAV, Ogg Vorbis or MP3 file.
+    File(PathBuf),
+}
+
+impl Music {
+    /// `"chiptune"` for the built-in tune, anything else is a path.
+    pub fn named(name: &str) -> Music {
+        match name {
+            "chiptune" => Music::Chiptune,
+            path => Music::File(path.into()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SoundConfig {
+    /// How loud effects are, 0 (silent) to `MAX_VOLUME`.
+    pub volume: u8,
+    /// Whether to ring the terminal bell when there's no audio output.
+    pub bell: bool,
+    /// What loops in the background, if anything.
+    pub music: Option<Music>,
+    /// 0 (silent) to `MAX_VOLUME`.
+    pub music_volume: u8,
+}
+
+impl Default for SoundConfig {
+    fn default() -> SoundConfig {
+        SoundConfig {
+            volume: 5,
+            bell: true,
+            music: None,
+            music_volume: 5,
+        }
+    }
+}
+
+/// Something worth making a noise about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Eat,
+    Turn,
+    Death,
+    Win,
+}
+
+impl Effect {
+    /// The effect for `event`, if it has one.
+    pub fn for_event(event: &GameEvent) -> Option<Effect> {
+        match event {
+            GameEvent::AppleEaten { .. } | GameEvent::BugEaten { .. } => Some(Effect::Eat),
+            GameEvent::Turned { .. } | GameEvent::Bounced { .. } => Some(Effect::Turn),
+            GameEvent::Died { .. } => Some(Effect::Death),
+            GameEvent::Won | GameEvent::Cleared { .. } => Some(Effect::Win),
+            GameEvent::AppleSpawned { .. }
+            | GameEvent::BugSpawned { .. }
+            | GameEvent::OutOfOrder { .. }
+            | GameEvent::ObstacleSpawned { .. } => None,
+        }
+    }
+}
+
+enum Output {
+    #[cfg(feature = "sound")]
+    Speakers(rodio::MixerDeviceSink),
+    Bell,
+    Silent,
+}
+
+pub struct Sound {
+    // ahead of `output` so it stops before the device goes
+    #[cfg(feature = "sound")]
+    music: Option<rodio::Player>,
+    output: Output,
+    // 0.0 to 1.0
+    #[cfg_attr(not(feature = "sound"), allow(dead_code))]
+    volume: f32,
+    muted: bool,
+    // rung with the next frame rather than on its own, mid-draw
+    bell_due: bool,
+    // when the last effect started stops, so quitting doesn't cut it off
+    #[cfg(feature = "sound")]
+    playing_until: time::Instant,
+}
+
+fn fraction(volume: u8) -> f32 {
+    f32::from(volume.min(MAX_VOLUME)) / f32::from(MAX_VOLUME)
+}
+
+impl Sound {
+    /// Opens the default output device if there is one and starts the
+    /// music. Best done before the terminal is taken over, since audio
+    /// libraries like to complain on stderr when there's no device.
+    ///
+    /// Errors if the music is a file that can't be played. Without the
+    /// `sound` feature there's no music, and it isn't looked at.
+    pub fn new(config: &SoundConfig) -> Result<Sound> {
+        let wanted = config.volume > 0 || (config.music.is_some() && config.music_volume > 0);
+        let found = if wanted { speakers() } else { None };
+        let output = match found {
+            Some(output) => output,
+            None if config.bell && config.volume > 0 => Output::Bell,
+            None => Output::Silent,
+        };
+        #[cfg(feature = "sound")]
+        let music = match (&output, &config.music) {
+            (Output::Speakers(sink), Some(music)) if config.music_volume > 0 => {
+                let player = rodio::Player::connect_new(sink.mixer());
+                player.set_volume(fraction(config.music_volume));
+                synth::queue_music(&player, music)?;
+                Some(player)
+            }
+            _ => None,
+        };
+        Ok(Sound {
+            #[cfg(feature = "sound")]
+            music,
+            output,
+            volume: fraction(config.volume),
+            muted: false,
+            bell_due: false,
+            #[cfg(feature = "sound")]
+            playing_until: time::Instant::now(),
+        })
+    }
+
+    /// Silences everything, or brings it back.
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        #[cfg(feature = "sound")]
+        if let Some(music) = &self.music {
+            if self.muted {
+                music.pause();
+            } else {
+                music.play();
+            }
+        }
+    }
+
+    pub fn play(&mut self, effect: Effect) {
+        if self.muted || self.volume == 0.0 {
+            return;
+        }
+        match &self.output {
+            #[cfg(feature = "sound")]
+            Output::Speakers(sink) => {
+                let length = synth::play(sink.mixer(), effect, self.volume);
+                self.playing_until = self.playing_until.max(time::Instant::now() + length);
+            }
+            // a bell on every turn would be unbearable
+            Output::Bell if effect != Effect::Turn => self.bell_due = true,
+            Output::Bell | Output::Silent => {}
+        }
+    }
+
+    /// Plays whatever `events` call for, like `Game::events` after a tick.
+    pub fn react(&mut self, events: &[GameEvent]) {
+        for effect in events.iter().filter_map(Effect::for_event) {
+            self.play(effect);
+        }
+    }
+
+    /// Rings the terminal bell on `renderer` if anything played since the
+    /// last call wanted it.
+    pub fn ring(&mut self, renderer: &mut impl Renderer) -> Result<()> {
+        if std::mem::take(&mut self.bell_due) {
+            renderer.bell()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Sound {
+    fn drop(&mut self) {
+        #[cfg(feature = "sound")]
+        if let Output::Speakers(_) = self.output {
+            std::thread::sleep(
+                self.playing_until
+                    .saturating_duration_since(time::Instant::now()),
+            );
+        }
+    }
+}
+
+#[cfg(feature = "sound")]
+fn speakers() -> Option<Output> {
+    let mut sink = rodio::DeviceSinkBuilder::open_default_sink().ok()?;
+    sink.log_on_drop(false);
+    Some(Output::Speakers(sink))
+}
+
+#[cfg(not(feature = "sound"))]
+fn speakers() -> Option<Output> {
+    None
+}
+
+#[cfg(feature = "sound")]
+mod synth {
+    use super::{Effect, Music};
+    use crate::{RakeError, Result};
+    use rodio::buffer::SamplesBuffer;
+    use rodio::mixer::Mixer;
+    use rodio::source::{SawtoothWave, SineWave, SquareWave};
+    use rodio::{Decoder, Player, Source};
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::num::NonZero;
+    use std::time::Duration;
+
+    // full volume is still well short of clipping, with a few effects
+    // playing over each other
+    const LOUDEST: f32 = 0.25;
+
+    /// Starts `effect` on `mixer` and says how long it lasts.
+    pub fn play(mixer: &Mixer, effect: Effect, volume: f32) -> Duration {
+        let amplitude = volume * LOUDEST;
+        let mut start = Duration::ZERO;
+        let notes: &[(f32, u64)] = match effect {
+            // a quick rising blip
+            Effect::Eat => &[(660.0, 40), (990.0, 60)],
+            Effect::Turn => &[(440.0, 20)],
+            // three falling notes
+            Effect::Death => &[(330.0, 120), (247.0, 120), (165.0, 240)],
+            // a major arpeggio, up to the octave and held
+            Effect::Win => &[(523.0, 100), (659.0, 100), (784.0, 100), (1047.0, 400)],
+        };
+        for &(frequency, millis) in notes {
+            let length = Duration::from_millis(millis);
+            match effect {
+                Effect::Eat | Effect::Win => mixer.add(
+                    SquareWave::new(frequency)
+                        .take_duration(length)
+                        .amplify(amplitude)
+                        .delay(start),
+                ),
+                // softer than the rest since it happens so often
+                Effect::Turn => mixer.add(
+                    SineWave::new(frequency)
+                        .take_duration(length)
+                        .amplify(amplitude / 2.0)
+                        .delay(start),
+                ),
+                Effect::Death => mixer.add(
+                    SawtoothWave::new(frequency)
+                        .take_duration(length)
+                        .fade_out(length)
+                        .amplify(amplitude)
+                        .delay(start),
+                ),
+            }
+            start += length;
+        }
+        start
+    }
+
+    /// Puts `music` on `player`, looping forever.
+    pub fn queue_music(player: &Player, music: &Music) -> Result<()> {
+        match music {
+            Music::Chiptune => player.append(chiptune().repeat_infinite()),
+            Music::File(path) => {
+                let error = |e: &dyn std::fmt::Display| {
+                    RakeError::Config(format!("can't play {}: {e}", path.display()))
+                };
+                let file = File::open(path).map_err(|e| error(&e))?;
+                let looped = Decoder::new_looped(BufReader::new(file)).map_err(|e| error(&e))?;
+                player.append(looped);
+            }
+        }
+        Ok(())
+    }
+
+    const RATE: u32 = 22050;
+    // an eighth note at 150 beats a minute
+    const STEP: f32 = 0.2;
+
+    // a lead and a bass line in A minor, one note each per eighth, as MIDI
+    // note numbers with 0 for a rest
+    const LEAD: [u8; 32] = [
+        69, 0, 72, 76, 74, 72, 71, 72, //
+        69, 0, 72, 76, 79, 76, 74, 0, //
+        77, 76, 74, 72, 74, 0, 71, 67, //
+        69, 72, 71, 67, 69, 0, 0, 0,
+    ];
+    const BASS: [u8; 32] = [
+        45, 45, 52, 52, 45, 45, 52, 52, //
+        48, 48, 55, 55, 48, 48, 55, 55, //
+        50, 50, 57, 57, 43, 43, 50, 50, //
+        45, 45, 52, 52, 45, 45, 40, 40,
+    ];
+
+    // one loop of the built-in tune, two square waves like an old console
+    fn chiptune() -> SamplesBuffer {
+        let per_step = (STEP * RATE as f32) as usize;
+        let mut samples = Vec::with_capacity(per_step * LEAD.len());
+        for (&lead, &bass) in LEAD.iter().zip(&BASS) {
+            for i in 0..per_step {
+                let t = i as f32 / RATE as f32;
+                // each note fades a little as it goes, and stops just short
+                // of the next so they don't click into each other
+                let left = 1.0 - i as f32 / per_step as f32;
+                let envelope = if left < 0.05 { 0.0 } else { 0.5 + 0.5 * left };
+                let sample = 0.5 * pulse(lead, t, 0.25) + 0.4 * pulse(bass, t, 0.5);
+                samples.push(sample * envelope * LOUDEST);
+            }
+        }
+        SamplesBuffer::new(
+            NonZero::new(1).expect("one channel"),
+            NonZero::new(RATE).expect("a real sample rate"),
+            samples,
+        )
+    }
+
+    // a square wave that's high for `duty` of each cycle
+    fn pulse(note: u8, t: f32, duty: f32) -> f32 {
+        if note == 0 {
+            return 0.0;
+        }
+        let frequency = 440.0 * 2f32.powf((f32::from(note) - 69.0) / 12.0);
+        if (t * frequency).fract() < duty {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Where apples come out. A `SpawnPolicy` picks the cell for each new
+//! apple; `SpawnPattern`, from the settings or a level, is the built-in
+//! ones, and `Game::set_spawn_policy` takes any other. Rooms and corridors
+//! keep putting apples in the rooms that still want them, whatever the
+//! pattern, until none do.
+//!
+//! Every policy only looks, and draws from the game's RNG, so a seed still
+//! plays out the same way each time.
+
+use crate::config::{GameConfig, SpawnPattern, WallMode};
+use crate::grid::{Coord, Grid};
+use rand::{Rng, RngCore};
+
+// how far from the last apple a clustered one can be, in cells either way
+const CLUSTER: i16 = 3;
+// how many goes at finding a cell before giving up on a pattern
+const TRIES: usize = 16;
+
+/// What a policy gets to go on.
+pub struct Board<'a> {
+    pub config: &'a GameConfig,
+    /// What's on every cell. Only empty cells take an apple.
+    pub grid: &'a Grid,
+    /// Where the snake's head is.
+    pub head: Coord,
+    /// Where the last apple came out, if one has.
+    pub last: Option<Coord>,
+    /// How many apples have come out so far.
+    pub spawned: u32,
+}
+
+/// Picks where apples go.
+pub trait SpawnPolicy {
+    /// An empty cell for the next apple, or `None` to leave it to chance.
+    fn place(&self, board: &Board<'_>, rng: &mut dyn RngCore) -> Option<Coord>;
+}
+
+impl SpawnPolicy for SpawnPattern {
+    fn place(&self, board: &Board<'_>, rng: &mut dyn RngCore) -> Option<Coord> {
+        match self {
+            SpawnPattern::Random => board.grid.random_free(rng),
+            SpawnPattern::Clustered => clustered(board, rng),
+            SpawnPattern::Far => far(board, rng),
+            SpawnPattern::Ring => ring(board, rng),
+            SpawnPattern::Sequence => sequence(board),
+        }
+    }
+}
+
+fn clustered(board: &Board<'_>, rng: &mut dyn RngCore) -> Option<Coord> {
+    let [x, y] = board.last?;
+    (0..TRIES)
+        .map(|_| {
+            [
+                x + rng.random_range(-CLUSTER..=CLUSTER),
+                y + rng.random_range(-CLUSTER..=CLUSTER),
+            ]
+        })
+        .find(|cell| board.grid.is_empty(*cell))
+}
+
+// the farthest of a handful of free cells, which is far enough without
+// measuring every one
+fn far(board: &Board<'_>, rng: &mut dyn RngCore) -> Option<Coord> {
+    (0..TRIES)
+        .filter_map(|_| board.grid.random_free(rng))
+        .max_by_key(|cell| distance(board.config, board.head, *cell))
+}
+
+// steps apart, going across the border on a board that wraps
+fn distance(config: &GameConfig, [x1, y1]: Coord, [x2, y2]: Coord) -> u16 {
+    let (dx, dy) = (x1.abs_diff(x2), y1.abs_diff(y2));
+    match config.wall_mode {
+        WallMode::Wrap => {
+            let (width, height) = (config.width - 2, config.height - 2);
+            dx.min(width.saturating_sub(dx)) + dy.min(height.saturating_sub(dy))
+        }
+        _ => dx + dy,
+    }
+}
+
+// a free cell on the ring, going round from somewhere random on it
+fn ring(board: &Board<'_>, rng: &mut dyn RngCore) -> Option<Coord> {
+    let (width, height) = (board.config.width as i16, board.config.height as i16);
+    let inset = (width.min(height) / 4).max(1);
+    let (left, top, right, bottom) = (inset, inset, width - 1 - inset, height - 1 - inset);
+    let across = (right - left) as usize;
+    let down = (bottom - top) as usize;
+    let around = 2 * (across + down);
+    if around == 0 {
+        return None;
+    }
+    let start = rng.random_range(0..around);
+    (0..around)
+        .map(|step| {
+            // clockwise from the top left corner
+            let n = ((start + step) % around) as i16;
+            let (across, down) = (across as i16, down as i16);
+            match n {
+                n if n < across => [left + n, top],
+                n if n < across + down => [right, top + n - across],
+                n if n < 2 * across + down => [right - (n - across - down), bottom],
+                n => [left, bottom - (n - 2 * across - down)],
+            }
+        })
+        .find(|cell| board.grid.is_empty(*cell))
+}
+
+// the next of the level's cells that's free, counting on from where the
+// last one was
+fn sequence(board: &Board<'_>) -> Option<Coord> {
+    let cells = &board.config.apple_cells;
+    let start = board.spawned as usize;
+    (0..cells.len())
+        .map(|n| cells[(start + n) % cells.len()])
+        .find(|cell| board.grid.is_empty(*cell))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Tile;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn config() -> GameConfig {
+        GameConfig::builder().size(16, 12).build().unwrap()
+    }
+
+    fn board<'a>(config: &'a GameConfig, grid: &'a Grid, last: Option<Coord>) -> Board<'a> {
+        Board {
+            config,
+            grid,
+            head: [1, 1],
+            last,
+            spawned: 0,
+        }
+    }
+
+    // what `pattern` picks on `board` with each of a few seeds
+    fn picks(pattern: SpawnPattern, board: &Board<'_>) -> Vec<Option<Coord>> {
+        (0..50)
+            .map(|seed| pattern.place(board, &mut ChaCha8Rng::seed_from_u64(seed)))
+            .collect()
+    }
+
+    #[test]
+    fn random_apples_only_go_on_empty_cells() {
+        let config = config();
+        let mut grid = Grid::new(16, 12);
+        for x in 0..16 {
+            grid.set([x, 5], Tile::Wall);
+        }
+        for cell in picks(SpawnPattern::Random, &board(&config, &grid, None)) {
+            assert!(grid.is_empty(cell.unwrap()));
+        }
+    }
+
+    #[test]
+    fn clustered_apples_come_out_near_the_last_one() {
+        let (config, grid) = (config(), Grid::new(16, 12));
+        for cell in picks(
+            SpawnPattern::Clustered,
+            &board(&config, &grid, Some([8, 6])),
+        ) {
+            let [x, y] = cell.unwrap();
+            assert!(x.abs_diff(8) <= CLUSTER as u16 && y.abs_diff(6) <= CLUSTER as u16);
+        }
+        // the first one's left to chance
+        let first = picks(SpawnPattern::Clustered, &board(&config, &grid, None));
+        assert!(first.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn far_apples_pick_the_farthest_cell_they_find() {
+        let config = config();
+        let mut grid = Grid::new(16, 12);
+        for x in 0..16 {
+            for y in 0..12 {
+                grid.set([x, y], Tile::Wall);
+            }
+        }
+        grid.set([2, 1], Tile::Empty);
+        grid.set([14, 10], Tile::Empty);
+        let board = board(&config, &grid, None);
+        assert!(
+            picks(SpawnPattern::Far, &board)
+                .iter()
+                .all(|cell| *cell == Some([14, 10]))
+        );
+    }
+
+    #[test]
+    fn distance_goes_across_the_border_when_it_wraps() {
+        let mut config = config();
+        assert_eq!(distance(&config, [1, 1], [14, 10]), 22);
+        config.wall_mode = WallMode::Wrap;
+        assert_eq!(distance(&config, [1, 1], [14, 10]), 1 + 1);
+    }
+
+    #[test]
+    fn ring_apples_stay_on_the_ring() {
+        let config = config();
+        let mut grid = Grid::new(16, 12);
+        // a quarter of the short side in: columns 3 and 12, rows 3 and 8
+        let on_ring = |[x, y]: Coord| {
+            ((x == 3 || x == 12) && (3..=8).contains(&y))
+                || ((y == 3 || y == 8) && (3..=12).contains(&x))
+        };
+        for cell in picks(SpawnPattern::Ring, &board(&config, &grid, None)) {
+            assert!(on_ring(cell.unwrap()), "{cell:?} is off the ring");
+        }
+        for x in 0..16 {
+            for y in 0..12 {
+                if on_ring([x, y]) {
+                    grid.set([x, y], Tile::Snake);
+                }
+            }
+        }
+        let full = picks(SpawnPattern::Ring, &board(&config, &grid, None));
+        assert!(full.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn a_sequence_goes_on_from_the_last_apple_and_skips_taken_cells() {
+        let mut config = config();
+        config.apple_cells = vec![[2, 2], [4, 4], [6, 6]];
+        let grid = Grid::new(16, 12);
+        let mut taken = Grid::new(16, 12);
+        taken.set([2, 2], Tile::Snake);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut next = |grid: &Grid, spawned| {
+            let board = Board {
+                spawned,
+                ..board(&config, grid, None)
+            };
+            SpawnPattern::Sequence.place(&board, &mut rng)
+        };
+        assert_eq!(next(&grid, 0), Some([2, 2]));
+        assert_eq!(next(&grid, 2), Some([6, 6]));
+        // round to the start again
+        assert_eq!(next(&grid, 3), Some([2, 2]));
+        assert_eq!(next(&taken, 3), Some([4, 4]));
+    }
+}
@@ -0,0 +1,516 @@
+//! Playing over SSH.
+//!
+//! `serve` accepts any user with any password or key (or none) and gives
+//! every session its own game, drawn with the same escape codes a local
+//! terminal gets. Games run on plain threads like they do at home; the
+//! SSH side is async, so the two meet over channels: keystrokes come in
+//! through a `std::sync::mpsc` channel and frames go out through tokio's.
+//!
+//! Since anyone can get in, it only listens on this machine unless told
+//! otherwise, and only runs `MAX_SESSIONS` games at once.
+
+use crate::config::GameConfig;
+use crate::game::Game;
+use crate::help;
+use crate::input::{self, InputAction, Keymap};
+use crate::lang::Lang;
+use crate::pacing::Pacer;
+use crate::render::{self, Renderer, TerminalRenderer};
+use crate::storage;
+use crate::{RakeError, Result};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{QueueableCommand, cursor};
+use rand::Rng;
+use russh::keys::ssh_key::LineEnding;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::{PrivateKey, PublicKey};
+use russh::server::{Auth, ChannelOpenHandle, Handle, Msg, Server, Session};
+use russh::{Channel, ChannelId};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time;
+use tokio::sync::mpsc::{self as tokio_mpsc, Receiver, Sender};
+
+/// The port `rake ssh-serve` listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 2222;
+
+/// The address `rake ssh-serve` listens on unless told otherwise, which
+/// only lets in people on this machine.
+pub const DEFAULT_BIND: &str = "127.0.0.1";
+
+/// How many games run at once. Anyone else who connects is told the
+/// server's full.
+pub const MAX_SESSIONS: usize = 16;
+
+// how many frames can be waiting for a slow connection before its game
+// waits for them to go
+const FRAMES: usize = 64;
+
+fn connection_error(e: impl std::fmt::Display) -> RakeError {
+    RakeError::Connection(e.to_string())
+}
+
+/// Serves a game of `config`, played with `keymap` and written in `lang`,
+/// to everyone who connects to `bind` on `port`, until the process is
+/// stopped. Each game gets a seed of its own unless `config` fixes one.
+pub fn serve(
+    bind: &str,
+    port: u16,
+    config: GameConfig,
+    keymap: Keymap,
+    lang: Arc<Lang>,
+) -> Result<()> {
+    let server_config = russh::server::Config {
+        keys: vec![host_key()?],
+        inactivity_timeout: Some(time::Duration::from_secs(600)),
+        auth_rejection_time: time::Duration::from_secs(1),
+        auth_rejection_time_initial: Some(time::Duration::ZERO),
+        nodelay: true,
+        ..Default::default()
+    };
+    let runtime = tokio::runtime::Runtime::new()?;
+    let mut server = Arcade {
+        config,
+        keymap,
+        lang,
+        sessions: Arc::new(AtomicUsize::new(0)),
+    };
+    runtime
+        .block_on(server.run_on_address(Arc::new(server_config), (bind, port)))
+        .map_err(connection_error)
+}
+
+// kept from one run to the next so clients don't think they've been had
+// every time the server restarts
+fn host_key() -> Result<PrivateKey> {
+    let Some(dir) = storage::data_dir() else {
+        return Ok(new_key());
+    };
+    let path = dir.join("ssh_host_ed25519_key");
+    if let Some(text) = storage::read_optional(&path)? {
+        return PrivateKey::from_openssh(text)
+            .map_err(|e| RakeError::Config(format!("couldn't read {}: {e}", path.display())));
+    }
+    let key = new_key();
+    let text = key.to_openssh(LineEnding::LF).map_err(connection_error)?;
+    storage::write_atomic(&path, text.as_bytes())?;
+    keep_private(&path)?;
+    Ok(key)
+}
+
+fn new_key() -> PrivateKey {
+    let seed: [u8; 32] = rand::rng().random();
+    PrivateKey::from(Ed25519Keypair::from_seed(&seed))
+}
+
+#[cfg(unix)]
+fn keep_private(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn keep_private(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+struct Arcade {
+    config: GameConfig,
+    keymap: Keymap,
+    lang: Arc<Lang>,
+    // how many games are going
+    sessions: Arc<AtomicUsize>,
+}
+
+impl Server for Arcade {
+    type Handler = Visitor;
+
+    fn new_client(&mut self, _: Option<SocketAddr>) -> Visitor {
+        Visitor {
+            config: self.config.clone(),
+            keymap: self.keymap.clone(),
+            lang: self.lang.clone(),
+            sessions: self.sessions.clone(),
+            size: None,
+            keys: None,
+        }
+    }
+}
+
+// one of the `MAX_SESSIONS` games, given back when it's dropped
+struct Seat(Arc<AtomicUsize>);
+
+impl Seat {
+    fn take(sessions: &Arc<AtomicUsize>) -> Option<Seat> {
+        sessions
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |taken| {
+                (taken < MAX_SESSIONS).then_some(taken + 1)
+            })
+            .ok()
+            .map(|_| Seat(sessions.clone()))
+    }
+}
+
+impl Drop for Seat {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// one per connection
+struct Visitor {
+    config: GameConfig,
+    keymap: Keymap,
+    lang: Arc<Lang>,
+    sessions: Arc<AtomicUsize>,
+    // the client's terminal, in columns and rows, if it asked for one
+    size: Option<(u16, u16)>,
+    // where keystrokes go once the game's started
+    keys: Option<mpsc::Sender<Event>>,
+}
+
+impl russh::server::Handler for Visitor {
+    type Error = russh::Error;
+
+    async fn auth_none(&mut self, _: &str) -> std::result::Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn auth_password(&mut self, _: &str, _: &str) -> std::result::Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn auth_publickey(
+        &mut self,
+        _: &str,
+        _: &PublicKey,
+    ) -> std::result::Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _: Channel<Msg>,
+        reply: ChannelOpenHandle,
+        _: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        reply.accept().await;
+        Ok(())
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _: &str,
+        columns: u32,
+        rows: u32,
+        _: u32,
+        _: u32,
+        _: &[(russh::Pty, u32)],
+        session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        self.size = Some(terminal_size(columns, rows));
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn window_change_request(
+        &mut self,
+        channel: ChannelId,
+        columns: u32,
+        rows: u32,
+        _: u32,
+        _: u32,
+        session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        let (columns, rows) = terminal_size(columns, rows);
+        self.size = Some((columns, rows));
+        if let Some(keys) = &self.keys {
+            // the game's over and gone if this fails
+            let _ = keys.send(Event::Resize(columns, rows));
+        }
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        if self.keys.is_some() {
+            return Ok(());
+        }
+        let (frames, outbox) = tokio_mpsc::channel(FRAMES);
+        tokio::spawn(forward(session.handle(), channel, outbox));
+        let Some(seat) = Seat::take(&self.sessions) else {
+            let full = format!("{}\r\n", self.lang.text("ssh-full"));
+            // it's going either way
+            let _ = frames.send(Frame::Data(full.into_bytes())).await;
+            let _ = frames.send(Frame::Close).await;
+            return Ok(());
+        };
+        let (keys, events) = mpsc::channel();
+        self.keys = Some(keys);
+
+        let mut config = self.config.clone();
+        config.seed = config.seed.or_else(|| Some(rand::rng().random()));
+        let keymap = self.keymap.clone();
+        let lang = self.lang.clone();
+        let size = self.size;
+        thread::spawn(move || {
+            let mut out = ChannelWriter {
+                frames,
+                buffer: vec![],
+            };
+            let _ = play(config, &keymap, lang, &events, &mut out, size);
+            let _ = out.frames.blocking_send(Frame::Close);
+            drop(seat);
+        });
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        _: ChannelId,
+        data: &[u8],
+        _: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        if let Some(keys) = &self.keys {
+            for event in input::decode(data) {
+                // the game's over and gone if this fails
+                let _ = keys.send(event);
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Frame {
+    Data(Vec<u8>),
+    Close,
+}
+
+// hands whatever the game draws to the SSH session
+async fn forward(handle: Handle, channel: ChannelId, mut outbox: Receiver<Frame>) {
+    while let Some(frame) = outbox.recv().await {
+        match frame {
+            Frame::Data(bytes) => {
+                if handle.data(channel, bytes).await.is_err() {
+                    break;
+                }
+            }
+            Frame::Close => break,
+        }
+    }
+    let _ = handle.exit_status_request(channel, 0).await;
+    let _ = handle.close(channel).await;
+}
+
+// collects escape codes until a flush, then sends them as one frame
+struct ChannelWriter {
+    frames: Sender<Frame>,
+    buffer: Vec<u8>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let frame = Frame::Data(std::mem::take(&mut self.buffer));
+        self.frames
+            .blocking_send(frame)
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+// a game much like the one at home, minus saving and high scores
+fn play(
+    config: GameConfig,
+    keymap: &Keymap,
+    lang: Arc<Lang>,
+    events: &mpsc::Receiver<Event>,
+    out: &mut ChannelWriter,
+    mut size: Option<(u16, u16)>,
+) -> Result<()> {
+    out.queue(cursor::Hide)?;
+    let mut renderer = TerminalRenderer::new(&mut *out);
+    let mut game = Game::new(config);
+    game.set_help(keymap.help(&lang));
+    game.set_lang(lang.clone());
+    if !wait_for_room(&mut renderer, &game, keymap, &lang, events, &mut size)? {
+        return Ok(());
+    }
+    renderer.clear()?;
+    game.draw_border(&mut renderer)?;
+    game.draw(&mut renderer)?;
+    renderer.present()?;
+
+    let mut pacer = Pacer::new(game.tick_rate());
+    'game: while !game.is_over() {
+        while let Some(wait) = pacer.remaining() {
+            match events.recv_timeout(wait) {
+                // there's no terminal of ours to raise SIGINT, so Ctrl-C
+                // has to be caught here
+                Ok(Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                })) => break 'game,
+                Ok(Event::Resize(columns, rows)) => {
+                    size = Some((columns, rows));
+                    if !wait_for_room(&mut renderer, &game, keymap, &lang, events, &mut size)? {
+                        break 'game;
+                    }
+                    let mut frame = render::Frame::new(&mut renderer);
+                    frame.clear()?;
+                    game.draw_border(&mut frame)?;
+                    game.draw(&mut frame)?;
+                    frame.finish()?;
+                }
+                Ok(event) => match keymap.action(&event) {
+                    Some(InputAction::Turn(direction)) => game.turn(direction),
+                    Some(InputAction::Quit) => break 'game,
+                    Some(InputAction::Help) => {
+                        let lines = help::lines(keymap, game.config(), &[], &lang);
+                        let mut frame = render::Frame::new(&mut renderer);
+                        help::draw(&mut frame, &game, &lines)?;
+                        frame.finish()?;
+                        // the game waits, and starts again a whole tick away
+                        while let Ok(event) = events.recv() {
+                            if matches!(event, Event::Key(key) if key.kind == KeyEventKind::Press) {
+                                break;
+                            }
+                        }
+                        let mut frame = render::Frame::new(&mut renderer);
+                        frame.clear()?;
+                        game.draw_border(&mut frame)?;
+                        game.draw(&mut frame)?;
+                        frame.finish()?;
+                        pacer = Pacer::new(game.tick_rate());
+                    }
+                    Some(InputAction::Pause) => {
+                        let [line, short] = ["idle-paused", "idle-paused-short"]
+                            .map(|key| lang.text(key).to_string());
+                        game.set_help(vec![line, short]);
+                        let mut frame = render::Frame::new(&mut renderer);
+                        game.draw(&mut frame)?;
+                        frame.finish()?;
+                        while let Ok(event) = events.recv() {
+                            match keymap.action(&event) {
+                                Some(InputAction::Quit) => break 'game,
+                                Some(InputAction::Turn(direction)) => {
+                                    game.turn(direction);
+                                    break;
+                                }
+                                _ if matches!(event, Event::Key(key) if key.kind == KeyEventKind::Press) =>
+                                {
+                                    break;
+                                }
+                                _ => {}
+                            }
+                        }
+                        game.set_help(keymap.help(&lang));
+                        pacer = Pacer::new(game.tick_rate());
+                    }
+                    _ => {}
+                },
+                Err(RecvTimeoutError::Disconnected) => break 'game,
+                Err(RecvTimeoutError::Timeout) => break,
+            }
+        }
+        pacer.advance(game.tick_rate());
+        game.tick(None);
+        let mut frame = render::Frame::new(&mut renderer);
+        game.draw(&mut frame)?;
+        frame.finish()?;
+    }
+
+    out.queue(cursor::MoveTo(0, game.height() + 1))?
+        .queue(cursor::Show)?;
+    let outcome = if game.has_won() {
+        "game-won"
+    } else {
+        "game-over"
+    };
+    let outcome = lang.format(outcome, &[("score", &game.score())]);
+    if let Some(cause) = game.death() {
+        write!(out, "{}\r\n", lang.text(&format!("death-{}", cause.name())))?;
+    }
+    write!(out, "{outcome}\r\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+// what a pty or window change says, capped to what a terminal can be
+fn terminal_size(columns: u32, rows: u32) -> (u16, u16) {
+    let cap = |n: u32| u16::try_from(n).unwrap_or(u16::MAX);
+    (cap(columns), cap(rows))
+}
+
+// the enlarge message, like at home, until the client's terminal has room
+// for the board and the HUD. a client that never said how big it is gets
+// the board whatever. `false` if they quit or left first
+fn wait_for_room(
+    renderer: &mut impl Renderer,
+    game: &Game,
+    keymap: &Keymap,
+    lang: &Lang,
+    events: &mpsc::Receiver<Event>,
+    size: &mut Option<(u16, u16)>,
+) -> Result<bool> {
+    let needed = (game.width(), game.height() + 1);
+    while let Some((columns, rows)) = *size
+        && (columns < needed.0 || rows < needed.1)
+    {
+        let message = lang.format(
+            "enlarge",
+            &[
+                ("width", &needed.0),
+                ("height", &needed.1),
+                ("columns", &columns),
+                ("rows", &rows),
+            ],
+        );
+        renderer.clear()?;
+        for (y, line) in render::wrap(&message, columns.max(1) as usize)
+            .iter()
+            .enumerate()
+        {
+            renderer.draw_text(0, y as u16, line, game.config().theme.text)?;
+        }
+        renderer.present()?;
+        // anything but a resize or quitting is ignored
+        loop {
+            match events.recv() {
+                Ok(Event::Resize(columns, rows)) => {
+                    *size = Some((columns, rows));
+                    break;
+                }
+                Ok(Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                })) => return Ok(false),
+                Ok(event) if keymap.action(&event) == Some(InputAction::Quit) => {
+                    return Ok(false);
+                }
+                Ok(_) => {}
+                Err(_) => return Ok(false),
+            }
+        }
+    }
+    Ok(true)
+}
@@ -0,0 +1,219 @@
+//! How rake keeps its own files: high scores, death counts, saved games.
+//!
+//! Every write goes to a temporary file in the same directory first and is
+//! renamed over the real one once it's safely on disk, so a crash or a
+//! full disk halfway through leaves the old file intact rather than half
+//! of a new one.
+//!
+//! JSON files are wrapped as `{"version": N, "data": …}`. Bumping a type's
+//! `Versioned::VERSION` and teaching `migrate` about the old shape keeps
+//! existing files loading.
+
+use crate::{RakeError, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Where rake keeps data files: `$XDG_DATA_HOME/rake` if that's set,
+/// otherwise under the platform's data directory (`~/.local/share` on
+/// Linux, `~/Library/Application Support` on macOS, `%APPDATA%` on
+/// Windows).
+pub fn data_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(dirs::data_dir)?;
+    Some(base.join("rake"))
+}
+
+/// Replaces `path` with `contents` all at once, creating its directory if
+/// needed.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+    let name = path.file_name().ok_or(io::ErrorKind::InvalidInput)?;
+    // the pid keeps two rakes saving at once from sharing a temp file
+    let mut temp_name = name.to_os_string();
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp = dir.join(temp_name);
+
+    let result = (|| {
+        let mut file = File::create(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// The contents of `path`, or `None` if there's no such file.
+pub fn read_optional(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Something stored as versioned JSON.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// The version being written now. Start at 1.
+    const VERSION: u32;
+
+    /// Turns `data` as written by `version` into what `VERSION` expects.
+    /// Version 0 is anything from before files were versioned at all.
+    ///
+    /// The default only accepts the current version.
+    fn migrate(version: u32, data: Value) -> std::result::Result<Value, String> {
+        if version == Self::VERSION {
+            Ok(data)
+        } else {
+            Err(format!("don't know how to read version {version}"))
+        }
+    }
+}
+
+/// Reads a `T` from `path`, migrating it if it's old. A missing file gives
+/// `T::default()`.
+pub fn load<T: Versioned + Default>(path: &Path) -> Result<T> {
+    let error = |message: String| RakeError::Save {
+        path: path.into(),
+        source: io::Error::new(io::ErrorKind::InvalidData, message),
+    };
+    let Some(text) = read_optional(path).map_err(|source| RakeError::Save {
+        path: path.into(),
+        source,
+    })?
+    else {
+        return Ok(T::default());
+    };
+
+    let value: Value = serde_json::from_str(&text).map_err(|e| error(e.to_string()))?;
+    let (version, data) = match value {
+        Value::Object(mut map) if map.len() == 2 && map.contains_key("data") => {
+            let version = map
+                .get("version")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| error("version isn't a number".into()))?;
+            (version as u32, map.remove("data").unwrap_or_default())
+        }
+        unversioned => (0, unversioned),
+    };
+    if version > T::VERSION {
+        return Err(error(format!(
+            "written by a newer rake (version {version}, this one reads up to {})",
+            T::VERSION
+        )));
+    }
+    let data = T::migrate(version, data).map_err(error)?;
+    serde_json::from_value(data).map_err(|e| error(e.to_string()))
+}
+
+/// Writes `value` to `path` atomically, stamped with `T::VERSION`.
+pub fn store<T: Versioned>(path: &Path, value: &T) -> Result<()> {
+    #[derive(Serialize)]
+    struct Envelope<'a, T> {
+        version: u32,
+        data: &'a T,
+    }
+    let json = serde_json::to_string(&Envelope {
+        version: T::VERSION,
+        data: value,
+    })
+    .map_err(|e| RakeError::Save {
+        path: path.into(),
+        source: e.into(),
+    })?;
+    write_atomic(path, json.as_bytes()).map_err(|source| RakeError::Save {
+        path: path.into(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    // was a bare number before there were versions, then `{"n": …}`
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        count: u32,
+    }
+
+    impl Versioned for Counter {
+        const VERSION: u32 = 2;
+
+        fn migrate(version: u32, data: Value) -> std::result::Result<Value, String> {
+            match version {
+                0 => Ok(serde_json::json!({ "count": data })),
+                1 => Ok(serde_json::json!({ "count": data["n"] })),
+                2 => Ok(data),
+                _ => Err(format!("don't know how to read version {version}")),
+            }
+        }
+    }
+
+    // a file of its own in the temp directory, with `contents` if given
+    fn file(name: &str, contents: Option<&str>) -> PathBuf {
+        let path = env::temp_dir().join(format!("rake-storage-{}-{name}.json", process::id()));
+        let _ = fs::remove_file(&path);
+        if let Some(contents) = contents {
+            fs::write(&path, contents).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn a_missing_file_is_the_default() {
+        assert_eq!(
+            load::<Counter>(&file("missing", None)).unwrap(),
+            Counter::default()
+        );
+    }
+
+    #[test]
+    fn what_goes_in_comes_back_out() {
+        let path = file("round-trip", None);
+        store(&path, &Counter { count: 7 }).unwrap();
+        assert!(
+            fs::read_to_string(&path)
+                .unwrap()
+                .contains(r#""version":2"#)
+        );
+        assert_eq!(load::<Counter>(&path).unwrap(), Counter { count: 7 });
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn old_files_are_migrated() {
+        let unversioned = file("unversioned", Some("3"));
+        assert_eq!(load::<Counter>(&unversioned).unwrap(), Counter { count: 3 });
+        let first = file("first", Some(r#"{"version": 1, "data": {"n": 4}}"#));
+        assert_eq!(load::<Counter>(&first).unwrap(), Counter { count: 4 });
+        fs::remove_file(unversioned).unwrap();
+        fs::remove_file(first).unwrap();
+    }
+
+    #[test]
+    fn newer_or_broken_files_are_errors() {
+        let newer = file("newer", Some(r#"{"version": 3, "data": {"count": 1}}"#));
+        let error = load::<Counter>(&newer).unwrap_err().to_string();
+        assert!(error.contains("newer rake"), "{error}");
+        let broken = file("broken", Some(r#"{"version": "two", "data": {}}"#));
+        assert!(load::<Counter>(&broken).is_err());
+        fs::remove_file(newer).unwrap();
+        fs::remove_file(broken).unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+use crossterm::{
+    ExecutableCommand, cursor,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::io;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// set while we're on the alternate screen, so `restore` knows whether
+// there's anything to leave
+static ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// Puts the terminal into game mode (raw, cursor hidden, optionally on the
+/// alternate screen) and puts it back when dropped, whichever way the
+/// program leaves the scope.
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    pub fn enter(alternate_screen: bool) -> io::Result<TerminalGuard> {
+        install_panic_hook();
+        take_over(alternate_screen)?;
+        Ok(TerminalGuard { _private: () })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore();
+    }
+}
+
+/// Undoes everything `TerminalGuard::enter` did. Safe to call more than
+/// once.
+pub fn restore() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+        stdout.execute(LeaveAlternateScreen)?;
+    }
+    stdout.execute(cursor::Show)?;
+    terminal::disable_raw_mode()
+}
+
+fn take_over(alternate_screen: bool) -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    if alternate_screen {
+        stdout.execute(EnterAlternateScreen)?;
+        ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
+    }
+    stdout.execute(cursor::Hide)?;
+    Ok(())
+}
+
+/// Does what Ctrl+Z would if raw mode hadn't turned it into a key press:
+/// hands the terminal back to the shell and stops, then takes it over
+/// again once resumed with `fg`. Whatever was on screen is gone by then,
+/// so everything needs redrawing. Does nothing where there's no job
+/// control.
+pub fn suspend() -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let alternate_screen = ALTERNATE_SCREEN.load(Ordering::SeqCst);
+        restore()?;
+        // SAFETY: raising a signal on ourselves has no memory safety
+        // concerns. With the default handler this returns after SIGCONT
+        unsafe {
+            libc::raise(libc::SIGTSTP);
+        }
+        take_over(alternate_screen)?;
+    }
+    Ok(())
+}
+
+/// Whether this is an old Windows console that doesn't understand escape
+/// codes, conhost before Windows 10 or with them turned off. crossterm
+/// talks to those through the console API instead, which works but is
+/// slow, so the game draws as little as it can. Never anywhere else.
+pub fn is_legacy_console() -> bool {
+    #[cfg(windows)]
+    {
+        !crossterm::ansi_support::supports_ansi()
+    }
+    #[cfg(not(windows))]
+    {
+        false
+    }
+}
+
+// restores the terminal before the default hook prints the panic message,
+// otherwise it comes out staircased in raw mode and the shell is left
+// unusable
+fn install_panic_hook() {
+    static INSTALLED: AtomicBool = AtomicBool::new(false);
+    if INSTALLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        previous(info);
+    }));
+}
@@ -0,0 +1,276 @@
+use crate::config::Food;
+use crate::grid::Ground;
+use crate::skins::Skin;
+use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
+
+/// A symbol and the color to draw it in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Glyph {
+    pub symbol: char,
+    pub color: Color,
+}
+
+impl Glyph {
+    pub const fn new(symbol: char, color: Color) -> Glyph {
+        Glyph { symbol, color }
+    }
+}
+
+/// How everything on the board looks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Theme {
+    pub wall: Glyph,
+    // the snake alternates between these, starting from the head
+    pub snake: [Glyph; 2],
+    // cherries, the plain apples every theme started out with
+    pub apple: Glyph,
+    #[serde(default = "default_banana")]
+    pub banana: Glyph,
+    #[serde(default = "default_melon")]
+    pub melon: Glyph,
+    pub text: Color,
+    // ice and mud: the symbol fills empty cells and the color goes behind
+    // everything, snake and apples included. older saves have neither
+    #[serde(default = "default_ice")]
+    pub ice: Glyph,
+    #[serde(default = "default_mud")]
+    pub mud: Glyph,
+    #[serde(default = "default_portal")]
+    pub portal: Glyph,
+    // the bonus bug's two cells, top or left first
+    #[serde(default = "default_bug")]
+    pub bug: [Glyph; 2],
+    // the snake where it's crossed over itself, in casual games
+    #[serde(default = "default_crossing")]
+    pub crossing: Color,
+    // the cells the tail's just left, fading out a tick at a time
+    #[serde(default = "default_trail")]
+    pub trail: [Glyph; 3],
+    // for reduced motion: no banners coming and going
+    #[serde(default)]
+    pub steady: bool,
+}
+
+impl Theme {
+    /// Everything `Theme::named` knows about.
+    pub const NAMES: [&str; 3] = ["classic", "ocean", "ember"];
+
+    pub fn named(name: &str) -> Option<Theme> {
+        match name {
+            "classic" => Some(Theme::classic()),
+            "ocean" => Some(Theme::ocean()),
+            "ember" => Some(Theme::ember()),
+            _ => None,
+        }
+    }
+
+    pub fn classic() -> Theme {
+        Theme {
+            wall: Glyph::new('█', Color::Magenta),
+            snake: [Glyph::new('$', Color::Green), Glyph::new('$', Color::Cyan)],
+            apple: Glyph::new('@', Color::Red),
+            text: Color::Reset,
+            banana: default_banana(),
+            melon: default_melon(),
+            ice: default_ice(),
+            mud: default_mud(),
+            portal: default_portal(),
+            bug: default_bug(),
+            crossing: default_crossing(),
+            trail: default_trail(),
+            steady: false,
+        }
+    }
+
+    pub fn ocean() -> Theme {
+        Theme {
+            wall: Glyph::new('█', Color::DarkBlue),
+            snake: [Glyph::new('o', Color::Cyan), Glyph::new('o', Color::White)],
+            apple: Glyph::new('*', Color::Yellow),
+            text: Color::Cyan,
+            banana: default_banana(),
+            melon: default_melon(),
+            ice: Glyph::new(' ', Color::DarkGrey),
+            mud: default_mud(),
+            portal: default_portal(),
+            bug: default_bug(),
+            crossing: default_crossing(),
+            trail: default_trail(),
+            steady: false,
+        }
+    }
+
+    pub fn ember() -> Theme {
+        Theme {
+            wall: Glyph::new('▓', Color::DarkRed),
+            snake: [
+                Glyph::new('#', Color::Yellow),
+                Glyph::new('#', Color::DarkYellow),
+            ],
+            apple: Glyph::new('@', Color::Green),
+            text: Color::Reset,
+            banana: default_banana(),
+            melon: default_melon(),
+            ice: default_ice(),
+            mud: Glyph::new(' ', Color::DarkGrey),
+            portal: default_portal(),
+            bug: default_bug(),
+            crossing: default_crossing(),
+            trail: default_trail(),
+            steady: false,
+        }
+    }
+
+    /// How `food` looks.
+    pub fn food(&self, food: Food) -> Glyph {
+        match food {
+            Food::Cherry => self.apple,
+            Food::Banana => self.banana,
+            Food::Melon => self.melon,
+        }
+    }
+
+    /// How `ground` looks, or `None` for plain ground, which is just the
+    /// terminal's own background.
+    pub fn ground(&self, ground: Ground) -> Option<Glyph> {
+        match ground {
+            Ground::Plain => None,
+            Ground::Ice => Some(self.ice),
+            Ground::Mud => Some(self.mud),
+        }
+    }
+
+    /// The same theme with the snake wearing `skin`.
+    pub fn skinned(mut self, skin: &Skin) -> Theme {
+        self.snake = skin.snake;
+        self
+    }
+
+    /// The same theme with every symbol swapped for something plain ASCII,
+    /// for terminals and fonts that can't draw block characters.
+    pub fn ascii(mut self) -> Theme {
+        for glyph in [
+            &mut self.wall,
+            &mut self.apple,
+            &mut self.banana,
+            &mut self.melon,
+            &mut self.ice,
+            &mut self.mud,
+            &mut self.portal,
+        ]
+        .into_iter()
+        .chain(self.snake.iter_mut())
+        .chain(self.bug.iter_mut())
+        {
+            if !glyph.symbol.is_ascii() {
+                glyph.symbol = '#';
+            }
+        }
+        self
+    }
+
+    /// The same theme with nothing changing on screen that doesn't have
+    /// to, for anyone bothered by motion: the snake is one color all along,
+    /// so its stripes don't crawl as it moves, there's no trail behind it,
+    /// and the HUD doesn't put up banners.
+    pub fn steady(mut self) -> Theme {
+        self.snake[1] = self.snake[0];
+        self.steady = true;
+        self
+    }
+}
+
+// stand-ins when two things would look the same without color
+const SHAPES: [char; 7] = ['#', 'o', '@', '+', '*', '%', '='];
+
+impl Theme {
+    /// The same theme in the terminal's default colors. Walls, the snake
+    /// and each food are given different symbols if they shared one, since
+    /// shape is all that's left to tell them apart.
+    pub fn monochrome(mut self) -> Theme {
+        let mut used: Vec<char> = vec![];
+        for glyph in [
+            &mut self.wall,
+            &mut self.snake[0],
+            &mut self.apple,
+            &mut self.banana,
+            &mut self.melon,
+            &mut self.portal,
+        ] {
+            if used.contains(&glyph.symbol) {
+                // SHAPES has more entries than there are glyphs
+                glyph.symbol = SHAPES.into_iter().find(|c| !used.contains(c)).unwrap();
+            }
+            used.push(glyph.symbol);
+        }
+        self.snake[1].symbol = self.snake[0].symbol;
+        // no tint to show where they are, so they're dotted instead
+        self.ice.symbol = '.';
+        self.mud.symbol = ',';
+        for glyph in [
+            &mut self.wall,
+            &mut self.apple,
+            &mut self.banana,
+            &mut self.melon,
+            &mut self.ice,
+            &mut self.mud,
+            &mut self.portal,
+        ]
+        .into_iter()
+        .chain(self.snake.iter_mut())
+        .chain(self.bug.iter_mut())
+        .chain(self.trail.iter_mut())
+        {
+            glyph.color = Color::Reset;
+        }
+        self.crossing = Color::Reset;
+        self.text = Color::Reset;
+        self
+    }
+}
+
+fn default_banana() -> Glyph {
+    Glyph::new(')', Color::Yellow)
+}
+
+fn default_melon() -> Glyph {
+    Glyph::new('O', Color::DarkGreen)
+}
+
+fn default_crossing() -> Color {
+    Color::DarkGrey
+}
+
+fn default_trail() -> [Glyph; 3] {
+    [
+        Glyph::new(':', Color::Grey),
+        Glyph::new('.', Color::Grey),
+        Glyph::new('.', Color::DarkGrey),
+    ]
+}
+
+fn default_portal() -> Glyph {
+    Glyph::new('O', Color::Blue)
+}
+
+fn default_bug() -> [Glyph; 2] {
+    [
+        Glyph::new('{', Color::DarkMagenta),
+        Glyph::new('}', Color::DarkMagenta),
+    ]
+}
+
+fn default_ice() -> Glyph {
+    Glyph::new(' ', Color::DarkCyan)
+}
+
+fn default_mud() -> Glyph {
+    Glyph::new(' ', Color::DarkYellow)
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::classic()
+    }
+}
@@ -0,0 +1,91 @@
+//! Bots against each other, headlessly, over the same seeded games.
+//!
+//! Every entrant plays every game on its own board, so nobody gets in
+//! anyone else's way; what's compared is how they do from the same start.
+
+use crate::bot::{self, Controller};
+use crate::config::GameConfig;
+use crate::game::Game;
+
+pub struct Entrant {
+    pub name: String,
+    controller: Box<dyn Controller>,
+}
+
+impl Entrant {
+    pub fn new(name: &str, controller: Box<dyn Controller>) -> Entrant {
+        Entrant {
+            name: name.into(),
+            controller,
+        }
+    }
+}
+
+/// How one entrant did over the whole tournament.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Standing {
+    pub name: String,
+    pub games: u64,
+    pub total: u64,
+    pub best: u16,
+    /// Games it scored more in than anybody else.
+    pub wins: u64,
+    /// Games that ended in a crash rather than running out of time.
+    pub deaths: u64,
+}
+
+impl Standing {
+    pub fn average(&self) -> f64 {
+        self.total as f64 / self.games.max(1) as f64
+    }
+}
+
+/// Plays `games` games per entrant on boards like `config`. Game `n` uses
+/// seed `seed + n` for everyone, and stops after `max_ticks` if the snake
+/// is still going. Best total first, then most wins.
+pub fn run(
+    entrants: &mut [Entrant],
+    config: &GameConfig,
+    games: u64,
+    seed: u64,
+    max_ticks: u64,
+) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = entrants
+        .iter()
+        .map(|entrant| Standing {
+            name: entrant.name.clone(),
+            ..Standing::default()
+        })
+        .collect();
+
+    let mut scores = vec![0; entrants.len()];
+    for n in 0..games {
+        let config = GameConfig {
+            seed: Some(seed + n),
+            ..config.clone()
+        };
+        for (i, entrant) in entrants.iter_mut().enumerate() {
+            let mut game = Game::new(config.clone());
+            while game.ticks() < max_ticks && !game.is_over() {
+                bot::step(&mut game, entrant.controller.as_mut());
+            }
+            let standing = &mut standings[i];
+            standing.games += 1;
+            standing.total += game.score() as u64;
+            standing.best = standing.best.max(game.score());
+            if game.is_over() && !game.has_won() {
+                standing.deaths += 1;
+            }
+            scores[i] = game.score();
+        }
+        let top = scores.iter().copied().max().unwrap_or(0);
+        if scores.iter().filter(|score| **score == top).count() == 1
+            && let Some(winner) = scores.iter().position(|score| *score == top)
+        {
+            standings[winner].wins += 1;
+        }
+    }
+
+    standings.sort_by(|a, b| b.total.cmp(&a.total).then(b.wins.cmp(&a.wins)));
+    standings
+}
@@ -0,0 +1,103 @@
+//! A first game with someone looking over your shoulder.
+//!
+//! `Tutorial` walks through the controls a step at a time: a turn each
+//! way, an apple, then a while without hitting anything. It only watches
+//! the game and says what to do next; running the game is up to whoever
+//! drives it, which is `rake tutorial`.
+
+use crate::game::{Direction, Game, GameEvent};
+use crate::input::{self, InputAction, Keymap};
+use crate::lang::Lang;
+use rand::Rng;
+
+/// Something the player has to do before moving on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    Turn(Direction),
+    Eat,
+    /// Keep going for a while without crashing.
+    AvoidWalls,
+}
+
+// each turn a quarter of the way round from the last, starting from the
+// way the snake spawns heading, so none of them is a reversal
+const STEPS: [Step; 6] = [
+    Step::Turn(Direction::Up),
+    Step::Turn(Direction::Left),
+    Step::Turn(Direction::Down),
+    Step::Turn(Direction::Right),
+    Step::Eat,
+    Step::AvoidWalls,
+];
+
+// how many moves `AvoidWalls` lasts
+const AVOID_TICKS: u64 = 40;
+
+#[derive(Clone, Debug, Default)]
+pub struct Tutorial {
+    step: usize,
+    // the tick the current step started on
+    since: u64,
+}
+
+impl Tutorial {
+    pub fn new() -> Tutorial {
+        Tutorial::default()
+    }
+
+    /// What's being asked for, or `None` once it's all done.
+    pub fn step(&self) -> Option<Step> {
+        STEPS.get(self.step).copied()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.step().is_none()
+    }
+
+    /// Starts the current step over, for when the snake crashed and there's
+    /// a fresh game.
+    pub fn retry(&mut self) {
+        self.since = 0;
+    }
+
+    /// Checks the latest tick against the current step, moving on if it's
+    /// been done. Returns whether it moved on.
+    pub fn observe<R: Rng>(&mut self, game: &Game<R>) -> bool {
+        let done = match self.step() {
+            Some(Step::Turn(direction)) => game.events().contains(&GameEvent::Turned { direction }),
+            Some(Step::Eat) => game
+                .events()
+                .iter()
+                .any(|event| matches!(event, GameEvent::AppleEaten { .. })),
+            Some(Step::AvoidWalls) => game.ticks() - self.since >= AVOID_TICKS,
+            None => false,
+        };
+        if done {
+            self.step += 1;
+            self.since = game.ticks();
+        }
+        done
+    }
+
+    /// What to tell the player to do, with their own keys.
+    pub fn prompt(&self, keymap: &Keymap, lang: &Lang) -> String {
+        match self.step() {
+            Some(Step::Turn(direction)) => {
+                let name = lang.text(match direction {
+                    Direction::Up => "direction-up",
+                    Direction::Down => "direction-down",
+                    Direction::Left => "direction-left",
+                    Direction::Right => "direction-right",
+                });
+                let key = keymap
+                    .keys_for(InputAction::Turn(direction))
+                    .next()
+                    .map_or_else(|| "?".to_string(), input::key_name);
+                lang.format("tutorial-turn", &[("key", &key), ("direction", &name)])
+            }
+            Some(Step::Eat) => lang.text("tutorial-eat").to_string(),
+            Some(Step::AvoidWalls) => lang.text("tutorial-walls").to_string(),
+            None => lang.text("tutorial-done").to_string(),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Chat plays rake: a Twitch channel's chat steering the snake by vote,
+//! with `--twitch CHANNEL`.
+//!
+//! It joins the channel anonymously over Twitch's IRC gateway, so there's
+//! nothing to log in to, and reads chat for `w`, `a`, `s` and `d` (or `up`,
+//! `left`, `down` and `right`). Every chatter gets one vote a tick, the
+//! last one they sent, and the snake goes whichever way got the most.
+//! Chat lags a few seconds behind the stream, so it plays best slowed
+//! right down with `--tick-ms`.
+
+use crate::bot::Controller;
+use crate::game::{Direction, Game};
+use crate::{RakeError, Result};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Twitch's IRC gateway, without TLS.
+pub const ADDRESS: &str = "irc.chat.twitch.tv:6667";
+
+/// Votes coming in from a channel's chat.
+pub struct TwitchChat {
+    votes: Receiver<(String, Direction)>,
+    // each chatter's latest vote since the last tick
+    ballot: BTreeMap<String, Direction>,
+}
+
+impl TwitchChat {
+    /// Joins `channel`, with or without its `#`.
+    pub fn connect(channel: &str) -> Result<TwitchChat> {
+        TwitchChat::connect_to(ADDRESS, channel)
+    }
+
+    /// Like `connect`, but to any IRC server, say a local one for testing.
+    pub fn connect_to(addr: impl ToSocketAddrs, channel: &str) -> Result<TwitchChat> {
+        let channel = channel.trim_start_matches('#').to_lowercase();
+        if channel.is_empty() {
+            return Err(RakeError::Config("no Twitch channel to join".into()));
+        }
+        let mut stream = TcpStream::connect(addr).map_err(connection_error)?;
+        let reader = stream.try_clone().map_err(connection_error)?;
+        // `justinfan` and any number is Twitch's way in for reading only
+        let nick = format!("justinfan{}", rand::random_range(10_000..100_000));
+        let hello = format!("NICK {nick}\r\nJOIN #{channel}\r\n");
+        stream
+            .write_all(hello.as_bytes())
+            .map_err(connection_error)?;
+
+        let (sender, votes) = mpsc::channel();
+        // the channel disconnects when chat does, and then there are just
+        // no more votes
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let line = line.trim_end();
+                // going quiet on these gets the connection dropped
+                if let Some(server) = line.strip_prefix("PING ") {
+                    let pong = format!("PONG {server}\r\n");
+                    if stream.write_all(pong.as_bytes()).is_err() {
+                        break;
+                    }
+                } else if let Some(vote) = vote(line)
+                    && sender.send(vote).is_err()
+                {
+                    break;
+                }
+            }
+        });
+        Ok(TwitchChat {
+            votes,
+            ballot: BTreeMap::new(),
+        })
+    }
+}
+
+impl Controller for TwitchChat {
+    /// Counts the votes since the last tick and starts a fresh count. A
+    /// tie at the top, or no votes at all, carries on straight, and votes
+    /// to double back don't count since the snake can't.
+    fn decide(&mut self, game: &Game) -> Option<Direction> {
+        while let Ok((chatter, direction)) = self.votes.try_recv() {
+            self.ballot.insert(chatter, direction);
+        }
+        let backwards = game.snake().direction().opposite();
+        let counts = Direction::ALL.map(|direction| {
+            let votes = self.ballot.values().filter(|vote| **vote == direction);
+            (direction, votes.count())
+        });
+        self.ballot.clear();
+        let most = counts
+            .iter()
+            .filter(|(direction, _)| *direction != backwards)
+            .map(|(_, count)| *count)
+            .max()
+            .filter(|most| *most > 0)?;
+        let mut winners = counts
+            .iter()
+            .filter(|(direction, count)| *direction != backwards && *count == most);
+        match (winners.next(), winners.next()) {
+            (Some((direction, _)), None) => Some(*direction),
+            _ => None,
+        }
+    }
+}
+
+/// Who voted which way in a line of IRC, if it's a chat message that's a
+/// vote, like `:name!name@name.tmi.twitch.tv PRIVMSG #channel :w`.
+pub fn vote(line: &str) -> Option<(String, Direction)> {
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let (_channel, text) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+    let chatter = prefix.split('!').next()?;
+    let direction = match text.trim().to_lowercase().as_str() {
+        "w" | "up" => Direction::Up,
+        "a" | "left" => Direction::Left,
+        "s" | "down" => Direction::Down,
+        "d" | "right" => Direction::Right,
+        _ => return None,
+    };
+    Some((chatter.to_string(), direction))
+}
+
+fn connection_error(e: impl std::fmt::Display) -> RakeError {
+    RakeError::Connection(e.to_string())
+}
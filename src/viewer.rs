@@ -0,0 +1,222 @@
+//! `rake replay`'s controls: pausing, stepping a tick at a time either
+//! way, playing slower or faster, and jumping straight to an apple, the
+//! death or anywhere else in the run.
+//!
+//! Under the board's HUD there's a timeline of the whole run, with `*`
+//! where apples were eaten, `x` where it died and `|` where it's up to.
+
+use crate::Result;
+use crate::game::{Game, GameEvent};
+use crate::lang::Lang;
+use crate::render::Renderer;
+use crate::replay::Playback;
+use crate::results::RunSummary;
+use crossterm::style::Color;
+use std::sync::Arc;
+use std::time;
+
+/// How many rows the viewer takes up under the HUD.
+pub const STATUS_LINES: u16 = 4;
+
+/// How fast it can play, as many times the speed the run went at.
+pub const SPEEDS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+
+// where it starts off, at the speed the run went at
+const NORMAL: usize = 1;
+
+/// A run being watched.
+pub struct Viewer {
+    playback: Playback,
+    lang: Arc<Lang>,
+    // the ticks an apple was eaten on
+    apples: Vec<u64>,
+    died: bool,
+    paused: bool,
+    speed: usize,
+    // for the help line, whatever the quit key is
+    quit: String,
+    // set by jumps, which leave the last frame's cells all over the place
+    jumped: bool,
+}
+
+impl Viewer {
+    /// Watches `playback`, which is `run` being played back, from the
+    /// start. `quit` is what the help calls the key that quits.
+    pub fn new(run: &RunSummary, playback: Playback, lang: Arc<Lang>, quit: String) -> Viewer {
+        Viewer {
+            playback,
+            lang,
+            apples: run.apples.iter().map(|apple| apple.tick).collect(),
+            died: run.death.is_some(),
+            paused: false,
+            speed: NORMAL,
+            quit,
+            jumped: false,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        self.playback.game()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Pauses, or plays on. Playing on from the end starts over.
+    pub fn toggle_pause(&mut self) -> Result<()> {
+        if self.paused && self.playback.is_done() {
+            self.seek(0)?;
+        }
+        self.paused = !self.paused;
+        Ok(())
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    /// How long until the next tick, at the speed it's playing at.
+    pub fn tick_rate(&self) -> time::Duration {
+        self.game().tick_rate().div_f64(SPEEDS[self.speed])
+    }
+
+    /// Plays a tick, and pauses once the run's over.
+    pub fn tick(&mut self) {
+        self.playback.tick();
+        self.check_stage();
+        if self.playback.is_done() {
+            self.paused = true;
+        }
+    }
+
+    /// Pauses and moves on a tick.
+    pub fn step_forward(&mut self) {
+        self.paused = true;
+        self.playback.tick();
+        self.check_stage();
+    }
+
+    /// Pauses and goes back a tick.
+    pub fn step_back(&mut self) -> Result<()> {
+        self.paused = true;
+        self.seek(self.game().ticks().saturating_sub(1))
+    }
+
+    /// Jumps to the next apple, or the death after the last one.
+    pub fn next_event(&mut self) -> Result<()> {
+        let at = self.game().ticks();
+        let next = self.events().find(|tick| *tick > at);
+        match next {
+            Some(tick) => self.seek(tick),
+            None => Ok(()),
+        }
+    }
+
+    /// Jumps back to the apple before, or the start before the first.
+    pub fn previous_event(&mut self) -> Result<()> {
+        let at = self.game().ticks();
+        let tick = self.events().filter(|tick| *tick < at).last();
+        self.seek(tick.unwrap_or(0))
+    }
+
+    /// Jumps `tenths` of the way through the run.
+    pub fn jump(&mut self, tenths: u8) -> Result<()> {
+        self.seek(self.playback.end() * u64::from(tenths.min(10)) / 10)
+    }
+
+    /// Draws the board, HUD and all, and the status lines under it as
+    /// wide as `columns`.
+    pub fn draw(&mut self, renderer: &mut impl Renderer, columns: u16) -> Result<()> {
+        let game = self.playback.game();
+        if self.jumped {
+            renderer.clear()?;
+            game.draw_border(renderer)?;
+            self.jumped = false;
+        }
+        game.draw(renderer)?;
+
+        let theme = &game.config().theme;
+        let (width, y) = (game.width(), game.height() + 1);
+        let end = self.playback.end().max(1);
+        let column = |tick: u64| (tick * u64::from(width) / end).min(u64::from(width) - 1) as u16;
+        let here = column(game.ticks());
+        for x in 0..width {
+            let (symbol, color) = if x == here {
+                ('|', theme.text)
+            } else if self.died && x == column(end) {
+                ('x', theme.text)
+            } else if self.apples.iter().any(|tick| column(*tick) == x) {
+                ('*', theme.apple.color)
+            } else if x < here {
+                ('=', theme.text)
+            } else {
+                ('-', Color::DarkGrey)
+            };
+            renderer.draw_cell(x, y, symbol, color)?;
+        }
+        for x in width..columns {
+            renderer.clear_cell(x, y)?;
+        }
+
+        let speed = SPEEDS[self.speed];
+        let mut status = self.lang.format(
+            "replay-at",
+            &[
+                ("tick", &game.ticks()),
+                ("end", &self.playback.end()),
+                ("speed", &speed),
+            ],
+        );
+        let state = match (self.playback.is_done(), self.paused) {
+            (true, _) => Some("replay-ended"),
+            (false, true) => Some("replay-paused"),
+            (false, false) => None,
+        };
+        if let Some(state) = state {
+            status.push_str("  ");
+            status.push_str(self.lang.text(state));
+        }
+        let seek = self
+            .lang
+            .format("replay-help-seek", &[("quit", &self.quit)]);
+        let lines = [status.as_str(), self.lang.text("replay-help-play"), &seek];
+        for (n, line) in lines.into_iter().enumerate() {
+            // padded out to blank whatever was there before
+            let line: String = line
+                .chars()
+                .chain(std::iter::repeat(' '))
+                .take(columns as usize)
+                .collect();
+            renderer.draw_text(0, y + 1 + n as u16, &line, theme.text)?;
+        }
+        Ok(())
+    }
+
+    // every apple, then the death if there was one
+    fn events(&self) -> impl Iterator<Item = u64> + '_ {
+        let death = self.died.then(|| self.playback.end());
+        self.apples.iter().copied().chain(death)
+    }
+
+    // a new stage of rooms needs its walls drawing, like after a jump
+    fn check_stage(&mut self) {
+        let events = self.playback.game().events();
+        if events
+            .iter()
+            .any(|event| matches!(event, GameEvent::Cleared { .. }))
+        {
+            self.jumped = true;
+        }
+    }
+
+    fn seek(&mut self, ticks: u64) -> Result<()> {
+        self.playback.seek(ticks)?;
+        self.jumped = true;
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! Watching from a browser.
+//!
+//! `bridge` follows a game the way `rake watch` does and passes everything
+//! the host says on to any number of WebSocket clients, so a web page can
+//! show it without knowing anything about rake's own protocol. The game
+//! itself still only ever runs on the host.
+//!
+//! Every WebSocket message is a text frame holding one JSON object, the
+//! same `ServerMessage` the host sends, tagged by `"type"`:
+//!
+//! - `{"type": "lobby", "players": 1, "needed": 2}` while the host waits
+//!   for everyone to turn up
+//! - `{"type": "state", "view": {...}}` after every tick, the whole board:
+//!   `width` and `height` (border included), `wall_mode` (`"wrap"` or
+//!   `"fatal"`), `tick`, `apples` as `[x, y]` cells, and `players`, each
+//!   with a `name`, a `body` of `[x, y]` cells head first, a `score`, and
+//!   whether it's `alive` or has `left`; and `planes`, the same board as
+//!   numbers (see `planes::Planes`): `width`, `height`, and `data` with a
+//!   plane each for walls, bodies, heads and apples, one after the other
+//! - `{"type": "over", "winner": 0}` when a round ends, `winner` being an
+//!   index into `players`, or `null` for a draw
+//!
+//! A browser that connects partway gets the latest of those straight
+//! away, so it never has to wait a tick to have something to draw.
+//! Anything a browser sends is ignored. `web/watch.html` is a page that
+//! draws the lot.
+
+use crate::net::{Client, ServerMessage};
+use crate::planes::Planes;
+use crate::{RakeError, Result};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time;
+use tungstenite::{Message, WebSocket};
+
+/// The port `rake bridge` listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 8080;
+
+// a browser that can't keep up with this is dropped rather than allowed
+// to hold up the others
+const WRITE_TIMEOUT: time::Duration = time::Duration::from_secs(2);
+
+/// Passes what `client` hears on to browsers connecting to `listener`,
+/// until the game's over or the host goes away.
+pub fn bridge(client: Client, listener: TcpListener) -> Result<()> {
+    let (joined, browsers) = mpsc::channel();
+    thread::spawn(move || accept(listener, joined));
+
+    let mut sockets: Vec<WebSocket<TcpStream>> = vec![];
+    let mut last: Option<String> = None;
+    loop {
+        for mut socket in browsers.try_iter() {
+            if let Some(text) = &last
+                && socket.send(Message::text(text.as_str())).is_err()
+            {
+                continue;
+            }
+            sockets.push(socket);
+        }
+        let message = match client
+            .messages()
+            .recv_timeout(time::Duration::from_millis(20))
+        {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => {
+                close(sockets);
+                return Err(RakeError::Connection(
+                    "lost the connection to the host".into(),
+                ));
+            }
+        };
+        let mut json = serde_json::to_value(&message).expect("messages serialize");
+        if let ServerMessage::State { view } = &message {
+            json["planes"] = serde_json::to_value(Planes::of_view(view)).expect("planes serialize");
+        }
+        let text = json.to_string();
+        sockets.retain_mut(|socket| socket.send(Message::text(text.as_str())).is_ok());
+        if let ServerMessage::Over { .. } = message {
+            close(sockets);
+            return Ok(());
+        }
+        last = Some(text);
+    }
+}
+
+fn close(sockets: Vec<WebSocket<TcpStream>>) {
+    for mut socket in sockets {
+        let _ = socket.close(None);
+        let _ = socket.flush();
+    }
+}
+
+fn accept(listener: TcpListener, joined: Sender<WebSocket<TcpStream>>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let _ = stream.set_nodelay(true);
+        let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+        // nor one that never finishes its handshake
+        let _ = stream.set_read_timeout(Some(WRITE_TIMEOUT));
+        let joined = joined.clone();
+        // handshakes happen off to the side so one slow browser doesn't
+        // keep the next waiting
+        thread::spawn(move || {
+            if let Ok(socket) = tungstenite::accept(stream) {
+                let _ = joined.send(socket);
+            }
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use crate::Result;
+use crate::config::GameConfig;
+use crate::game::{Game, TickResult};
+use crate::input::{InputAction, Keymap};
+use crate::render::{Renderer, Viewport};
+use crossterm::event::Event;
+use std::time;
+
+// if the host stops calling `update` for a while, don't fast-forward
+// through more than this many ticks at once
+const MAX_CATCH_UP: u32 = 5;
+
+/// A self-contained game for embedding in another terminal app.
+///
+/// The host owns the event loop: it passes key events to `handle_event`,
+/// calls `update` as often as it likes, and calls `render` with whatever
+/// `Renderer` backs its screen (wrap a ratatui buffer or similar in one).
+/// Nothing here touches the real terminal.
+pub struct RakeWidget {
+    config: GameConfig,
+    game: Game,
+    keymap: Keymap,
+    next_tick: Option<time::Instant>,
+}
+
+impl RakeWidget {
+    pub fn new(config: GameConfig) -> RakeWidget {
+        let game = Game::new(config.clone());
+        RakeWidget {
+            config,
+            game,
+            keymap: Keymap::default(),
+            next_tick: None,
+        }
+    }
+
+    /// Uses `keymap` instead of the default WASD controls.
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The space `render` draws into, as `(width, height)`: the board plus
+    /// one line for the score.
+    pub fn size(&self) -> (u16, u16) {
+        (self.game.width(), self.game.height() + 1)
+    }
+
+    /// Starts over with a fresh board.
+    pub fn restart(&mut self) {
+        self.game = Game::new(self.config.clone());
+        self.next_tick = None;
+    }
+
+    /// Steers the snake if `event` is one of the game's keys. Returns
+    /// whether the event was used, so the host can handle the rest.
+    /// Quitting is left to the host.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match self.keymap.action(event) {
+            Some(InputAction::Turn(direction)) => {
+                self.game.turn(direction);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs every tick that has come due by `now`. The first call only
+    /// starts the clock.
+    pub fn update(&mut self, now: time::Instant) -> TickResult {
+        let mut result = TickResult::default();
+        let tick_rate = self.game.tick_rate();
+        let Some(mut next_tick) = self.next_tick else {
+            self.next_tick = Some(now + tick_rate);
+            return result;
+        };
+
+        let mut ticks = 0;
+        while next_tick <= now && ticks < MAX_CATCH_UP && !self.game.is_over() {
+            let tick = self.game.tick(None);
+            result.moved |= tick.moved;
+            result.ate |= tick.ate;
+            result.died |= tick.died;
+            result.won |= tick.won;
+            next_tick += tick_rate;
+            ticks += 1;
+        }
+        if next_tick <= now {
+            next_tick = now + tick_rate;
+        }
+        self.next_tick = Some(next_tick);
+        result
+    }
+
+    /// Draws the whole widget with its top left corner at `x`, `y`. This
+    /// is a full redraw every time, so it suits hosts that rebuild their
+    /// screen each frame. Doesn't present.
+    pub fn render<R: Renderer + ?Sized>(&self, renderer: &mut R, x: u16, y: u16) -> Result<()> {
+        let (width, height) = self.size();
+        let mut viewport = Viewport::new(renderer, x, y, width, height);
+        viewport.clear()?;
+        self.game.draw_border(&mut viewport)?;
+        self.game.draw(&mut viewport)?;
+        Ok(())
+    }
+}